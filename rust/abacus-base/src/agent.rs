@@ -24,6 +24,19 @@ pub struct InboxContracts {
     pub validator_manager: Arc<InboxValidatorManagers>,
 }
 
+/// Contracts relating to an origin chain
+#[derive(Clone, Debug)]
+pub struct OriginContracts {
+    /// A boxed Outbox
+    pub outbox: Arc<CachingOutbox>,
+    /// A boxed InterchainGasPaymaster
+    pub interchain_gas_paymaster: Option<Arc<CachingInterchainGasPaymaster>>,
+    /// A map of boxed Inbox contracts receiving messages from the Outbox
+    pub inboxes: HashMap<String, InboxContracts>,
+    /// The height at which to start indexing the Outbox
+    pub indexer: IndexSettings,
+}
+
 /// Properties shared across all abacus agents
 #[derive(Debug)]
 pub struct AbacusAgentCore {
@@ -33,6 +46,9 @@ pub struct AbacusAgentCore {
     pub interchain_gas_paymaster: Option<Arc<CachingInterchainGasPaymaster>>,
     /// A map of boxed Inbox contracts
     pub inboxes: HashMap<String, InboxContracts>,
+    /// Contracts of the origins served in addition to the Outbox's, keyed by outbox chain
+    /// name. Empty unless the settings declare `origins`.
+    pub additional_origins: HashMap<String, OriginContracts>,
     /// A persistent KV Store (currently implemented as rocksdb)
     pub db: DB,
     /// Prometheus metrics
@@ -87,6 +103,20 @@ pub trait Agent: Send + Sync + Debug + AsRef<AbacusAgentCore> {
         self.inboxes().get(name).map(Clone::clone)
     }
 
+    /// Get the contracts of every origin served: the Outbox's, followed by any additional
+    /// origins
+    fn origins(&self) -> Vec<OriginContracts> {
+        let core = self.as_ref();
+        std::iter::once(OriginContracts {
+            outbox: core.outbox.clone(),
+            interchain_gas_paymaster: core.interchain_gas_paymaster.clone(),
+            inboxes: core.inboxes.clone(),
+            indexer: core.indexer.clone(),
+        })
+        .chain(core.additional_origins.values().cloned())
+        .collect()
+    }
+
     /// Run tasks
     #[allow(clippy::unit_arg, unused_must_use)]
     fn run_all(
@@ -1,4 +1,4 @@
-use abacus_core::InterchainGasPaymasterIndexer;
+use abacus_core::{chain_from_domain, CommittedMessage, InterchainGasPaymasterIndexer};
 
 use tokio::{task::JoinHandle, time::sleep};
 use tracing::{debug, info, info_span, instrument::Instrumented, Instrument};
@@ -31,6 +31,9 @@ where
             .stored_events
             .with_label_values(&[GAS_PAYMENTS_LABEL, &self.chain_name]);
 
+        let chain_name = self.chain_name.clone();
+        let gas_received = self.metrics.interchain_gas_received.clone();
+
         let config_from = self.index_settings.from();
         let chunk_size = self.index_settings.chunk_size();
 
@@ -73,7 +76,20 @@ where
                 );
 
                 for gas_payment in gas_payments.iter() {
-                    db.process_gas_payment(gas_payment)?;
+                    if !db.process_gas_payment(gas_payment)? {
+                        continue;
+                    }
+                    // Payments are usually made alongside the dispatch, so the message has
+                    // likely been indexed already
+                    let dst = db
+                        .message_by_leaf_index(gas_payment.payment.leaf_index)?
+                        .and_then(|raw_msg| CommittedMessage::try_from(raw_msg).ok())
+                        .and_then(|msg| chain_from_domain(msg.message.destination))
+                        .unwrap_or("unknown");
+                    let amount = gas_payment.payment.amount.to_f64_lossy();
+                    gas_received
+                        .with_label_values(&[&chain_name, dst])
+                        .inc_by(amount);
                 }
 
                 stored_messages.add(gas_payments.len().try_into()?);
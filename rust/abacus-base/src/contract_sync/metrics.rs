@@ -1,5 +1,5 @@
 use crate::CoreMetrics;
-use prometheus::{IntCounterVec, IntGaugeVec};
+use prometheus::{CounterVec, IntCounterVec, IntGaugeVec};
 use std::sync::Arc;
 
 /// Struct encapsulating prometheus metrics used by the ContractSync.
@@ -30,6 +30,9 @@ pub struct ContractSyncMetrics {
 
     /// See `last_known_message_leaf_index` in CoreMetrics.
     pub message_leaf_index: IntGaugeVec,
+
+    /// See `interchain_gas_received` in CoreMetrics.
+    pub interchain_gas_received: CounterVec,
 }
 
 impl ContractSyncMetrics {
@@ -60,12 +63,14 @@ impl ContractSyncMetrics {
             .expect("failed to register missed_events metric");
 
         let message_leaf_index = metrics.last_known_message_leaf_index();
+        let interchain_gas_received = metrics.interchain_gas_received();
 
         ContractSyncMetrics {
             indexed_height,
             stored_events,
             missed_events,
             message_leaf_index,
+            interchain_gas_received,
         }
     }
 }
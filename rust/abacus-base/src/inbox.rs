@@ -49,6 +49,16 @@ impl Inbox for CachingInbox {
     async fn message_status(&self, leaf: H256) -> Result<MessageStatus, ChainCommunicationError> {
         self.inbox.message_status(leaf).await
     }
+
+    async fn finalized_message_status(
+        &self,
+        leaf: H256,
+        finality_blocks: u32,
+    ) -> Result<MessageStatus, ChainCommunicationError> {
+        self.inbox
+            .finalized_message_status(leaf, finality_blocks)
+            .await
+    }
 }
 
 impl AbacusContract for CachingInbox {
@@ -158,6 +168,26 @@ impl Inbox for InboxVariants {
             InboxVariants::Other(inbox) => inbox.message_status(leaf).await,
         }
     }
+
+    async fn finalized_message_status(
+        &self,
+        leaf: H256,
+        finality_blocks: u32,
+    ) -> Result<MessageStatus, ChainCommunicationError> {
+        match self {
+            InboxVariants::Ethereum(inbox) => {
+                inbox.finalized_message_status(leaf, finality_blocks).await
+            }
+            InboxVariants::Mock(mock_inbox) => {
+                mock_inbox
+                    .finalized_message_status(leaf, finality_blocks)
+                    .await
+            }
+            InboxVariants::Other(inbox) => {
+                inbox.finalized_message_status(leaf, finality_blocks).await
+            }
+        }
+    }
 }
 
 impl AbacusContract for InboxVariants {
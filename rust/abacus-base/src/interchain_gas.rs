@@ -1,8 +1,9 @@
 use abacus_core::db::AbacusDB;
-use abacus_core::{AbacusContract, InterchainGasPaymaster};
+use abacus_core::{AbacusContract, ChainCommunicationError, InterchainGasPaymaster, TxOutcome};
 
 use abacus_ethereum::EthereumInterchainGasPaymaster;
 use async_trait::async_trait;
+use ethers::core::types::{H256, U256};
 use eyre::Result;
 use futures_util::future::select_all;
 use std::sync::Arc;
@@ -144,4 +145,30 @@ impl AbacusContract for InterchainGasPaymasterVariants {
 }
 
 #[async_trait]
-impl InterchainGasPaymaster for InterchainGasPaymasterVariants {}
+impl InterchainGasPaymaster for InterchainGasPaymasterVariants {
+    async fn pay_gas_for(
+        &self,
+        outbox: H256,
+        leaf_index: u32,
+        destination_domain: u32,
+        amount: U256,
+    ) -> Result<TxOutcome, ChainCommunicationError> {
+        match self {
+            InterchainGasPaymasterVariants::Ethereum(paymaster) => {
+                paymaster
+                    .pay_gas_for(outbox, leaf_index, destination_domain, amount)
+                    .await
+            }
+            InterchainGasPaymasterVariants::Mock(paymaster) => {
+                paymaster
+                    .pay_gas_for(outbox, leaf_index, destination_domain, amount)
+                    .await
+            }
+            InterchainGasPaymasterVariants::Other(paymaster) => {
+                paymaster
+                    .pay_gas_for(outbox, leaf_index, destination_domain, amount)
+                    .await
+            }
+        }
+    }
+}
@@ -43,9 +43,17 @@ pub struct CoreMetrics {
     span_events: IntCounterVec,
     last_known_message_leaf_index: IntGaugeVec,
     submitter_queue_length: IntGaugeVec,
+    submitter_sender_queue_length: IntGaugeVec,
     submitter_queue_duration_histogram: HistogramVec,
 
     messages_processed_count: IntCounterVec,
+    message_reverts_count: IntCounterVec,
+
+    relay_gas_used: IntCounterVec,
+    relay_cost: CounterVec,
+    relay_delivery_cost_histogram: HistogramVec,
+    interchain_gas_received: CounterVec,
+    relay_net_margin: GaugeVec,
 
     outbox_state: IntGaugeVec,
     latest_checkpoint: IntGaugeVec,
@@ -116,6 +124,16 @@ impl CoreMetrics {
             registry
         )?;
 
+        let submitter_sender_queue_length = register_int_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("submitter_sender_queue_length"),
+                "Submitter queue length per message sender",
+                const_labels_ref
+            ),
+            &["origin", "remote", "queue_name", "sender"],
+            registry
+        )?;
+
         let submitter_queue_duration_histogram = register_histogram_vec_with_registry!(
             histogram_opts!(
                 namespaced!("submitter_queue_duration_seconds"),
@@ -161,6 +179,73 @@ impl CoreMetrics {
             registry
         )?;
 
+        let message_reverts_count = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("message_reverts_count"),
+                "Number of failed attempts to process messages that reverted, by reason",
+                const_labels_ref
+            ),
+            &["origin", "remote", "reason", "permanent"],
+            registry
+        )?;
+
+        let relay_gas_used = register_int_counter_vec_with_registry!(
+            opts!(
+                namespaced!("relay_gas_used_total"),
+                "Gas used by transactions delivering messages, including reverted ones",
+                const_labels_ref
+            ),
+            &["origin", "remote"],
+            registry
+        )?;
+
+        let relay_cost = register_counter_vec_with_registry!(
+            opts!(
+                namespaced!("relay_cost_wei_total"),
+                concat!(
+                    "Cost of transactions delivering messages, including reverted ones, in ",
+                    "remote native token wei"
+                ),
+                const_labels_ref
+            ),
+            &["origin", "remote"],
+            registry
+        )?;
+
+        let relay_delivery_cost_histogram = register_histogram_vec_with_registry!(
+            histogram_opts!(
+                namespaced!("relay_delivery_cost_wei"),
+                concat!(
+                    "Total cost of delivering a message, across all attempts, in remote ",
+                    "native token wei"
+                ),
+                prometheus::exponential_buckets(1e12, 4., 16).unwrap(),
+                const_labels.clone()
+            ),
+            &["origin", "remote"],
+            registry
+        )?;
+
+        let interchain_gas_received = register_counter_vec_with_registry!(
+            opts!(
+                namespaced!("interchain_gas_received_wei_total"),
+                "Interchain gas payments received, in origin native token wei",
+                const_labels_ref
+            ),
+            &["origin", "remote"],
+            registry
+        )?;
+
+        let relay_net_margin = register_gauge_vec_with_registry!(
+            opts!(
+                namespaced!("relay_net_margin_wei"),
+                "Interchain gas payments for delivered messages less the cost of delivering messages, in origin native token wei",
+                const_labels_ref
+            ),
+            &["origin", "remote"],
+            registry
+        )?;
+
         Ok(Self {
             agent_name: for_agent.into(),
             registry,
@@ -172,9 +257,17 @@ impl CoreMetrics {
             last_known_message_leaf_index,
 
             submitter_queue_length,
+            submitter_sender_queue_length,
             submitter_queue_duration_histogram,
 
             messages_processed_count,
+            message_reverts_count,
+
+            relay_gas_used,
+            relay_cost,
+            relay_delivery_cost_histogram,
+            interchain_gas_received,
+            relay_net_margin,
 
             outbox_state,
             latest_checkpoint,
@@ -286,6 +379,10 @@ impl CoreMetrics {
     ///   has gotten to but not attempted to send it.
     /// - `message_processed`: When a leaf index was processed as part of the
     ///   MessageProcessor loop.
+    /// - `validator_observed`: Highest leaf index of the outbox's latest
+    ///   checkpoint observed by a validator, behind its reorg period.
+    /// - `validator_processed`: Highest leaf index of a checkpoint which a
+    ///   validator has signed and written to its checkpoint syncer.
     pub fn last_known_message_leaf_index(&self) -> IntGaugeVec {
         self.last_known_message_leaf_index.clone()
     }
@@ -320,6 +417,18 @@ impl CoreMetrics {
         self.submitter_queue_length.clone()
     }
 
+    /// Gauge for reporting the number of messages from each sender in a
+    /// submitter's queues. Senders with no queued messages are not reported.
+    ///
+    /// Labels:
+    /// - `origin`: Origin chain the queue is for.
+    /// - `remote`: Remote chain the queue is for.
+    /// - `queue_name`: Which queue the messages are in.
+    /// - `sender`: Address of the messages' sender on the origin chain.
+    pub fn submitter_sender_queue_length(&self) -> IntGaugeVec {
+        self.submitter_sender_queue_length.clone()
+    }
+
     /// Time a message spends queued in the serial submitter measured from
     /// the moment the message was discovered as "sendable" from AbacusDB and
     /// being enqueued and being enqueued with the relevant `submitter` ending
@@ -349,6 +458,75 @@ impl CoreMetrics {
         self.messages_processed_count.clone()
     }
 
+    /// Failed attempts to process messages that reverted, whether in a
+    /// transaction or when simulated before sending one.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the message came from.
+    /// - `remote`: Chain we tried to deliver the message to.
+    /// - `reason`: The Inbox's reason if retrying cannot change the outcome,
+    ///   otherwise `recipient` for decoded reasons or `unknown`.
+    /// - `permanent`: `true` if retrying cannot change the outcome, `false`
+    ///   otherwise.
+    pub fn message_reverts_count(&self) -> IntCounterVec {
+        self.message_reverts_count.clone()
+    }
+
+    /// Gas used by `process` transactions, counting every transaction that
+    /// landed on chain including reverted ones.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the messages came from.
+    /// - `remote`: Chain the messages were delivered to.
+    pub fn relay_gas_used(&self) -> IntCounterVec {
+        self.relay_gas_used.clone()
+    }
+
+    /// Cost of `process` transactions in remote native token wei, counting
+    /// every transaction that landed on chain including reverted ones.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the messages came from.
+    /// - `remote`: Chain the messages were delivered to.
+    pub fn relay_cost(&self) -> CounterVec {
+        self.relay_cost.clone()
+    }
+
+    /// Total cost of delivering a message in remote native token wei,
+    /// observed once the message is processed and including any reverted
+    /// attempts.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the messages came from.
+    /// - `remote`: Chain the messages were delivered to.
+    pub fn relay_delivery_cost_histogram(&self) -> HistogramVec {
+        self.relay_delivery_cost_histogram.clone()
+    }
+
+    /// Interchain gas payments indexed from the InterchainGasPaymaster, in
+    /// origin native token wei.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the payments were made on.
+    /// - `remote`: Destination of the paid-for messages, or `unknown` if the
+    ///   message had not been indexed when the payment was.
+    pub fn interchain_gas_received(&self) -> CounterVec {
+        self.interchain_gas_received.clone()
+    }
+
+    /// Interchain gas payments for delivered messages less the cost of
+    /// delivering messages, since the agent started, in origin native token
+    /// wei. Costs are paid in remote native token wei, so are converted at the
+    /// route's configured exchange rate, and the margin is only reported for
+    /// routes with one.
+    ///
+    /// Labels:
+    /// - `origin`: Chain the messages came from.
+    /// - `remote`: Chain the messages were delivered to.
+    pub fn relay_net_margin(&self) -> GaugeVec {
+        self.relay_net_margin.clone()
+    }
+
     /// Histogram for measuring span durations provided by tracing.
     ///
     /// Labels:
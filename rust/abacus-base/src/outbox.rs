@@ -1,7 +1,7 @@
 use abacus_core::db::AbacusDB;
 use abacus_core::{
-    AbacusCommon, AbacusContract, ChainCommunicationError, Checkpoint, Message, Outbox,
-    OutboxEvents, OutboxState, RawCommittedMessage, TxOutcome,
+    AbacusCommon, AbacusContract, ChainCommunicationError, Checkpoint, DispatchOutcome, Message,
+    Outbox, OutboxEvents, OutboxState, RawCommittedMessage, TxOutcome,
 };
 
 use abacus_ethereum::EthereumOutbox;
@@ -85,7 +85,10 @@ impl CachingOutbox {
 
 #[async_trait]
 impl Outbox for CachingOutbox {
-    async fn dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError> {
+    async fn dispatch(
+        &self,
+        message: &Message,
+    ) -> Result<DispatchOutcome, ChainCommunicationError> {
         self.outbox.dispatch(message).await
     }
 
@@ -238,7 +241,10 @@ impl From<Box<dyn Outbox>> for Outboxes {
 #[async_trait]
 impl Outbox for OutboxVariants {
     #[instrument(level = "trace", err)]
-    async fn dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError> {
+    async fn dispatch(
+        &self,
+        message: &Message,
+    ) -> Result<DispatchOutcome, ChainCommunicationError> {
         match self {
             OutboxVariants::Ethereum(outbox) => outbox.dispatch(message).await,
             OutboxVariants::Mock(mock_outbox) => mock_outbox.dispatch(message).await,
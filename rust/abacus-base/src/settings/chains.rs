@@ -1,12 +1,14 @@
 use ethers::signers::Signer;
-use eyre::Report;
+use ethers::types::U256;
+use eyre::{eyre, Report};
 use serde::Deserialize;
 
 use abacus_core::{AbacusAbi, ContractLocator, Signers};
 use abacus_ethereum::{
     Connection, EthereumInboxAbi, EthereumInterchainGasPaymasterAbi, EthereumOutboxAbi,
-    InboxBuilder, InboxValidatorManagerBuilder, InterchainGasPaymasterBuilder,
-    MakeableWithProvider, OutboxBuilder,
+    GasEscalator, GasEscalatorConf, InboxBuilder, InboxValidatorManagerBuilder,
+    InterchainGasPaymasterBuilder, MakeableWithProvider, OutboxBuilder,
+    SharedInboxValidatorManagersBuilder, TransactionOverrides, TransactionOverridesConf,
 };
 use ethers_prometheus::{ChainInfo, ContractInfo, PrometheusMiddlewareConf, WalletInfo};
 
@@ -37,11 +39,36 @@ impl Default for ChainConf {
 pub struct GelatoConf {
     /// Whether to use Gelato's Relay service for processing messages on inboxes.
     pub enabled_for_message_submission: bool,
-    // Other gelato configuration options can go here, like
-    // max-in-flight submissions we're willing to try, perhaps
-    // the Ethereum-based chain_id (which may differ from
-    // abacus domain), number of retry attempts before giving up,
-    // etc.
+    /// The Ethereum-based chain id, which may differ from the abacus domain.
+    /// Required if enabled for message submission.
+    #[serde(default)]
+    pub chain_id: Option<String>,
+    /// Address of the GelatoRelayForwarder contract that verifies and
+    /// executes forward requests on this chain. Required if enabled for
+    /// message submission.
+    #[serde(default)]
+    pub forwarder_address: Option<String>,
+    /// Base URL of the Gelato Relay API. Defaults to Gelato's public
+    /// endpoint if not provided.
+    #[serde(default)]
+    pub relay_url: Option<String>,
+    /// Address of the token Gelato fees are paid in. Defaults to the native
+    /// token if not provided.
+    #[serde(default)]
+    pub fee_token: Option<String>,
+    /// The maximum fee, in the smallest unit of the fee token, we are willing
+    /// to pay Gelato to relay a single message. Required if enabled for
+    /// message submission.
+    #[serde(default)]
+    pub max_fee: Option<String>,
+    /// Number of times a forward request is retried before the message is
+    /// returned to the wait queue.
+    #[serde(default)]
+    pub max_retries: Option<String>,
+    /// Maximum number of forward requests we are willing to have in flight
+    /// at once.
+    #[serde(default)]
+    pub max_in_flight: Option<String>,
 }
 
 /// Addresses for outbox chain contracts
@@ -82,6 +109,18 @@ pub struct ChainSetup<T> {
     pub chain: ChainConf,
     /// Gelato configuration for this chain (Gelato unused if None)
     pub gelato_conf: Option<GelatoConf>,
+    /// Configuration for replacing stuck transactions with ones paying higher fees (stuck
+    /// transactions are waited on without replacement if None)
+    #[serde(default)]
+    pub gas_escalator: Option<GasEscalatorConf>,
+    /// Overrides of how transactions are built, e.g. whether to use EIP-1559
+    #[serde(default)]
+    pub transaction_overrides: TransactionOverridesConf,
+    /// The minimum balance, in the smallest unit of the native token, of the wallet submitting
+    /// transactions to the chain. The relayer stops processing messages on the inbox while its
+    /// wallet is below it (no minimum if None). Does nothing for outboxes.
+    #[serde(default)]
+    pub min_balance: Option<String>,
     /// Set this key to disable the inbox. Does nothing for outboxes.
     #[serde(default)]
     pub disabled: Option<String>,
@@ -99,6 +138,30 @@ impl<T> ChainSetup<T> {
             .parse::<u32>()
             .expect("could not parse finality_blocks")
     }
+
+    /// Get the parsed gas escalator configuration, if any
+    pub fn gas_escalator(&self) -> Result<Option<GasEscalator>, Report> {
+        self.gas_escalator
+            .as_ref()
+            .map(GasEscalator::try_from)
+            .transpose()
+    }
+
+    /// Get the parsed minimum wallet balance, if any
+    pub fn min_balance(&self) -> Result<Option<U256>, Report> {
+        self.min_balance
+            .as_deref()
+            .map(|balance| {
+                U256::from_dec_str(balance)
+                    .map_err(|e| eyre!("Invalid minBalance {balance}: {e:?}"))
+            })
+            .transpose()
+    }
+
+    /// Get the parsed transaction overrides
+    pub fn transaction_overrides(&self) -> Result<TransactionOverrides, Report> {
+        TransactionOverrides::try_from(&self.transaction_overrides)
+    }
 }
 
 impl ChainSetup<OutboxAddresses> {
@@ -108,24 +171,29 @@ impl ChainSetup<OutboxAddresses> {
         signer: Option<Signers>,
         metrics: &CoreMetrics,
     ) -> Result<Outboxes, Report> {
+        let gas_escalator = self.gas_escalator()?;
+        let transaction_overrides = self.transaction_overrides()?;
         match &self.chain {
             ChainConf::Ethereum(conf) => Ok(OutboxVariants::Ethereum(
-                OutboxBuilder {}
-                    .make_with_connection(
-                        conf.clone(),
-                        &ContractLocator {
-                            chain_name: self.name.clone(),
-                            domain: self.domain.parse().expect("invalid uint"),
-                            address: self
-                                .addresses
-                                .outbox
-                                .parse::<ethers::types::Address>()?
-                                .into(),
-                        },
-                        signer,
-                        Some((metrics.provider_metrics(), self.metrics_conf())),
-                    )
-                    .await?,
+                OutboxBuilder {
+                    gas_escalator,
+                    transaction_overrides,
+                }
+                .make_with_connection(
+                    conf.clone(),
+                    &ContractLocator {
+                        chain_name: self.name.clone(),
+                        domain: self.domain.parse().expect("invalid uint"),
+                        address: self
+                            .addresses
+                            .outbox
+                            .parse::<ethers::types::Address>()?
+                            .into(),
+                    },
+                    signer,
+                    Some((metrics.provider_metrics(), self.metrics_conf())),
+                )
+                .await?,
             )
             .into()),
         }
@@ -142,23 +210,26 @@ impl ChainSetup<OutboxAddresses> {
         } else {
             return Ok(None);
         };
+        let gas_escalator = self.gas_escalator()?;
+        let transaction_overrides = self.transaction_overrides()?;
         match &self.chain {
             ChainConf::Ethereum(conf) => Ok(Some(
                 InterchainGasPaymasterVariants::Ethereum(
-                    InterchainGasPaymasterBuilder {}
-                        .make_with_connection(
-                            conf.clone(),
-                            &ContractLocator {
-                                chain_name: self.name.clone(),
-                                domain: self.domain.parse().expect("invalid uint"),
-                                address: paymaster_address
-                                    .parse::<ethers::types::Address>()?
-                                    .into(),
-                            },
-                            signer,
-                            Some((metrics.provider_metrics(), self.metrics_conf())),
-                        )
-                        .await?,
+                    InterchainGasPaymasterBuilder {
+                        gas_escalator,
+                        transaction_overrides,
+                    }
+                    .make_with_connection(
+                        conf.clone(),
+                        &ContractLocator {
+                            chain_name: self.name.clone(),
+                            domain: self.domain.parse().expect("invalid uint"),
+                            address: paymaster_address.parse::<ethers::types::Address>()?.into(),
+                        },
+                        signer,
+                        Some((metrics.provider_metrics(), self.metrics_conf())),
+                    )
+                    .await?,
                 )
                 .into(),
             )),
@@ -231,25 +302,31 @@ impl ChainSetup<InboxAddresses> {
         metrics: &CoreMetrics,
     ) -> Result<InboxValidatorManagers, Report> {
         let inbox_address = self.addresses.inbox.parse::<ethers::types::Address>()?;
+        let gas_escalator = self.gas_escalator()?;
+        let transaction_overrides = self.transaction_overrides()?;
         let metrics_conf = self.metrics_conf(metrics.agent_name(), &signer);
         match &self.chain {
             ChainConf::Ethereum(conf) => Ok(InboxValidatorManagerVariants::Ethereum(
-                InboxValidatorManagerBuilder { inbox_address }
-                    .make_with_connection(
-                        conf.clone(),
-                        &ContractLocator {
-                            chain_name: self.name.clone(),
-                            domain: self.domain.parse().expect("invalid uint"),
-                            address: self
-                                .addresses
-                                .validator_manager
-                                .parse::<ethers::types::Address>()?
-                                .into(),
-                        },
-                        signer,
-                        Some((metrics.provider_metrics(), metrics_conf)),
-                    )
-                    .await?,
+                InboxValidatorManagerBuilder {
+                    inbox_address,
+                    gas_escalator,
+                    transaction_overrides,
+                }
+                .make_with_connection(
+                    conf.clone(),
+                    &ContractLocator {
+                        chain_name: self.name.clone(),
+                        domain: self.domain.parse().expect("invalid uint"),
+                        address: self
+                            .addresses
+                            .validator_manager
+                            .parse::<ethers::types::Address>()?
+                            .into(),
+                    },
+                    signer,
+                    Some((metrics.provider_metrics(), metrics_conf)),
+                )
+                .await?,
             )
             .into()),
         }
@@ -291,3 +368,55 @@ impl ChainSetup<InboxAddresses> {
         cfg
     }
 }
+
+/// Try to convert the settings of several inboxes on the same chain into InboxValidatorManager
+/// contracts that share one provider, and so one nonce manager for the signer they submit
+/// transactions with. The provider connects as configured for the first inbox.
+pub async fn try_into_shared_inbox_validator_managers(
+    setups: &[&ChainSetup<InboxAddresses>],
+    signer: Option<Signers>,
+    metrics: &CoreMetrics,
+) -> Result<Vec<InboxValidatorManagers>, Report> {
+    let first = match setups.first() {
+        Some(first) => first,
+        None => return Ok(Vec::new()),
+    };
+    let mut metrics_conf = first.metrics_conf(metrics.agent_name(), &signer);
+    let mut validator_managers = Vec::new();
+    for setup in setups {
+        for (address, info) in setup.metrics_conf(metrics.agent_name(), &signer).contracts {
+            metrics_conf.contracts.entry(address).or_insert(info);
+        }
+        let locator = ContractLocator {
+            chain_name: setup.name.clone(),
+            domain: setup.domain.parse().expect("invalid uint"),
+            address: setup
+                .addresses
+                .validator_manager
+                .parse::<ethers::types::Address>()?
+                .into(),
+        };
+        let builder = InboxValidatorManagerBuilder {
+            inbox_address: setup.addresses.inbox.parse::<ethers::types::Address>()?,
+            gas_escalator: setup.gas_escalator()?,
+            transaction_overrides: setup.transaction_overrides()?,
+        };
+        validator_managers.push((locator, builder));
+    }
+    let locator = validator_managers[0].0.clone();
+    match &first.chain {
+        ChainConf::Ethereum(conf) => Ok(SharedInboxValidatorManagersBuilder { validator_managers }
+            .make_with_connection(
+                conf.clone(),
+                &locator,
+                signer,
+                Some((metrics.provider_metrics(), metrics_conf)),
+            )
+            .await?
+            .into_iter()
+            .map(|validator_manager| {
+                InboxValidatorManagerVariants::Ethereum(validator_manager).into()
+            })
+            .collect()),
+    }
+}
@@ -76,7 +76,7 @@ use std::{collections::HashMap, env, sync::Arc};
 
 use config::{Config, ConfigError, Environment, File};
 use ethers::prelude::AwsSigner;
-use eyre::{bail, Report};
+use eyre::{bail, eyre, Report};
 use once_cell::sync::OnceCell;
 use rusoto_core::{credential::EnvironmentProvider, HttpClient};
 use rusoto_kms::KmsClient;
@@ -91,12 +91,13 @@ use abacus_core::{
 use abacus_ethereum::{
     InterchainGasPaymasterIndexerBuilder, MakeableWithProvider, OutboxIndexerBuilder,
 };
+use chains::try_into_shared_inbox_validator_managers;
 pub use chains::{ChainConf, ChainSetup, InboxAddresses, OutboxAddresses};
 
 use crate::{settings::trace::TracingConfig, CachingInterchainGasPaymaster};
 use crate::{
     AbacusAgentCore, CachingInbox, CachingOutbox, CoreMetrics, InboxContracts,
-    InboxValidatorManagers, InterchainGasPaymasterIndexers, OutboxIndexers,
+    InboxValidatorManagers, InterchainGasPaymasterIndexers, OriginContracts, OutboxIndexers,
 };
 
 /// Chain configuration
@@ -188,6 +189,20 @@ impl IndexSettings {
     }
 }
 
+/// Settings of an origin chain: its outbox and the inboxes on other chains that receive its
+/// messages.
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginSettings {
+    /// Settings for the outbox indexer
+    #[serde(default)]
+    pub index: IndexSettings,
+    /// Configurations for contracts on the outbox chain
+    pub outbox: ChainSetup<OutboxAddresses>,
+    /// Configurations for contracts on inbox chains
+    pub inboxes: HashMap<String, ChainSetup<InboxAddresses>>,
+}
+
 /// Settings. Usually this should be treated as a base config and used as
 /// follows:
 ///
@@ -226,6 +241,10 @@ pub struct Settings {
     pub outbox: ChainSetup<OutboxAddresses>,
     /// Configurations for contracts on inbox chains
     pub inboxes: HashMap<String, ChainSetup<InboxAddresses>>,
+    /// Origins served in addition to `outbox`, keyed by outbox chain name. Only agents that
+    /// serve several origins from one process, e.g. the relayer, use these.
+    #[serde(default)]
+    pub origins: HashMap<String, OriginSettings>,
     /// The tracing configuration
     pub tracing: TracingConfig,
     /// Transaction signers
@@ -241,10 +260,22 @@ impl Settings {
             index: self.index.clone(),
             outbox: self.outbox.clone(),
             inboxes: self.inboxes.clone(),
+            origins: self.origins.clone(),
             tracing: self.tracing.clone(),
             signers: self.signers.clone(),
         }
     }
+
+    /// The settings of an origin served in addition to `outbox`, as if it were the only one.
+    fn for_origin(&self, origin: &OriginSettings) -> Self {
+        Self {
+            index: origin.index.clone(),
+            outbox: origin.outbox.clone(),
+            inboxes: origin.inboxes.clone(),
+            origins: HashMap::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Settings {
@@ -253,11 +284,38 @@ impl Settings {
         self.signers.get(name)?.try_into_signer().await.ok()
     }
 
-    /// Try to get a map of inbox name -> inbox contracts
+    /// Get the settings of every origin served: `outbox` and `inboxes`, followed by `origins`
+    pub fn all_origins(&self) -> Vec<OriginSettings> {
+        std::iter::once(OriginSettings {
+            index: self.index.clone(),
+            outbox: self.outbox.clone(),
+            inboxes: self.inboxes.clone(),
+        })
+        .chain(self.origins.values().cloned())
+        .collect()
+    }
+
+    /// Get the settings of the inbox on chain `destination` that receives messages from the
+    /// outbox on chain `origin`
+    pub fn inbox_setup(
+        &self,
+        origin: &str,
+        destination: &str,
+    ) -> Option<&ChainSetup<InboxAddresses>> {
+        if origin == self.outbox.name {
+            self.inboxes.get(destination)
+        } else {
+            self.origins.get(origin)?.inboxes.get(destination)
+        }
+    }
+
+    /// Try to get a map of inbox name -> inbox contracts, taking the InboxValidatorManagers from
+    /// `validator_managers` (see `try_inbox_validator_managers`)
     pub async fn try_inbox_contracts(
         &self,
         db: DB,
         metrics: &CoreMetrics,
+        validator_managers: &HashMap<(String, String), InboxValidatorManagers>,
     ) -> Result<HashMap<String, InboxContracts>, Report> {
         let mut result = HashMap::new();
         for (k, v) in self.inboxes.iter().filter(|(_, v)| v.disabled.is_none()) {
@@ -269,7 +327,10 @@ impl Settings {
                 );
             }
             let caching_inbox = self.try_caching_inbox(v, db.clone(), metrics).await?;
-            let validator_manager = self.try_inbox_validator_manager(v, metrics).await?;
+            let validator_manager = validator_managers
+                .get(&(self.outbox.name.clone(), v.name.clone()))
+                .cloned()
+                .ok_or_else(|| eyre!("No InboxValidatorManager for inbox {}", v.name))?;
             result.insert(
                 v.name.clone(),
                 InboxContracts {
@@ -294,17 +355,38 @@ impl Settings {
         Ok(CachingInbox::new(inbox, abacus_db))
     }
 
-    /// Try to get an InboxValidatorManager
-    async fn try_inbox_validator_manager(
+    /// Try to get the InboxValidatorManagers of the enabled inboxes of every origin, keyed by
+    /// outbox and inbox chain name. The InboxValidatorManagers on each chain share a provider,
+    /// as they submit transactions with the chain's signer.
+    pub async fn try_inbox_validator_managers(
         &self,
-        chain_setup: &ChainSetup<InboxAddresses>,
         metrics: &CoreMetrics,
-    ) -> Result<InboxValidatorManagers, Report> {
-        let signer = self.get_signer(&chain_setup.name).await;
+    ) -> Result<HashMap<(String, String), InboxValidatorManagers>, Report> {
+        let origins = self.all_origins();
+        let mut by_chain: HashMap<&str, Vec<(&str, &ChainSetup<InboxAddresses>)>> = HashMap::new();
+        for origin in origins.iter() {
+            for setup in origin.inboxes.values().filter(|v| v.disabled.is_none()) {
+                by_chain
+                    .entry(setup.name.as_str())
+                    .or_default()
+                    .push((origin.outbox.name.as_str(), setup));
+            }
+        }
 
-        chain_setup
-            .try_into_inbox_validator_manager(signer, metrics)
-            .await
+        let mut result = HashMap::new();
+        for (chain_name, inboxes) in by_chain {
+            let signer = self.get_signer(chain_name).await;
+            let setups: Vec<_> = inboxes.iter().map(|(_, setup)| *setup).collect();
+            let validator_managers =
+                try_into_shared_inbox_validator_managers(&setups, signer, metrics).await?;
+            for ((outbox_name, _), validator_manager) in inboxes.iter().zip(validator_managers) {
+                result.insert(
+                    (outbox_name.to_string(), chain_name.to_owned()),
+                    validator_manager,
+                );
+            }
+        }
+        Ok(result)
     }
 
     /// Try to get a CachingOutbox
@@ -437,26 +519,78 @@ impl Settings {
         )?);
 
         let db = DB::from_path(&self.db)?;
-        let outbox = Arc::new(self.try_caching_outbox(db.clone(), &metrics).await?);
+        let validator_managers = if parse_inboxes {
+            self.try_inbox_validator_managers(&metrics).await?
+        } else {
+            HashMap::new()
+        };
+        let OriginContracts {
+            outbox,
+            interchain_gas_paymaster,
+            inboxes,
+            indexer,
+        } = self
+            .try_origin_contracts(db.clone(), &metrics, parse_inboxes, &validator_managers)
+            .await?;
+
+        let mut additional_origins = HashMap::new();
+        for (k, v) in self.origins.iter() {
+            if k != &v.outbox.name {
+                bail!(
+                    "Origin key does not match outbox name:\n key: {}  name: {}",
+                    k,
+                    v.outbox.name
+                );
+            }
+            if k == &self.outbox.name {
+                bail!("Origin {} duplicates the outbox", k);
+            }
+            let origin = self
+                .for_origin(v)
+                .try_origin_contracts(db.clone(), &metrics, parse_inboxes, &validator_managers)
+                .await?;
+            additional_origins.insert(k.clone(), origin);
+        }
+
+        Ok(AbacusAgentCore {
+            outbox,
+            inboxes,
+            interchain_gas_paymaster,
+            additional_origins,
+            db,
+            metrics,
+            indexer,
+            settings: self.clone(),
+        })
+    }
+
+    /// Try to get the contracts of the origin configured by `outbox` and `inboxes`. Its data
+    /// is kept in the AbacusDB namespace of the outbox chain.
+    async fn try_origin_contracts(
+        &self,
+        db: DB,
+        metrics: &CoreMetrics,
+        parse_inboxes: bool,
+        validator_managers: &HashMap<(String, String), InboxValidatorManagers>,
+    ) -> Result<OriginContracts, Report> {
+        let outbox = Arc::new(self.try_caching_outbox(db.clone(), metrics).await?);
         let interchain_gas_paymaster = self
-            .try_caching_interchain_gas_paymaster(db.clone(), &metrics)
+            .try_caching_interchain_gas_paymaster(db.clone(), metrics)
             .await?
             .map(Arc::new);
 
-        let inbox_contracts = if parse_inboxes {
-            self.try_inbox_contracts(db.clone(), &metrics).await?
+        let inboxes = if parse_inboxes {
+            self.try_inbox_contracts(db, metrics, validator_managers)
+                .await?
         } else {
             HashMap::new()
         };
 
-        Ok(AbacusAgentCore {
+        Ok(OriginContracts {
             outbox,
-            inboxes: inbox_contracts,
             interchain_gas_paymaster,
-            db,
-            metrics,
+            inboxes,
             indexer: self.index.clone(),
-            settings: self.clone(),
         })
     }
 
@@ -1,9 +1,10 @@
 use async_trait::async_trait;
+use ethers::core::types::{Address, H256, U256};
 use std::sync::Arc;
 
 use abacus_core::{
     accumulator::merkle::Proof, AbacusMessage, ChainCommunicationError, InboxValidatorManager,
-    MultisigSignedCheckpoint, TxOutcome,
+    MultisigSignedCheckpoint, ProcessCall, TxCostEstimate, TxOutcome,
 };
 
 #[derive(Debug, Clone)]
@@ -43,29 +44,206 @@ pub enum InboxValidatorManagerVariants {
 
 #[async_trait]
 impl InboxValidatorManager for InboxValidatorManagerVariants {
+    fn contract_address(&self) -> Address {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager.contract_address()
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager.contract_address()
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager.contract_address()
+            }
+        }
+    }
+
     /// Submit a signed checkpoint for inclusion
     async fn process(
         &self,
         multisig_signed_checkpoint: &MultisigSignedCheckpoint,
         message: &AbacusMessage,
         proof: &Proof,
+        gas_limit: Option<U256>,
     ) -> Result<TxOutcome, ChainCommunicationError> {
         match self {
             InboxValidatorManagerVariants::Ethereum(validator_manager) => {
                 validator_manager
-                    .process(multisig_signed_checkpoint, message, proof)
+                    .process(multisig_signed_checkpoint, message, proof, gas_limit)
                     .await
             }
             InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
                 mock_validator_manager
-                    .process(multisig_signed_checkpoint, message, proof)
+                    .process(multisig_signed_checkpoint, message, proof, gas_limit)
                     .await
             }
             InboxValidatorManagerVariants::Other(validator_manager) => {
                 validator_manager
-                    .process(multisig_signed_checkpoint, message, proof)
+                    .process(multisig_signed_checkpoint, message, proof, gas_limit)
                     .await
             }
         }
     }
+
+    async fn process_estimate_costs(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Result<TxCostEstimate, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager
+                    .process_estimate_costs(multisig_signed_checkpoint, message, proof)
+                    .await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager
+                    .process_estimate_costs(multisig_signed_checkpoint, message, proof)
+                    .await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager
+                    .process_estimate_costs(multisig_signed_checkpoint, message, proof)
+                    .await
+            }
+        }
+    }
+
+    fn process_calldata(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Vec<u8> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager.process_calldata(multisig_signed_checkpoint, message, proof)
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager.process_calldata(multisig_signed_checkpoint, message, proof)
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager.process_calldata(multisig_signed_checkpoint, message, proof)
+            }
+        }
+    }
+
+    async fn simulate_batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+    ) -> Result<Vec<bool>, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager
+                    .simulate_batch_process(multicall_address, calls)
+                    .await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager
+                    .simulate_batch_process(multicall_address, calls)
+                    .await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager
+                    .simulate_batch_process(multicall_address, calls)
+                    .await
+            }
+        }
+    }
+
+    async fn batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+        gas_limit: U256,
+    ) -> Result<TxOutcome, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager
+                    .batch_process(multicall_address, calls, gas_limit)
+                    .await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager
+                    .batch_process(multicall_address, calls, gas_limit)
+                    .await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager
+                    .batch_process(multicall_address, calls, gas_limit)
+                    .await
+            }
+        }
+    }
+
+    async fn submitter_nonce(&self) -> Result<U256, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager.submitter_nonce().await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager.submitter_nonce().await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager.submitter_nonce().await
+            }
+        }
+    }
+
+    async fn submitter_balance(&self) -> Result<U256, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager.submitter_balance().await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager.submitter_balance().await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager.submitter_balance().await
+            }
+        }
+    }
+
+    async fn send_process(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+        nonce: U256,
+        tx_cost: &TxCostEstimate,
+    ) -> Result<H256, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager
+                    .send_process(multisig_signed_checkpoint, message, proof, nonce, tx_cost)
+                    .await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager
+                    .send_process(multisig_signed_checkpoint, message, proof, nonce, tx_cost)
+                    .await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager
+                    .send_process(multisig_signed_checkpoint, message, proof, nonce, tx_cost)
+                    .await
+            }
+        }
+    }
+
+    async fn tx_outcome(&self, txid: H256) -> Result<Option<TxOutcome>, ChainCommunicationError> {
+        match self {
+            InboxValidatorManagerVariants::Ethereum(validator_manager) => {
+                validator_manager.tx_outcome(txid).await
+            }
+            InboxValidatorManagerVariants::Mock(mock_validator_manager) => {
+                mock_validator_manager.tx_outcome(txid).await
+            }
+            InboxValidatorManagerVariants::Other(validator_manager) => {
+                validator_manager.tx_outcome(txid).await
+            }
+        }
+    }
 }
@@ -1,13 +1,16 @@
 use crate::db::{DbError, TypedDB, DB};
 use crate::{
     accumulator::merkle::Proof, traits::RawCommittedMessage, AbacusMessage, CommittedMessage,
-    Decode, InterchainGasPayment, InterchainGasPaymentMeta, InterchainGasPaymentWithMeta,
+    DeadLetter, Decode, InterchainGasPayment, InterchainGasPaymentMeta,
+    InterchainGasPaymentWithMeta, MessageSchedulingState, RecipientGasUsage, RelayExpense,
+    RouteProfitAndLoss, TxOutcome,
 };
 use ethers::core::types::{H256, U256};
 use eyre::Result;
 use tokio::time::sleep;
 use tracing::{debug, info, trace};
 
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::time::Duration;
 
@@ -22,6 +25,10 @@ static LATEST_LEAF_INDEX_FOR_DESTINATION: &str = "latest_known_leaf_index_for_de
 static LEAF_PROCESS_STATUS: &str = "leaf_process_status_";
 static GAS_PAYMENT_FOR_LEAF: &str = "gas_payment_for_leaf_";
 static GAS_PAYMENT_META_PROCESSED: &str = "gas_payment_meta_processed_";
+static DEAD_LETTER: &str = "dead_letter_";
+static RELAY_EXPENSE: &str = "relay_expense_";
+static SCHEDULING_STATE: &str = "scheduling_state_";
+static RECIPIENT_GAS_USAGE: &str = "recipient_gas_usage_";
 
 /// DB handle for storing data tied to a specific Outbox.
 ///
@@ -234,17 +241,145 @@ impl AbacusDB {
         Ok(value.map(|x| x == 1))
     }
 
+    /// Store a dead letter for a message that could not be delivered, replacing
+    /// any existing dead letter for the message
+    pub fn store_dead_letter(&self, dead_letter: &DeadLetter) -> Result<(), DbError> {
+        debug!(leaf_index = dead_letter.leaf_index, "store dead letter");
+        self.store_keyed_encodable(DEAD_LETTER, &dead_letter.leaf_index, dead_letter)
+    }
+
+    /// Retrieve the dead letter for a message by its leaf index
+    pub fn retrieve_dead_letter(&self, leaf_index: u32) -> Result<Option<DeadLetter>, DbError> {
+        self.retrieve_keyed_decodable(DEAD_LETTER, &leaf_index)
+    }
+
+    /// Remove the dead letter for a message, e.g. once it has been re-driven
+    pub fn remove_dead_letter(&self, leaf_index: u32) -> Result<(), DbError> {
+        self.delete_keyed(DEAD_LETTER, &leaf_index)
+    }
+
+    /// Retrieve all dead letters, ordered by leaf index
+    pub fn dead_letters(&self) -> Result<Vec<DeadLetter>, DbError> {
+        self.retrieve_all_decodable(DEAD_LETTER)
+    }
+
+    /// Store the relayer's scheduling state for a message
+    pub fn store_scheduling_state(&self, state: &MessageSchedulingState) -> Result<(), DbError> {
+        debug!(leaf_index = state.leaf_index, "store scheduling state");
+        self.store_keyed_encodable(SCHEDULING_STATE, &state.leaf_index, state)
+    }
+
+    /// Retrieve the relayer's scheduling state for a message by its leaf index
+    pub fn retrieve_scheduling_state(
+        &self,
+        leaf_index: u32,
+    ) -> Result<Option<MessageSchedulingState>, DbError> {
+        self.retrieve_keyed_decodable(SCHEDULING_STATE, &leaf_index)
+    }
+
+    /// Remove the scheduling state for a message, e.g. once it is delivered
+    pub fn remove_scheduling_state(&self, leaf_index: u32) -> Result<(), DbError> {
+        self.delete_keyed(SCHEDULING_STATE, &leaf_index)
+    }
+
+    /// Add the gas spent by a `process` transaction for a message to the
+    /// message's relay expense, and record the gas payment received for it
+    pub fn record_relay_expense(
+        &self,
+        leaf_index: u32,
+        destination: u32,
+        outcome: &TxOutcome,
+    ) -> Result<RelayExpense, DbError> {
+        let previous = self.retrieve_relay_expense(leaf_index)?;
+        let (num_transactions, gas_used, cost) = previous.map_or_else(
+            || (0, U256::zero(), U256::zero()),
+            |e| (e.num_transactions, e.gas_used, e.cost),
+        );
+        let expense = RelayExpense {
+            leaf_index,
+            destination,
+            txid: outcome.txid,
+            num_transactions: num_transactions + 1,
+            gas_used: gas_used.saturating_add(outcome.gas_used),
+            gas_price: outcome.gas_price,
+            cost: cost.saturating_add(outcome.cost()),
+            gas_payment: self.retrieve_gas_payment_for_leaf(leaf_index)?,
+        };
+        debug!(expense=?expense, "store relay expense");
+        self.store_keyed_encodable(RELAY_EXPENSE, &leaf_index, &expense)?;
+        Ok(expense)
+    }
+
+    /// Record the gas used by a `process` transaction delivering one message to
+    /// a recipient on a destination
+    ///
+    /// Key --> value: (`destination`, `recipient`) --> `usage`
+    pub fn record_recipient_gas_used(
+        &self,
+        destination: u32,
+        recipient: H256,
+        gas_used: U256,
+    ) -> Result<RecipientGasUsage, DbError> {
+        let mut usage = self
+            .retrieve_recipient_gas_usage(destination, recipient)?
+            .unwrap_or_default();
+        usage.record(gas_used);
+        debug!(destination, recipient = ?recipient, usage = ?usage, "store recipient gas usage");
+        self.store_keyed_encodable(
+            format!("{RECIPIENT_GAS_USAGE}{destination}_"),
+            &recipient,
+            &usage,
+        )?;
+        Ok(usage)
+    }
+
+    /// Retrieve the gas used delivering messages to a recipient on a destination
+    pub fn retrieve_recipient_gas_usage(
+        &self,
+        destination: u32,
+        recipient: H256,
+    ) -> Result<Option<RecipientGasUsage>, DbError> {
+        self.retrieve_keyed_decodable(format!("{RECIPIENT_GAS_USAGE}{destination}_"), &recipient)
+    }
+
+    /// Retrieve the relay expense of a message by its leaf index
+    pub fn retrieve_relay_expense(&self, leaf_index: u32) -> Result<Option<RelayExpense>, DbError> {
+        self.retrieve_keyed_decodable(RELAY_EXPENSE, &leaf_index)
+    }
+
+    /// Retrieve all relay expenses, ordered by leaf index
+    pub fn relay_expenses(&self) -> Result<Vec<RelayExpense>, DbError> {
+        self.retrieve_all_decodable(RELAY_EXPENSE)
+    }
+
+    /// The profit or loss of relaying messages to each destination, ordered by
+    /// destination
+    pub fn route_profit_and_loss(&self) -> Result<Vec<RouteProfitAndLoss>, DbError> {
+        let mut routes = BTreeMap::new();
+        for expense in self.relay_expenses()? {
+            routes
+                .entry(expense.destination)
+                .or_insert_with(|| RouteProfitAndLoss {
+                    destination: expense.destination,
+                    ..Default::default()
+                })
+                .add(&expense);
+        }
+        Ok(routes.into_values().collect())
+    }
+
     /// If the provided gas payment, identified by its metadata, has not been processed,
-    /// processes the gas payment and records it as processed.
+    /// processes the gas payment and records it as processed. Returns whether the payment
+    /// was newly processed.
     pub fn process_gas_payment(
         &self,
         gas_payment_with_meta: &InterchainGasPaymentWithMeta,
-    ) -> Result<(), DbError> {
+    ) -> Result<bool, DbError> {
         let meta = &gas_payment_with_meta.meta;
         // If the gas payment has already been processed, do nothing
         if self.retrieve_gas_payment_meta_processed(meta)? {
             trace!(gas_payment_with_meta=?gas_payment_with_meta, "Attempted to process an already-processed gas payment");
-            return Ok(());
+            return Ok(false);
         }
         // Set the gas payment as processed
         self.store_gas_payment_meta_processed(meta)?;
@@ -252,7 +387,7 @@ impl AbacusDB {
         // Update the total gas payment for the leaf to include the payment
         self.update_gas_payment_for_leaf(&gas_payment_with_meta.payment)?;
 
-        Ok(())
+        Ok(true)
     }
 
     /// Record a gas payment, identified by its metadata, as processed
@@ -289,7 +424,7 @@ impl AbacusDB {
     }
 
     /// Retrieve the total gas payment for a leaf index
-    fn retrieve_gas_payment_for_leaf(&self, leaf_index: u32) -> Result<U256, DbError> {
+    pub fn retrieve_gas_payment_for_leaf(&self, leaf_index: u32) -> Result<U256, DbError> {
         Ok(self
             .retrieve_keyed_decodable(GAS_PAYMENT_FOR_LEAF, &leaf_index)?
             .unwrap_or(U256::zero()))
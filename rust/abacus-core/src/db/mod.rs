@@ -76,6 +76,11 @@ impl DB {
         Ok(self.0.get(key)?)
     }
 
+    /// Delete a value from the DB
+    fn _delete(&self, key: impl AsRef<[u8]>) -> Result<()> {
+        Ok(self.0.delete(key)?)
+    }
+
     /// Prefix a key and store in the DB
     fn prefix_store(
         &self,
@@ -142,6 +147,23 @@ impl DB {
         self.retrieve_decodable(prefix, key.to_vec())
     }
 
+    /// Delete the value stored under an encodable key
+    pub fn delete_keyed<K: Encode>(&self, prefix: impl AsRef<[u8]>, key: &K) -> Result<()> {
+        let mut buf = vec![];
+        buf.extend(prefix.as_ref());
+        buf.extend(key.to_vec());
+        self._delete(buf)
+    }
+
+    /// Retrieve and attempt to decode all values whose keys start with `prefix`
+    pub fn retrieve_all_decodable<V: Decode>(&self, prefix: impl AsRef<[u8]>) -> Result<Vec<V>> {
+        let prefix = prefix.as_ref();
+        self.prefix_iterator(prefix)
+            .take_while(|(k, _)| k.starts_with(prefix))
+            .map(|(_, v)| Ok(V::read_from(&mut v.as_ref())?))
+            .collect()
+    }
+
     /// Get prefix db iterator for `prefix`
     pub fn prefix_iterator(&self, prefix: impl AsRef<[u8]>) -> DBIterator {
         self.0.prefix_iterator(prefix)
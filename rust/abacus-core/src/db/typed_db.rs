@@ -71,4 +71,21 @@ impl TypedDB {
         self.db
             .retrieve_keyed_decodable(self.full_prefix(prefix), key)
     }
+
+    /// Delete the value stored under an encodable key
+    pub fn delete_keyed<K: Encode>(
+        &self,
+        prefix: impl AsRef<[u8]>,
+        key: &K,
+    ) -> Result<(), DbError> {
+        self.db.delete_keyed(self.full_prefix(prefix), key)
+    }
+
+    /// Retrieve all decodable values stored under the prefix
+    pub fn retrieve_all_decodable<V: Decode>(
+        &self,
+        prefix: impl AsRef<[u8]>,
+    ) -> Result<Vec<V>, DbError> {
+        self.db.retrieve_all_decodable(self.full_prefix(prefix))
+    }
 }
@@ -17,4 +17,12 @@ pub trait Inbox: AbacusCommon + Send + Sync + Debug {
 
     /// Fetch the status of a message
     async fn message_status(&self, leaf: H256) -> Result<MessageStatus, ChainCommunicationError>;
+
+    /// Fetch the status of a message as of `finality_blocks` blocks behind
+    /// the chain tip, i.e. a status that will not be undone by a re-org
+    async fn finalized_message_status(
+        &self,
+        leaf: H256,
+        finality_blocks: u32,
+    ) -> Result<MessageStatus, ChainCommunicationError>;
 }
@@ -1,10 +1,22 @@
 use std::fmt::Debug;
 
 use async_trait::async_trait;
+use ethers::core::types::{H256, U256};
 
-use crate::AbacusContract;
+use crate::{AbacusContract, ChainCommunicationError, TxOutcome};
 
 /// Interface for the InterchainGasPaymaster chain contract.
 /// Allows abstraction over different chains.
 #[async_trait]
-pub trait InterchainGasPaymaster: AbacusContract + Send + Sync + Debug {}
+pub trait InterchainGasPaymaster: AbacusContract + Send + Sync + Debug {
+    /// Pay `amount` of the native token for the interchain gas of the message
+    /// with leaf index `leaf_index` dispatched by `outbox` to
+    /// `destination_domain`.
+    async fn pay_gas_for(
+        &self,
+        outbox: H256,
+        leaf_index: u32,
+        destination_domain: u32,
+        amount: U256,
+    ) -> Result<TxOutcome, ChainCommunicationError>;
+}
@@ -14,13 +14,17 @@ use async_trait::async_trait;
 use ethers::prelude::Selector;
 use ethers::{
     contract::ContractError,
-    core::types::{TransactionReceipt, H256},
+    core::types::{TransactionReceipt, H256, U256},
     providers::{Middleware, ProviderError},
 };
 use eyre::Result;
 use std::error::Error as StdError;
 
-use crate::{db::DbError, utils::domain_hash, AbacusError};
+use crate::{
+    db::DbError,
+    utils::{domain_hash, revert_reason_from_error},
+    AbacusError,
+};
 
 pub use common::*;
 pub use encode::*;
@@ -38,18 +42,50 @@ pub struct TxOutcome {
     pub txid: H256,
     /// True if executed, false otherwise (reverted, etc.)
     pub executed: bool,
+    /// The amount of gas used by the transaction
+    pub gas_used: U256,
+    /// The price paid per unit of gas
+    pub gas_price: U256,
     // TODO: more? What can be abstracted across all chains?
 }
 
+impl TxOutcome {
+    /// The total cost of the transaction in the native token, i.e. gas used *
+    /// gas price
+    pub fn cost(&self) -> U256 {
+        self.gas_used.saturating_mul(self.gas_price)
+    }
+}
+
 impl From<TransactionReceipt> for TxOutcome {
     fn from(t: TransactionReceipt) -> Self {
         Self {
             txid: t.transaction_hash,
             executed: t.status.unwrap().low_u32() == 1,
+            gas_used: t.gas_used.unwrap_or_default(),
+            gas_price: t.effective_gas_price.unwrap_or_default(),
         }
     }
 }
 
+/// An estimate of the cost of submitting a transaction
+#[derive(Debug, Clone, Copy)]
+pub struct TxCostEstimate {
+    /// The estimated gas limit, including a margin for estimation inaccuracies
+    pub gas_limit: U256,
+    /// The estimated gas used, without the margin included in `gas_limit`
+    pub gas_estimate: U256,
+    /// The gas price the transaction would be submitted with
+    pub gas_price: U256,
+}
+
+impl TxCostEstimate {
+    /// The total estimated cost in the native token, i.e. gas limit * gas price
+    pub fn total_cost(&self) -> U256 {
+        self.gas_limit.saturating_mul(self.gas_price)
+    }
+}
+
 /// ChainCommunicationError contains errors returned when attempting to
 /// call a chain or dispatch a transaction
 #[derive(Debug, thiserror::Error)]
@@ -75,6 +111,17 @@ pub enum ChainCommunicationError {
     /// A transaction submission timed out
     #[error("Transaction submission timed out")]
     TransactionTimeout(),
+    /// A call or transaction reverted, with the decoded reason if available
+    #[error("Reverted: {0}")]
+    Reverted(String),
+    /// Simulating a call showed it would revert for a reason that retrying it
+    /// cannot change, so no transaction was sent
+    #[error("Reverted permanently: {0}")]
+    PermanentRevert(String),
+    /// The estimated gas of a transaction exceeds the max gas limit it may be
+    /// given, so no transaction was sent
+    #[error("Estimated gas {0} exceeds the max gas limit {1}")]
+    GasLimitExceeded(U256, U256),
 }
 
 impl<M> From<ContractError<M>> for ChainCommunicationError
@@ -82,7 +129,10 @@ where
     M: Middleware + 'static,
 {
     fn from(e: ContractError<M>) -> Self {
-        Self::ContractError(Box::new(e))
+        match revert_reason_from_error(&e.to_string()) {
+            Some(reason) => Self::Reverted(reason),
+            None => Self::ContractError(Box::new(e)),
+        }
     }
 }
 
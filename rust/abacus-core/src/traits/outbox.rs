@@ -9,6 +9,16 @@ use async_trait::async_trait;
 use ethers::core::types::H256;
 use eyre::Result;
 
+/// The result of dispatching a message
+#[derive(Debug, Clone, Copy)]
+pub struct DispatchOutcome {
+    /// The outcome of the dispatch transaction
+    pub outcome: TxOutcome,
+    /// The leaf index the outbox assigned to the message, as emitted in its
+    /// Dispatch event. None if the transaction was not executed.
+    pub leaf_index: Option<u32>,
+}
+
 /// Interface for the Outbox chain contract. Allows abstraction over different
 /// chains
 #[async_trait]
@@ -20,7 +30,8 @@ pub trait Outbox: AbacusCommon + Send + Sync + Debug {
     async fn count(&self) -> Result<u32, ChainCommunicationError>;
 
     /// Dispatch a message.
-    async fn dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError>;
+    async fn dispatch(&self, message: &Message)
+        -> Result<DispatchOutcome, ChainCommunicationError>;
 
     /// Caches the latest checkpoint.
     async fn cache_checkpoint(&self) -> Result<TxOutcome, ChainCommunicationError>;
@@ -1,22 +1,122 @@
 use std::fmt::Debug;
 
 use async_trait::async_trait;
+use ethers::core::types::{Address, H256, U256};
 use eyre::Result;
 
 use crate::{
     accumulator::merkle::Proof,
-    traits::{ChainCommunicationError, TxOutcome},
+    traits::{ChainCommunicationError, TxCostEstimate, TxOutcome},
     AbacusMessage, MultisigSignedCheckpoint,
 };
 
+/// Reasons the Inbox reverts processing with that retrying with the same
+/// checkpoint, message and proof cannot change
+pub const PERMANENT_PROCESS_REVERTS: &[&str] = &["!index", "!proof", "!destination"];
+
+/// The start of the reason given for a revert whose data could not be decoded
+pub const UNDECODED_REVERT_REASON: &str = "undecoded revert data";
+
+/// A message to process with a proof against a signed checkpoint, as one
+/// member of a batch
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessCall<'a> {
+    /// The checkpoint the message is proven against
+    pub multisig_signed_checkpoint: &'a MultisigSignedCheckpoint,
+    /// The message to process
+    pub message: &'a AbacusMessage,
+    /// The proof of the message against the checkpoint
+    pub proof: &'a Proof,
+}
+
 /// Interface for an InboxValidatorManager
 #[async_trait]
 pub trait InboxValidatorManager: Send + Sync + Debug {
-    /// Process a message with a proof against the provided signed checkpoint
+    /// The address of the InboxValidatorManager contract
+    fn contract_address(&self) -> Address;
+
+    /// Process a message with a proof against the provided signed checkpoint.
+    ///
+    /// The InboxValidatorManager contract verifies the checkpoint's quorum of
+    /// signatures on every call, as it has no way to record a checkpoint once
+    /// and process later messages against it. Saving this verification for
+    /// messages sharing a checkpoint requires a contract that does.
+    ///
+    /// The call is simulated before a transaction is sent. If it would revert,
+    /// no transaction is sent and the error has the decoded revert reason,
+    /// which is a `PermanentRevert` if retrying cannot change it.
+    ///
+    /// If `gas_limit` is None, the transaction's gas limit is the estimate plus
+    /// a margin for estimation inaccuracies.
     async fn process(
         &self,
         multisig_signed_checkpoint: &MultisigSignedCheckpoint,
         message: &AbacusMessage,
         proof: &Proof,
+        gas_limit: Option<U256>,
+    ) -> Result<TxOutcome, ChainCommunicationError>;
+
+    /// Estimate the gas limit and gas price of processing a message with a
+    /// proof against the provided signed checkpoint
+    async fn process_estimate_costs(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Result<TxCostEstimate, ChainCommunicationError>;
+
+    /// Get the calldata of a call processing a message with a proof against
+    /// the provided signed checkpoint
+    fn process_calldata(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Vec<u8>;
+
+    /// Simulate processing a batch of messages in a single call through the
+    /// Multicall3 contract at `multicall_address`, returning whether
+    /// processing each message would succeed
+    async fn simulate_batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+    ) -> Result<Vec<bool>, ChainCommunicationError>;
+
+    /// Process a batch of messages in a single transaction through the
+    /// Multicall3 contract at `multicall_address`. A member that fails does
+    /// not revert the batch, so whether each message was processed must be
+    /// checked on the inbox. As a member running out of gas fails the same
+    /// way, `gas_limit` should cover the members' individual gas limits.
+    async fn batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+        gas_limit: U256,
     ) -> Result<TxOutcome, ChainCommunicationError>;
+
+    /// The nonce of the next transaction from the account that submits process
+    /// transactions, as of the latest block
+    async fn submitter_nonce(&self) -> Result<U256, ChainCommunicationError>;
+
+    /// The native token balance of the account that submits process
+    /// transactions, as of the latest block
+    async fn submitter_balance(&self) -> Result<U256, ChainCommunicationError>;
+
+    /// Broadcast a transaction processing a message with the provided nonce,
+    /// gas limit and gas price, without waiting for it to be mined. Returns the
+    /// hash of the broadcast transaction. The nonce is never replaced, so the
+    /// caller alone manages the nonces of these transactions.
+    async fn send_process(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+        nonce: U256,
+        tx_cost: &TxCostEstimate,
+    ) -> Result<H256, ChainCommunicationError>;
+
+    /// The outcome of a previously broadcast transaction, or None if it has
+    /// not been mined
+    async fn tx_outcome(&self, txid: H256) -> Result<Option<TxOutcome>, ChainCommunicationError>;
 }
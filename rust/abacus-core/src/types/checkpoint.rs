@@ -198,7 +198,7 @@ pub struct SignedCheckpointWithSigner {
 }
 
 /// A checkpoint and multiple signatures
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct MultisigSignedCheckpoint {
     /// The checkpoint
     pub checkpoint: Checkpoint,
@@ -1,4 +1,5 @@
 use ethers::types::{H256, U256};
+use serde::Serialize;
 
 mod checkpoint;
 mod messages;
@@ -63,3 +64,283 @@ pub struct InterchainGasPaymentWithMeta {
     /// Metadata for the payment
     pub meta: InterchainGasPaymentMeta,
 }
+
+/// A message the relayer gave up on delivering after exhausting its retries,
+/// kept so operators can inspect and re-drive it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetter {
+    /// The index of the message's leaf in the merkle tree
+    pub leaf_index: u32,
+    /// The domain the message is destined for
+    pub destination: u32,
+    /// The number of failed processing attempts
+    pub num_attempts: u32,
+    /// Description of the error of the last attempt
+    pub last_error: String,
+    /// Unix timestamp, in seconds, of the first failed attempt
+    pub first_failure_at: u64,
+    /// Unix timestamp, in seconds, at which the message was given up on
+    pub dead_lettered_at: u64,
+}
+
+impl Encode for DeadLetter {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        let mut written = 0;
+        written += self.leaf_index.write_to(writer)?;
+        written += self.destination.write_to(writer)?;
+        written += self.num_attempts.write_to(writer)?;
+        written += (self.last_error.len() as u32).write_to(writer)?;
+        writer.write_all(self.last_error.as_bytes())?;
+        written += self.last_error.len();
+        written += self.first_failure_at.write_to(writer)?;
+        written += self.dead_lettered_at.write_to(writer)?;
+        Ok(written)
+    }
+}
+
+impl Decode for DeadLetter {
+    fn read_from<R>(reader: &mut R) -> Result<Self, AbacusError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        let leaf_index = u32::read_from(reader)?;
+        let destination = u32::read_from(reader)?;
+        let num_attempts = u32::read_from(reader)?;
+        let mut last_error = vec![0; u32::read_from(reader)? as usize];
+        reader.read_exact(&mut last_error)?;
+        Ok(Self {
+            leaf_index,
+            destination,
+            num_attempts,
+            last_error: String::from_utf8_lossy(&last_error).into_owned(),
+            first_failure_at: u64::read_from(reader)?,
+            dead_lettered_at: u64::read_from(reader)?,
+        })
+    }
+}
+
+/// The relayer's scheduling state for a message it has not delivered yet,
+/// kept so that retries and backoff survive restarts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSchedulingState {
+    /// The index of the message's leaf in the merkle tree
+    pub leaf_index: u32,
+    /// Unix timestamp, in seconds, at which the message was first queued
+    pub first_seen_at: u64,
+    /// The number of failed processing attempts
+    pub num_retries: u32,
+    /// The number of failed processing attempts that reverted, which are
+    /// counted towards the retry limit
+    pub num_reverts: u32,
+    /// Unix timestamp, in seconds, of the last failed attempt
+    pub last_attempt_at: Option<u64>,
+    /// Description of the error of the last failed attempt
+    pub last_error: Option<String>,
+    /// Unix timestamp, in seconds, of the first failed attempt since the
+    /// message's retries were last reset
+    pub first_failure_at: Option<u64>,
+    /// Unix timestamp, in seconds, before which the message should not be
+    /// attempted again
+    pub next_attempt_at: Option<u64>,
+}
+
+impl Encode for MessageSchedulingState {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        // Timestamps are never 0, so 0 encodes None
+        let last_error = self.last_error.as_deref().unwrap_or_default();
+        let mut written = 0;
+        written += self.leaf_index.write_to(writer)?;
+        written += self.first_seen_at.write_to(writer)?;
+        written += self.num_retries.write_to(writer)?;
+        written += self.num_reverts.write_to(writer)?;
+        written += self.last_attempt_at.unwrap_or_default().write_to(writer)?;
+        written += (last_error.len() as u32).write_to(writer)?;
+        writer.write_all(last_error.as_bytes())?;
+        written += last_error.len();
+        written += self.first_failure_at.unwrap_or_default().write_to(writer)?;
+        written += self.next_attempt_at.unwrap_or_default().write_to(writer)?;
+        Ok(written)
+    }
+}
+
+impl Decode for MessageSchedulingState {
+    fn read_from<R>(reader: &mut R) -> Result<Self, AbacusError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        let timestamp = |reader: &mut R| -> Result<Option<u64>, AbacusError> {
+            Ok(Some(u64::read_from(reader)?).filter(|t| *t != 0))
+        };
+        let leaf_index = u32::read_from(reader)?;
+        let first_seen_at = u64::read_from(reader)?;
+        let num_retries = u32::read_from(reader)?;
+        let num_reverts = u32::read_from(reader)?;
+        let last_attempt_at = timestamp(reader)?;
+        let mut last_error = vec![0; u32::read_from(reader)? as usize];
+        reader.read_exact(&mut last_error)?;
+        Ok(Self {
+            leaf_index,
+            first_seen_at,
+            num_retries,
+            num_reverts,
+            last_attempt_at,
+            last_error: Some(String::from_utf8_lossy(&last_error).into_owned())
+                .filter(|e| !e.is_empty()),
+            first_failure_at: timestamp(reader)?,
+            next_attempt_at: timestamp(reader)?,
+        })
+    }
+}
+
+/// The expense of relaying a message: the gas spent by every `process`
+/// transaction that landed for it, reverted ones included, together with the
+/// interchain gas payment received for it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayExpense {
+    /// The index of the message's leaf in the merkle tree
+    pub leaf_index: u32,
+    /// The domain the message is destined for
+    pub destination: u32,
+    /// The hash of the latest `process` transaction
+    pub txid: H256,
+    /// The number of `process` transactions included on chain
+    pub num_transactions: u32,
+    /// The total gas used by the `process` transactions
+    pub gas_used: U256,
+    /// The effective gas price of the latest `process` transaction
+    pub gas_price: U256,
+    /// The total cost of the `process` transactions, in Inbox native token wei
+    pub cost: U256,
+    /// The interchain gas payment received for the message, in Outbox native
+    /// token wei
+    pub gas_payment: U256,
+}
+
+impl Encode for RelayExpense {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        let mut written = 0;
+        written += self.leaf_index.write_to(writer)?;
+        written += self.destination.write_to(writer)?;
+        written += self.txid.write_to(writer)?;
+        written += self.num_transactions.write_to(writer)?;
+        written += self.gas_used.write_to(writer)?;
+        written += self.gas_price.write_to(writer)?;
+        written += self.cost.write_to(writer)?;
+        written += self.gas_payment.write_to(writer)?;
+        Ok(written)
+    }
+}
+
+impl Decode for RelayExpense {
+    fn read_from<R>(reader: &mut R) -> Result<Self, AbacusError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        Ok(Self {
+            leaf_index: u32::read_from(reader)?,
+            destination: u32::read_from(reader)?,
+            txid: H256::read_from(reader)?,
+            num_transactions: u32::read_from(reader)?,
+            gas_used: U256::read_from(reader)?,
+            gas_price: U256::read_from(reader)?,
+            cost: U256::read_from(reader)?,
+            gas_payment: U256::read_from(reader)?,
+        })
+    }
+}
+
+/// The gas used by `process` transactions delivering messages to a recipient,
+/// from which the gas limit of delivering its next messages is learned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipientGasUsage {
+    /// The number of messages whose gas used has been recorded
+    pub num_processed: u32,
+    /// The most gas used by a recent message. It decays by 1/16 with each
+    /// message recorded, so that it follows the recipient's recent usage.
+    pub peak_gas_used: U256,
+}
+
+impl RecipientGasUsage {
+    /// Record the gas used by a `process` transaction delivering one message
+    pub fn record(&mut self, gas_used: U256) {
+        self.num_processed += 1;
+        let decayed = self.peak_gas_used - self.peak_gas_used / 16;
+        self.peak_gas_used = decayed.max(gas_used);
+    }
+}
+
+impl Encode for RecipientGasUsage {
+    fn write_to<W>(&self, writer: &mut W) -> std::io::Result<usize>
+    where
+        W: std::io::Write,
+    {
+        let mut written = 0;
+        written += self.num_processed.write_to(writer)?;
+        written += self.peak_gas_used.write_to(writer)?;
+        Ok(written)
+    }
+}
+
+impl Decode for RecipientGasUsage {
+    fn read_from<R>(reader: &mut R) -> Result<Self, AbacusError>
+    where
+        R: std::io::Read,
+        Self: Sized,
+    {
+        Ok(Self {
+            num_processed: u32::read_from(reader)?,
+            peak_gas_used: U256::read_from(reader)?,
+        })
+    }
+}
+
+/// The profit or loss of relaying messages from an Outbox to one destination.
+///
+/// Revenue is denominated in Outbox native token wei and cost in Inbox native
+/// token wei, so the two are only directly comparable when both chains share a
+/// native token.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteProfitAndLoss {
+    /// The destination domain of the route
+    pub destination: u32,
+    /// The number of messages relayed
+    pub num_messages: u32,
+    /// The total gas used relaying the messages
+    pub gas_used: U256,
+    /// The total interchain gas payments received for the messages
+    pub revenue: U256,
+    /// The total cost of relaying the messages
+    pub cost: U256,
+}
+
+impl RouteProfitAndLoss {
+    /// Add a message's relay expense to the route's totals
+    pub fn add(&mut self, expense: &RelayExpense) {
+        self.num_messages += 1;
+        self.gas_used = self.gas_used.saturating_add(expense.gas_used);
+        self.revenue = self.revenue.saturating_add(expense.gas_payment);
+        self.cost = self.cost.saturating_add(expense.cost);
+    }
+
+    /// Whether the revenue covered the cost
+    pub fn is_profitable(&self) -> bool {
+        self.revenue >= self.cost
+    }
+}
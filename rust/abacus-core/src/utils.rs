@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use ethers::abi::{decode, ParamType, Token};
 use ethers::core::types::H256;
 use eyre::{bail, Report};
 use sha3::{Digest, Keccak256};
@@ -28,6 +29,54 @@ pub fn domain_hash(domain: u32) -> H256 {
     )
 }
 
+/// Selector of the `Error(string)` revert data emitted by `require` and `revert`
+const ERROR_STRING_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of the `Panic(uint256)` revert data emitted by failed asserts, overflows, etc.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Decodes the reason from the return data of a reverted call. Returns `None`
+/// if the data is neither an `Error(string)` nor a `Panic(uint256)`.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_STRING_SELECTOR {
+        match decode(&[ParamType::String], args).ok()?.pop()? {
+            Token::String(reason) => Some(reason),
+            _ => None,
+        }
+    } else if selector == PANIC_SELECTOR {
+        match decode(&[ParamType::Uint(256)], args).ok()?.pop()? {
+            Token::Uint(code) => Some(format!("Panic({:#x})", code)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Returns the revert reason if an error returned by a node describes a
+/// reverted call or transaction. Nodes include the raw revert data in the
+/// error, which is decoded if present; otherwise the whole description is
+/// used as the reason.
+pub fn revert_reason_from_error(description: &str) -> Option<String> {
+    if !description.contains("revert") {
+        return None;
+    }
+    let encoded_reason = description
+        .find(&format!("0x{}", hex::encode(ERROR_STRING_SELECTOR)))
+        .or_else(|| description.find(&format!("0x{}", hex::encode(PANIC_SELECTOR))))
+        .and_then(|start| {
+            let data: String = description[start + 2..]
+                .chars()
+                .take_while(char::is_ascii_hexdigit)
+                .collect();
+            decode_revert_reason(&hex::decode(data).ok()?)
+        });
+    Some(encoded_reason.unwrap_or_else(|| description.to_owned()))
+}
+
 /// A Hex String of length `N` representing bytes of length `N / 2`
 #[derive(Debug, Clone)]
 pub struct HexString<const N: usize>(String);
@@ -75,3 +124,37 @@ impl<'de, const N: usize> serde::Deserialize<'de> for HexString<N> {
         Self::from_string(s).map_err(serde::de::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Revert data of `require(false, "!pending")`
+    const ERROR_STRING_DATA: &str = "08c379a0000000000000000000000000000000000000000000000000000000000000002000000000000000000000000000000000000000000000000000000000000000082170656e64696e67000000000000000000000000000000000000000000000000";
+
+    #[test]
+    fn decodes_revert_reason() {
+        assert_eq!(
+            decode_revert_reason(&hex::decode(ERROR_STRING_DATA).unwrap()),
+            Some("!pending".to_owned())
+        );
+        assert_eq!(decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]), None);
+    }
+
+    #[test]
+    fn extracts_revert_reason_from_error() {
+        let description = format!(
+            "(code: 3, message: execution reverted: !pending, data: Some(String(\"0x{}\")))",
+            ERROR_STRING_DATA
+        );
+        assert_eq!(
+            revert_reason_from_error(&description),
+            Some("!pending".to_owned())
+        );
+        assert_eq!(
+            revert_reason_from_error("execution reverted"),
+            Some("execution reverted".to_owned())
+        );
+        assert_eq!(revert_reason_from_error("connection refused"), None);
+    }
+}
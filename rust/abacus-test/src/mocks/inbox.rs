@@ -28,6 +28,12 @@ mock! {
 
         pub fn _message_status(&self, leaf: H256) -> Result<MessageStatus, ChainCommunicationError> {}
 
+        pub fn _finalized_message_status(
+            &self,
+            leaf: H256,
+            finality_blocks: u32,
+        ) -> Result<MessageStatus, ChainCommunicationError> {}
+
         // AbacusContract
         pub fn _chain_name(&self) -> &str {}
     }
@@ -48,6 +54,14 @@ impl Inbox for MockInboxContract {
     async fn message_status(&self, leaf: H256) -> Result<MessageStatus, ChainCommunicationError> {
         self._message_status(leaf)
     }
+
+    async fn finalized_message_status(
+        &self,
+        leaf: H256,
+        finality_blocks: u32,
+    ) -> Result<MessageStatus, ChainCommunicationError> {
+        self._finalized_message_status(leaf, finality_blocks)
+    }
 }
 
 impl AbacusContract for MockInboxContract {
@@ -24,7 +24,7 @@ mock! {
             tree_index: usize,
         ) -> Result<Option<H256>, ChainCommunicationError> {}
 
-        pub fn _dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError> {}
+        pub fn _dispatch(&self, message: &Message) -> Result<DispatchOutcome, ChainCommunicationError> {}
 
         pub fn _count(&self) -> Result<u32, ChainCommunicationError> {}
 
@@ -56,7 +56,10 @@ impl std::fmt::Debug for MockOutboxContract {
 
 #[async_trait]
 impl Outbox for MockOutboxContract {
-    async fn dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError> {
+    async fn dispatch(
+        &self,
+        message: &Message,
+    ) -> Result<DispatchOutcome, ChainCommunicationError> {
         self._dispatch(message)
     }
 
@@ -28,10 +28,13 @@ where
 
 #[cfg(test)]
 mod test {
-    use ethers::types::H256;
+    use ethers::types::{H256, U256};
 
     use abacus_core::{
-        accumulator::merkle::Proof, db::AbacusDB, AbacusMessage, Encode, RawCommittedMessage,
+        accumulator::merkle::Proof, db::AbacusDB, AbacusMessage, DeadLetter, Encode,
+        InterchainGasPayment, InterchainGasPaymentMeta, InterchainGasPaymentWithMeta,
+        MessageSchedulingState, RawCommittedMessage, RecipientGasUsage, RouteProfitAndLoss,
+        TxOutcome,
     };
 
     use super::*;
@@ -90,4 +93,175 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn db_stores_lists_and_removes_dead_letters() {
+        run_test_db(|db| async move {
+            let dead_letter = |leaf_index| DeadLetter {
+                leaf_index,
+                destination: 12,
+                num_attempts: 5,
+                last_error: "Reverted: !pending".to_owned(),
+                first_failure_at: 1_650_000_000,
+                dead_lettered_at: 1_650_000_100,
+            };
+            // Dead letters of another outbox must not be listed
+            AbacusDB::new("outbox_2", db.clone())
+                .store_dead_letter(&dead_letter(1))
+                .unwrap();
+
+            let db = AbacusDB::new("outbox_1", db);
+            db.store_dead_letter(&dead_letter(300)).unwrap();
+            db.store_dead_letter(&dead_letter(2)).unwrap();
+
+            assert_eq!(db.retrieve_dead_letter(2).unwrap(), Some(dead_letter(2)));
+            assert_eq!(
+                db.dead_letters().unwrap(),
+                vec![dead_letter(2), dead_letter(300)]
+            );
+
+            db.remove_dead_letter(2).unwrap();
+            assert_eq!(db.retrieve_dead_letter(2).unwrap(), None);
+            assert_eq!(db.dead_letters().unwrap(), vec![dead_letter(300)]);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_stores_and_removes_scheduling_states() {
+        run_test_db(|db| async move {
+            let db = AbacusDB::new("outbox_1", db);
+            let fresh = MessageSchedulingState {
+                leaf_index: 7,
+                first_seen_at: 1_650_000_000,
+                num_retries: 0,
+                num_reverts: 0,
+                last_attempt_at: None,
+                last_error: None,
+                first_failure_at: None,
+                next_attempt_at: None,
+            };
+            db.store_scheduling_state(&fresh).unwrap();
+            assert_eq!(
+                db.retrieve_scheduling_state(7).unwrap(),
+                Some(fresh.clone())
+            );
+
+            let failing = MessageSchedulingState {
+                num_retries: 3,
+                num_reverts: 2,
+                last_attempt_at: Some(1_650_000_100),
+                last_error: Some("Reverted: !pending".to_owned()),
+                first_failure_at: Some(1_650_000_050),
+                next_attempt_at: Some(1_650_000_160),
+                ..fresh
+            };
+            db.store_scheduling_state(&failing).unwrap();
+            assert_eq!(db.retrieve_scheduling_state(7).unwrap(), Some(failing));
+
+            db.remove_scheduling_state(7).unwrap();
+            assert_eq!(db.retrieve_scheduling_state(7).unwrap(), None);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_aggregates_relay_expenses_per_route() {
+        run_test_db(|db| async move {
+            let db = AbacusDB::new("outbox_1", db);
+            db.process_gas_payment(&InterchainGasPaymentWithMeta {
+                payment: InterchainGasPayment {
+                    leaf_index: 1,
+                    amount: 500_000u64.into(),
+                },
+                meta: InterchainGasPaymentMeta {
+                    transaction_hash: H256::repeat_byte(1),
+                    log_index: U256::zero(),
+                },
+            })
+            .unwrap();
+            let outcome = |txid, executed, gas_used: u64| TxOutcome {
+                txid: H256::repeat_byte(txid),
+                executed,
+                gas_used: gas_used.into(),
+                gas_price: 2u64.into(),
+            };
+
+            // Leaf 1 reverted once before being processed
+            db.record_relay_expense(1, 12, &outcome(1, false, 50_000))
+                .unwrap();
+            let expense = db
+                .record_relay_expense(1, 12, &outcome(2, true, 100_000))
+                .unwrap();
+            assert_eq!(expense.num_transactions, 2);
+            assert_eq!(expense.txid, H256::repeat_byte(2));
+            assert_eq!(expense.cost, 300_000u64.into());
+            assert_eq!(expense.gas_payment, 500_000u64.into());
+            assert_eq!(db.retrieve_relay_expense(1).unwrap(), Some(expense));
+
+            db.record_relay_expense(2, 12, &outcome(3, true, 100_000))
+                .unwrap();
+            db.record_relay_expense(3, 13, &outcome(4, true, 80_000))
+                .unwrap();
+
+            let routes = db.route_profit_and_loss().unwrap();
+            assert_eq!(
+                routes,
+                vec![
+                    RouteProfitAndLoss {
+                        destination: 12,
+                        num_messages: 2,
+                        gas_used: 250_000u64.into(),
+                        revenue: 500_000u64.into(),
+                        cost: 500_000u64.into(),
+                    },
+                    RouteProfitAndLoss {
+                        destination: 13,
+                        num_messages: 1,
+                        gas_used: 80_000u64.into(),
+                        revenue: U256::zero(),
+                        cost: 160_000u64.into(),
+                    },
+                ]
+            );
+            assert!(routes[0].is_profitable());
+            assert!(!routes[1].is_profitable());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn db_learns_recipient_gas_usage() {
+        run_test_db(|db| async move {
+            let db = AbacusDB::new("outbox_1", db);
+            let recipient = H256::repeat_byte(9);
+            assert_eq!(
+                db.retrieve_recipient_gas_usage(12, recipient).unwrap(),
+                None
+            );
+
+            db.record_recipient_gas_used(12, recipient, 160_000u64.into())
+                .unwrap();
+            let usage = db
+                .record_recipient_gas_used(12, recipient, 80_000u64.into())
+                .unwrap();
+            // The peak decays by 1/16 rather than dropping to the latest usage
+            assert_eq!(
+                usage,
+                RecipientGasUsage {
+                    num_processed: 2,
+                    peak_gas_used: 150_000u64.into(),
+                }
+            );
+            assert_eq!(
+                db.retrieve_recipient_gas_usage(12, recipient).unwrap(),
+                Some(usage)
+            );
+            assert_eq!(
+                db.retrieve_recipient_gas_usage(13, recipient).unwrap(),
+                None
+            );
+        })
+        .await;
+    }
 }
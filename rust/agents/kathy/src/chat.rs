@@ -0,0 +1,163 @@
+use std::ops::RangeInclusive;
+
+use ethers::types::{Address, H256};
+use eyre::{bail, eyre, Report, Result};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::Deserialize;
+
+/// How the recipients and bodies of dispatched messages are generated.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChatGenConf {
+    /// The recipients messages are sent to in turn, as hex addresses. If empty, each message
+    /// is sent to a random recipient.
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// How message bodies are generated.
+    #[serde(default)]
+    pub body: BodyConf,
+}
+
+/// How message bodies are generated. Lengths are specified as strings so that they can be
+/// set via env vars.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum BodyConf {
+    /// The same UTF-8 message every time.
+    Static { message: String },
+    /// Random alphanumeric text of `length` bytes.
+    Random { length: String },
+    /// Zero bytes, between `min_length` and `max_length` of them, e.g. to exercise the
+    /// processing cost of large bodies.
+    #[serde(rename_all = "camelCase")]
+    Sized {
+        min_length: String,
+        max_length: String,
+    },
+}
+
+impl Default for BodyConf {
+    fn default() -> Self {
+        Self::Random {
+            length: "32".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum BodyGenerator {
+    Static(Vec<u8>),
+    Random(usize),
+    Sized(RangeInclusive<usize>),
+}
+
+/// Generates the recipients and bodies of dispatched messages.
+#[derive(Debug, Clone)]
+pub(crate) struct ChatGenerator {
+    recipients: Vec<H256>,
+    next_recipient: usize,
+    body: BodyGenerator,
+}
+
+impl TryFrom<&ChatGenConf> for ChatGenerator {
+    type Error = Report;
+
+    fn try_from(conf: &ChatGenConf) -> Result<Self> {
+        let recipients = conf
+            .recipients
+            .iter()
+            .map(|recipient| {
+                recipient
+                    .parse::<Address>()
+                    .map(H256::from)
+                    .map_err(|e| eyre!("Invalid recipient {recipient}: {e}"))
+            })
+            .collect::<Result<_>>()?;
+        let parse_length = |length: &str| {
+            length
+                .parse::<usize>()
+                .map_err(|e| eyre!("Invalid body length {length}: {e}"))
+        };
+        let body = match &conf.body {
+            BodyConf::Static { message } => BodyGenerator::Static(message.as_bytes().to_vec()),
+            BodyConf::Random { length } => BodyGenerator::Random(parse_length(length)?),
+            BodyConf::Sized {
+                min_length,
+                max_length,
+            } => {
+                let lengths = parse_length(min_length)?..=parse_length(max_length)?;
+                if lengths.is_empty() {
+                    bail!("Body minLength {min_length} exceeds maxLength {max_length}");
+                }
+                BodyGenerator::Sized(lengths)
+            }
+        };
+        Ok(Self {
+            recipients,
+            next_recipient: 0,
+            body,
+        })
+    }
+}
+
+impl ChatGenerator {
+    /// The recipient of the next message.
+    pub fn gen_recipient(&mut self) -> H256 {
+        if self.recipients.is_empty() {
+            return H256::from(thread_rng().gen::<[u8; 32]>());
+        }
+        let recipient = self.recipients[self.next_recipient];
+        self.next_recipient = (self.next_recipient + 1) % self.recipients.len();
+        recipient
+    }
+
+    /// The body of the next message.
+    pub fn gen_body(&self) -> Vec<u8> {
+        match &self.body {
+            BodyGenerator::Static(message) => message.clone(),
+            BodyGenerator::Random(length) => thread_rng()
+                .sample_iter(&Alphanumeric)
+                .take(*length)
+                .collect(),
+            BodyGenerator::Sized(lengths) => vec![0; thread_rng().gen_range(lengths.clone())],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::H256;
+
+    use super::{BodyConf, ChatGenConf, ChatGenerator};
+
+    #[test]
+    fn generates_configured_recipients_and_bodies() {
+        let mut generator = ChatGenerator::try_from(&ChatGenConf {
+            recipients: vec![
+                "0x0000000000000000000000000000000000000001".into(),
+                "0x0000000000000000000000000000000000000002".into(),
+            ],
+            body: BodyConf::Sized {
+                min_length: "10".into(),
+                max_length: "20".into(),
+            },
+        })
+        .unwrap();
+        let recipients: Vec<_> = (0..3).map(|_| generator.gen_recipient()).collect();
+        assert_eq!(recipients, [1, 2, 1].map(H256::from_low_u64_be).to_vec());
+        for _ in 0..10 {
+            let body = generator.gen_body();
+            assert!((10..=20).contains(&body.len()));
+            assert!(body.iter().all(|b| *b == 0));
+        }
+
+        let invalid = ChatGenConf {
+            recipients: vec![],
+            body: BodyConf::Sized {
+                min_length: "20".into(),
+                max_length: "10".into(),
+            },
+        };
+        assert!(ChatGenerator::try_from(&invalid).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ethers::types::{H256, U256};
+use eyre::{bail, eyre, Result};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{info, info_span, instrument::Instrumented, warn, Instrument};
+
+use abacus_base::{AbacusAgentCore, Agent};
+use abacus_core::{AbacusContract, InterchainGasPaymaster, Message, Outbox};
+
+use crate::chat::ChatGenerator;
+use crate::settings::KathySettings;
+
+/// An agent that dispatches generated messages to the outbox
+#[derive(Debug)]
+pub struct Kathy {
+    interval: u64,
+    rounds: Option<u32>,
+    /// The domains messages are dispatched to in turn.
+    destinations: Vec<u32>,
+    generator: ChatGenerator,
+    /// The outbox address paid for in interchain gas payments.
+    outbox_address: H256,
+    gas_payment: Option<U256>,
+    core: AbacusAgentCore,
+}
+
+impl AsRef<AbacusAgentCore> for Kathy {
+    fn as_ref(&self) -> &AbacusAgentCore {
+        &self.core
+    }
+}
+
+#[async_trait]
+#[allow(clippy::unit_arg)]
+impl Agent for Kathy {
+    const AGENT_NAME: &'static str = "kathy";
+
+    type Settings = KathySettings;
+
+    async fn from_settings(settings: Self::Settings) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let interval = settings
+            .interval
+            .parse()
+            .map_err(|e| eyre!("Invalid interval {}: {e}", settings.interval))?;
+        let rounds = settings
+            .rounds
+            .as_deref()
+            .map(|rounds| {
+                rounds
+                    .parse()
+                    .map_err(|e| eyre!("Invalid rounds {rounds}: {e}"))
+            })
+            .transpose()?;
+
+        let inboxes = &settings.as_ref().inboxes;
+        let destination_names: Vec<String> = match &settings.destinations {
+            Some(destinations) => serde_json::from_str(destinations)
+                .map_err(|e| eyre!("Invalid destinations {destinations}: {e}"))?,
+            None => {
+                let mut names: Vec<_> = inboxes
+                    .iter()
+                    .filter(|(_, setup)| setup.disabled.is_none())
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                names.sort();
+                names
+            }
+        };
+        let destinations = destination_names
+            .iter()
+            .map(|name| {
+                let setup = inboxes
+                    .get(name)
+                    .ok_or_else(|| eyre!("No inbox configured for destination {name}"))?;
+                setup
+                    .domain
+                    .parse()
+                    .map_err(|e| eyre!("Invalid domain {} of {name}: {e}", setup.domain))
+            })
+            .collect::<Result<Vec<u32>>>()?;
+        if destinations.is_empty() {
+            bail!("No destinations to dispatch messages to");
+        }
+
+        let generator = ChatGenerator::try_from(&settings.chat)?;
+
+        let outbox_address = settings
+            .as_ref()
+            .outbox
+            .addresses
+            .outbox
+            .parse::<ethers::types::Address>()?
+            .into();
+        let gas_payment = settings
+            .gaspayment
+            .as_deref()
+            .map(|payment| {
+                U256::from_dec_str(payment).map_err(|e| eyre!("Invalid gaspayment {payment}: {e}"))
+            })
+            .transpose()?;
+
+        let core = settings
+            .as_ref()
+            .try_into_abacus_core(Self::AGENT_NAME, false)
+            .await?;
+        if gas_payment.is_some() && core.interchain_gas_paymaster.is_none() {
+            bail!("An interchain gas paymaster is required to pay gaspayment");
+        }
+
+        Ok(Self {
+            interval,
+            rounds,
+            destinations,
+            generator,
+            outbox_address,
+            gas_payment,
+            core,
+        })
+    }
+}
+
+impl Kathy {
+    pub fn run(&self) -> Instrumented<JoinHandle<Result<()>>> {
+        let outbox = self.outbox();
+        let span = info_span!("Kathy", outbox = outbox.chain_name());
+        let paymaster = self.interchain_gas_paymaster();
+        let interval = Duration::from_secs(self.interval);
+        let rounds = self.rounds;
+        let destinations = self.destinations.clone();
+        let mut generator = self.generator.clone();
+        let outbox_address = self.outbox_address;
+        let gas_payment = self.gas_payment;
+
+        let chat = tokio::spawn(async move {
+            let mut round = 0;
+            while rounds.map_or(true, |rounds| round < rounds) {
+                for &destination in destinations.iter() {
+                    let message = Message {
+                        destination,
+                        recipient: generator.gen_recipient(),
+                        body: generator.gen_body(),
+                    };
+                    let dispatched = match outbox.dispatch(&message).await {
+                        Ok(dispatched) => dispatched,
+                        Err(e) => {
+                            warn!(destination, error = ?e, "Failed to dispatch message");
+                            sleep(interval).await;
+                            continue;
+                        }
+                    };
+                    let txid = dispatched.outcome.txid;
+                    match dispatched.leaf_index {
+                        None if !dispatched.outcome.executed => {
+                            warn!(destination, ?txid, "Dispatch transaction reverted");
+                        }
+                        None => {
+                            warn!(destination, ?txid, "No Dispatch event in dispatch receipt");
+                        }
+                        Some(leaf_index) => {
+                            info!(
+                                leaf_index,
+                                destination,
+                                recipient = ?message.recipient,
+                                length = message.body.len(),
+                                ?txid,
+                                "Dispatched message"
+                            );
+                            if let (Some(paymaster), Some(amount)) = (&paymaster, gas_payment) {
+                                match paymaster
+                                    .paymaster()
+                                    .pay_gas_for(outbox_address, leaf_index, destination, amount)
+                                    .await
+                                {
+                                    Ok(outcome) => {
+                                        info!(leaf_index, %amount, txid = ?outcome.txid, "Paid interchain gas");
+                                    }
+                                    Err(e) => {
+                                        warn!(leaf_index, error = ?e, "Failed to pay interchain gas");
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    sleep(interval).await;
+                }
+                round += 1;
+            }
+            info!(rounds = round, "Dispatched all rounds of messages");
+            Ok(())
+        })
+        .instrument(span);
+
+        self.run_all(vec![chat])
+    }
+}
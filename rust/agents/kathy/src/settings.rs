@@ -0,0 +1,23 @@
+//! Configuration
+
+use abacus_base::decl_settings;
+
+use crate::chat::ChatGenConf;
+
+decl_settings!(Kathy {
+    /// The interval between dispatched messages in seconds
+    interval: String,
+    /// This is optional. The number of rounds to run, each dispatching a message to every
+    /// destination. If not provided, messages are dispatched until the agent is stopped.
+    rounds: Option<String>,
+    /// This is optional. A JSON list of the names of the inbox chains messages are dispatched
+    /// to in turn. If not provided, messages are dispatched to every inbox chain.
+    destinations: Option<String>,
+    /// This is optional. How the recipients and bodies of messages are generated. If not
+    /// provided, random bodies are sent to random recipients.
+    #[serde(default)]
+    chat: ChatGenConf,
+    /// This is optional. The amount of origin chain wei paid to the interchain gas paymaster
+    /// for each message. If not provided, no interchain gas is paid.
+    gaspayment: Option<String>,
+});
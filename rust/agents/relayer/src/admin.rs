@@ -0,0 +1,507 @@
+//! An HTTP API, served on localhost only, that lets operators inspect the
+//! relayer's queues and messages and control submission.
+//!
+//! Leaf indices are only unique within an origin, so messages are addressed by
+//! the name of their origin's outbox chain as well.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use abacus_core::db::{AbacusDB, DbError};
+use abacus_core::{
+    CommittedMessage, DeadLetter, MessageSchedulingState, MultisigSignedCheckpoint,
+    RawCommittedMessage, RelayExpense,
+};
+use ethers::types::{H256, U256};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use warp::http::StatusCode;
+use warp::reply::{Json, WithStatus};
+use warp::Filter;
+
+use crate::msg::control::{QueueSnapshot, SubmitterCommand, SubmitterHandle};
+use crate::relay_lists::RelayListsUpdater;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LeafIndicesRequest {
+    leaf_indices: Vec<u32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CommandResponse {
+    accepted: Vec<u32>,
+    /// Leaf indices that are unknown or whose destination is not served by a
+    /// submitter accepting commands.
+    not_accepted: Vec<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageInfo {
+    leaf_index: u32,
+    hash: H256,
+    origin: u32,
+    sender: H256,
+    destination: u32,
+    recipient: H256,
+    /// Whether processing of the message has been committed as final.
+    processed: bool,
+    gas_payment: U256,
+    /// The submitter queue holding the message, if any.
+    queue: Option<&'static str>,
+    dead_letter: Option<DeadLetter>,
+    scheduling_state: Option<MessageSchedulingState>,
+    relay_expense: Option<RelayExpense>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GasPaymentInfo {
+    leaf_index: u32,
+    amount: U256,
+}
+
+/// The messages of an origin served by the admin API.
+#[derive(Debug)]
+pub(crate) struct AdminOrigin {
+    db: AbacusDB,
+    /// The handles of the submitters delivering the origin's messages, keyed by
+    /// inbox name.
+    submitters: BTreeMap<String, SubmitterHandle>,
+    signed_checkpoint: watch::Receiver<Option<MultisigSignedCheckpoint>>,
+}
+
+impl AdminOrigin {
+    pub(crate) fn new(
+        db: AbacusDB,
+        signed_checkpoint: watch::Receiver<Option<MultisigSignedCheckpoint>>,
+    ) -> Self {
+        Self {
+            db,
+            submitters: BTreeMap::new(),
+            signed_checkpoint,
+        }
+    }
+
+    /// Serve the submitter delivering the origin's messages to an inbox.
+    pub(crate) fn add_submitter(&mut self, inbox: String, handle: SubmitterHandle) {
+        self.submitters.insert(inbox, handle);
+    }
+
+    fn message_info(
+        &self,
+        message: Result<Option<RawCommittedMessage>, DbError>,
+    ) -> WithStatus<Json> {
+        let message = match message.map(|raw| raw.map(CommittedMessage::try_from)) {
+            Ok(Some(Ok(message))) => message,
+            Ok(Some(Err(e))) => return error(e),
+            Ok(None) => return not_found("Unknown message"),
+            Err(e) => return error(e),
+        };
+        let leaf_index = message.leaf_index;
+        let info = || -> Result<MessageInfo> {
+            Ok(MessageInfo {
+                leaf_index,
+                hash: message.to_leaf(),
+                origin: message.message.origin,
+                sender: message.message.sender,
+                destination: message.message.destination,
+                recipient: message.message.recipient,
+                processed: self
+                    .db
+                    .retrieve_leaf_processing_status(leaf_index)?
+                    .unwrap_or(false),
+                gas_payment: self.db.retrieve_gas_payment_for_leaf(leaf_index)?,
+                queue: self
+                    .submitter_for(message.message.destination)
+                    .and_then(|handle| handle.snapshot.borrow().queue_of(leaf_index)),
+                dead_letter: self.db.retrieve_dead_letter(leaf_index)?,
+                scheduling_state: self.db.retrieve_scheduling_state(leaf_index)?,
+                relay_expense: self.db.retrieve_relay_expense(leaf_index)?,
+            })
+        };
+        reply(info())
+    }
+
+    fn submitter_for(&self, destination: u32) -> Option<&SubmitterHandle> {
+        self.submitters
+            .values()
+            .find(|handle| handle.domain == destination)
+    }
+
+    fn message_destination(&self, leaf_index: u32) -> Result<Option<u32>> {
+        self.db
+            .message_by_leaf_index(leaf_index)?
+            .map(|raw| Ok(CommittedMessage::try_from(raw)?.message.destination))
+            .transpose()
+    }
+
+    /// Send a command for each requested message to the submitter for its
+    /// destination.
+    fn command_messages(
+        &self,
+        request: LeafIndicesRequest,
+        command: impl Fn(u32) -> SubmitterCommand,
+        destination_of: impl Fn(u32) -> Result<Option<u32>>,
+    ) -> WithStatus<Json> {
+        let mut response = CommandResponse::default();
+        for leaf_index in request.leaf_indices {
+            let sent = match destination_of(leaf_index) {
+                Ok(Some(destination)) => self.submitter_for(destination).map_or(false, |handle| {
+                    handle.commands.send(command(leaf_index)).is_ok()
+                }),
+                Ok(None) => false,
+                Err(e) => {
+                    warn!(leaf_index, error = ?e, "Failed to look up message destination");
+                    false
+                }
+            };
+            if sent {
+                response.accepted.push(leaf_index);
+            } else {
+                response.not_accepted.push(leaf_index);
+            }
+        }
+        ok(&response)
+    }
+}
+
+#[derive(Debug)]
+struct AdminState {
+    /// The bearer token required by POST endpoints, which are disabled if it
+    /// is not set.
+    token: Option<String>,
+    /// The served origins, keyed by outbox name.
+    origins: BTreeMap<String, AdminOrigin>,
+    relay_lists: RelayListsUpdater,
+}
+
+/// Serves the admin API on `127.0.0.1:{port}`:
+///
+/// * `GET /queues` lists the run and wait queues of each submitter, keyed by
+///   origin and inbox.
+/// * `GET /origins/{origin}/messages/{leafIndex}` and
+///   `GET /origins/{origin}/messages/by_hash/{hash}` show a message and its
+///   delivery status.
+/// * `GET /origins/{origin}/gas_payments/{leafIndex}` shows the gas paid for a
+///   message.
+/// * `GET /origins/{origin}/checkpoint` shows the latest multisig signed
+///   checkpoint.
+/// * `GET /origins/{origin}/dead_letters` lists all dead letters.
+/// * `GET /origins/{origin}/profit_and_loss` lists the relaying profit and loss
+///   per destination.
+/// * `GET /relay_lists` shows the current whitelist and blacklist.
+///
+/// POST endpoints require an `Authorization: Bearer {token}` header:
+///
+/// * `POST /origins/{origin}/messages/retry` with body
+///   `{"leafIndices": [...]}` attempts the listed messages as soon as possible,
+///   ignoring backoff and gas payment.
+/// * `POST /origins/{origin}/messages/skip` with the same body gives up on the
+///   listed messages.
+/// * `POST /origins/{origin}/dead_letters/redrive` with the same body retries
+///   the listed dead-lettered messages.
+/// * `POST /inboxes/{name}/pause` and `POST /inboxes/{name}/resume` pause and
+///   resume submission to an inbox, from all origins.
+/// * `POST /relay_lists` with body `{"whitelist": ..., "blacklist": ...}`
+///   replaces the relay lists. Invalid lists are rejected and the current
+///   lists are kept.
+///
+/// `{origin}` is the chain name of the origin's outbox.
+pub(crate) fn run_admin_server(
+    port: u16,
+    token: Option<String>,
+    origins: HashMap<String, AdminOrigin>,
+    relay_lists: RelayListsUpdater,
+) -> JoinHandle<()> {
+    let state = Arc::new(AdminState {
+        token,
+        origins: origins.into_iter().collect(),
+        relay_lists,
+    });
+    let with_state = warp::any().map(move || state.clone());
+
+    let queues = warp::path!("queues")
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|state: Arc<AdminState>| ok(&state.queues()));
+    let message = warp::path!("origins" / String / "messages" / u32)
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, leaf_index, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| {
+                origin.message_info(origin.db.message_by_leaf_index(leaf_index))
+            })
+        });
+    let message_by_hash = warp::path!("origins" / String / "messages" / "by_hash" / H256)
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, hash, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| {
+                origin.message_info(origin.db.message_by_leaf(hash))
+            })
+        });
+    let gas_payment = warp::path!("origins" / String / "gas_payments" / u32)
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, leaf_index, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| {
+                reply(
+                    origin
+                        .db
+                        .retrieve_gas_payment_for_leaf(leaf_index)
+                        .map(|amount| GasPaymentInfo { leaf_index, amount }),
+                )
+            })
+        });
+    let checkpoint = warp::path!("origins" / String / "checkpoint")
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| ok(&*origin.signed_checkpoint.borrow()))
+        });
+    let dead_letters = warp::path!("origins" / String / "dead_letters")
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| reply(origin.db.dead_letters()))
+        });
+    let profit_and_loss = warp::path!("origins" / String / "profit_and_loss")
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|origin: String, state: Arc<AdminState>| {
+            state.with_origin(&origin, |origin| reply(origin.db.route_profit_and_loss()))
+        });
+    let relay_lists = warp::path!("relay_lists")
+        .and(warp::get())
+        .and(with_state.clone())
+        .map(|state: Arc<AdminState>| ok(&state.relay_lists.current().to_string()));
+
+    let retry = warp::path!("origins" / String / "messages" / "retry")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and(with_state.clone())
+        .map(
+            |origin: String, auth, request: LeafIndicesRequest, state: Arc<AdminState>| {
+                state.authorized(auth, || {
+                    state.with_origin(&origin, |origin| {
+                        origin.command_messages(request, SubmitterCommand::Retry, |leaf_index| {
+                            origin.message_destination(leaf_index)
+                        })
+                    })
+                })
+            },
+        );
+    let skip = warp::path!("origins" / String / "messages" / "skip")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and(with_state.clone())
+        .map(
+            |origin: String, auth, request: LeafIndicesRequest, state: Arc<AdminState>| {
+                state.authorized(auth, || {
+                    state.with_origin(&origin, |origin| {
+                        origin.command_messages(request, SubmitterCommand::Skip, |leaf_index| {
+                            origin.message_destination(leaf_index)
+                        })
+                    })
+                })
+            },
+        );
+    let redrive = warp::path!("origins" / String / "dead_letters" / "redrive")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::json())
+        .and(with_state.clone())
+        .map(
+            |origin: String, auth, request: LeafIndicesRequest, state: Arc<AdminState>| {
+                state.authorized(auth, || {
+                    state.with_origin(&origin, |origin| {
+                        origin.command_messages(request, SubmitterCommand::Retry, |leaf_index| {
+                            Ok(origin
+                                .db
+                                .retrieve_dead_letter(leaf_index)?
+                                .map(|dead_letter| dead_letter.destination))
+                        })
+                    })
+                })
+            },
+        );
+    let update_relay_lists = warp::path!("relay_lists")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::bytes())
+        .and(with_state.clone())
+        .map(
+            |auth, body: warp::hyper::body::Bytes, state: Arc<AdminState>| {
+                state.authorized(auth, || {
+                    let result = std::str::from_utf8(&body)
+                        .map_err(Into::into)
+                        .and_then(|json| state.relay_lists.update_from_json(json));
+                    match result {
+                        Ok(()) => ok(&"ok"),
+                        Err(e) => warp::reply::with_status(
+                            warp::reply::json(&e.to_string()),
+                            StatusCode::BAD_REQUEST,
+                        ),
+                    }
+                })
+            },
+        );
+    let pause = warp::path!("inboxes" / String / "pause")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(with_state.clone())
+        .map(|inbox: String, auth, state: Arc<AdminState>| {
+            state.authorized(auth, || {
+                state.command_inbox(&inbox, SubmitterCommand::Pause)
+            })
+        });
+    let resume = warp::path!("inboxes" / String / "resume")
+        .and(warp::post())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(with_state)
+        .map(|inbox: String, auth, state: Arc<AdminState>| {
+            state.authorized(auth, || {
+                state.command_inbox(&inbox, SubmitterCommand::Resume)
+            })
+        });
+
+    let routes = queues
+        .or(message)
+        .or(message_by_hash)
+        .or(gas_payment)
+        .or(checkpoint)
+        .or(dead_letters)
+        .or(profit_and_loss)
+        .or(relay_lists)
+        .or(update_relay_lists)
+        .or(retry)
+        .or(skip)
+        .or(redrive)
+        .or(pause)
+        .or(resume);
+
+    info!(port, "starting relayer admin server on 127.0.0.1:{port}");
+    tokio::spawn(warp::serve(routes).run(([127, 0, 0, 1], port)))
+}
+
+impl AdminState {
+    fn queues(&self) -> BTreeMap<&str, BTreeMap<&str, QueueSnapshot>> {
+        self.origins
+            .iter()
+            .map(|(outbox, origin)| {
+                let queues: BTreeMap<_, _> = origin
+                    .submitters
+                    .iter()
+                    .map(|(inbox, handle)| (inbox.as_str(), handle.snapshot.borrow().clone()))
+                    .collect();
+                (outbox.as_str(), queues)
+            })
+            .collect()
+    }
+
+    /// Run `handler` with the origin whose outbox is named `origin`.
+    fn with_origin(
+        &self,
+        origin: &str,
+        handler: impl FnOnce(&AdminOrigin) -> WithStatus<Json>,
+    ) -> WithStatus<Json> {
+        match self.origins.get(origin) {
+            Some(origin) => handler(origin),
+            None => not_found("Unknown origin"),
+        }
+    }
+
+    /// Send a command to the submitters delivering to an inbox from every origin.
+    fn command_inbox(&self, inbox: &str, command: SubmitterCommand) -> WithStatus<Json> {
+        let handles: Vec<_> = self
+            .origins
+            .values()
+            .filter_map(|origin| origin.submitters.get(inbox))
+            .collect();
+        if handles.is_empty() {
+            return not_found("Unknown inbox");
+        }
+        // Send to every submitter, rather than stopping at the first that does not accept it.
+        let accepted = handles
+            .into_iter()
+            .filter(|handle| handle.commands.send(command).is_ok())
+            .count();
+        if accepted > 0 {
+            ok(&"ok")
+        } else {
+            warp::reply::with_status(
+                warp::reply::json(&"Submitter does not accept commands"),
+                StatusCode::CONFLICT,
+            )
+        }
+    }
+
+    /// Run `handler` if the request carries the configured bearer token.
+    fn authorized(
+        &self,
+        header: Option<String>,
+        handler: impl FnOnce() -> WithStatus<Json>,
+    ) -> WithStatus<Json> {
+        let given = header.as_deref().and_then(|h| h.strip_prefix("Bearer "));
+        match (&self.token, given) {
+            (Some(token), Some(given)) if constant_time_eq(token.as_bytes(), given.as_bytes()) => {
+                handler()
+            }
+            (None, _) => warp::reply::with_status(
+                warp::reply::json(&"No admin token is configured"),
+                StatusCode::FORBIDDEN,
+            ),
+            _ => warp::reply::with_status(
+                warp::reply::json(&"Invalid admin token"),
+                StatusCode::UNAUTHORIZED,
+            ),
+        }
+    }
+}
+
+/// Compare secrets without leaking the length of their common prefix.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn ok<T: Serialize>(value: &T) -> WithStatus<Json> {
+    warp::reply::with_status(warp::reply::json(value), StatusCode::OK)
+}
+
+fn not_found(message: &str) -> WithStatus<Json> {
+    warp::reply::with_status(warp::reply::json(&message), StatusCode::NOT_FOUND)
+}
+
+fn error(e: impl std::fmt::Display) -> WithStatus<Json> {
+    warp::reply::with_status(
+        warp::reply::json(&e.to_string()),
+        StatusCode::INTERNAL_SERVER_ERROR,
+    )
+}
+
+fn reply<T: Serialize, E: std::fmt::Display>(result: Result<T, E>) -> WithStatus<Json> {
+    match result {
+        Ok(value) => ok(&value),
+        Err(e) => error(e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::constant_time_eq;
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+    }
+}
@@ -13,10 +13,12 @@ use abacus_base::Agent;
 
 use crate::relayer::Relayer;
 
+mod admin;
 mod checkpoint_fetcher;
 mod merkle_tree_builder;
 mod msg;
 mod prover;
+mod relay_lists;
 mod relayer;
 mod settings;
 
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use abacus_base::InboxValidatorManagers;
+use abacus_core::InboxValidatorManager;
+use ethers::types::U256;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// How often the balance of the submitting wallet is checked.
+const BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Pauses a submitter while the wallet it submits transactions with holds less than a minimum
+/// balance, so that messages stay queued rather than failing for lack of funds. Submission
+/// resumes once the wallet has been topped up.
+#[derive(Debug)]
+pub(crate) struct BalanceGuard {
+    /// The minimum balance, or None if submission is never paused for lack of funds.
+    min_balance: Option<U256>,
+    validator_manager: Arc<InboxValidatorManagers>,
+    /// When the balance was last checked.
+    last_checked: Option<Instant>,
+    /// Whether the balance was below the minimum when last checked.
+    below_min: bool,
+}
+
+impl BalanceGuard {
+    pub fn new(min_balance: Option<U256>, validator_manager: Arc<InboxValidatorManagers>) -> Self {
+        Self {
+            min_balance,
+            validator_manager,
+            last_checked: None,
+            below_min: false,
+        }
+    }
+
+    /// Whether submission should be paused for lack of funds. The balance is checked again if
+    /// it has not been checked recently.
+    pub async fn insufficient_balance(&mut self) -> bool {
+        let min_balance = match self.min_balance {
+            Some(min_balance) => min_balance,
+            None => return false,
+        };
+        if self
+            .last_checked
+            .map_or(true, |t| t.elapsed() >= BALANCE_CHECK_INTERVAL)
+        {
+            self.last_checked = Some(Instant::now());
+            match self.validator_manager.submitter_balance().await {
+                Ok(balance) => self.observe(balance, min_balance),
+                // Keep the previous assessment until the balance can be checked again.
+                Err(e) => warn!(error = ?e, "Unable to check wallet balance"),
+            }
+        }
+        self.below_min
+    }
+
+    fn observe(&mut self, balance: U256, min_balance: U256) {
+        let below_min = balance < min_balance;
+        match (self.below_min, below_min) {
+            (false, true) => error!(
+                %balance,
+                %min_balance,
+                "Wallet balance is below the minimum, pausing submission until it is topped up"
+            ),
+            (true, true) => error!(
+                %balance,
+                %min_balance,
+                "Wallet balance is still below the minimum, submission remains paused"
+            ),
+            (true, false) => info!(
+                %balance,
+                %min_balance,
+                "Wallet balance is no longer below the minimum, resuming submission"
+            ),
+            (false, false) => {}
+        }
+        self.below_min = below_min;
+    }
+}
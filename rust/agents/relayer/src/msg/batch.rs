@@ -0,0 +1,95 @@
+use abacus_core::TxOutcome;
+use ethers::types::{Address, U256};
+use eyre::{bail, eyre, Result};
+
+/// The address of the Multicall3 contract, which is deployed at the same address on most
+/// chains.
+const DEFAULT_MULTICALL_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// Configuration of a SerialSubmitter that processes several messages per transaction.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BatchConf {
+    /// The maximum number of messages processed by one transaction.
+    pub max_batch_size: usize,
+    /// The Multicall3 contract batches are sent through.
+    pub multicall_address: Address,
+}
+
+impl BatchConf {
+    pub fn new(max_batch_size: &str, multicall_address: Option<&str>) -> Result<Self> {
+        let max_batch_size = max_batch_size
+            .parse()
+            .map_err(|e| eyre!("Invalid maxBatchSize {max_batch_size}: {e}"))?;
+        if max_batch_size == 0 {
+            bail!("maxBatchSize must be at least 1");
+        }
+        let multicall_address = multicall_address.unwrap_or(DEFAULT_MULTICALL_ADDRESS);
+        let multicall_address = multicall_address
+            .parse()
+            .map_err(|e| eyre!("Invalid multicallAddress {multicall_address}: {e}"))?;
+        Ok(Self {
+            max_batch_size,
+            multicall_address,
+        })
+    }
+}
+
+/// Split the outcome of a batch transaction between its members in proportion to their
+/// estimated gas limits, so that each member's share can be recorded as its relay expense.
+/// Gas left over from rounding down is attributed to the first member.
+pub(crate) fn attribute_gas(outcome: &TxOutcome, gas_estimates: &[U256]) -> Vec<TxOutcome> {
+    let total = gas_estimates.iter().fold(U256::zero(), |total, estimate| {
+        total.saturating_add(*estimate)
+    });
+    let mut shares: Vec<U256> = gas_estimates
+        .iter()
+        .map(|estimate| {
+            if total.is_zero() {
+                outcome.gas_used / gas_estimates.len()
+            } else {
+                outcome.gas_used.saturating_mul(*estimate) / total
+            }
+        })
+        .collect();
+    let attributed = shares
+        .iter()
+        .fold(U256::zero(), |total, share| total.saturating_add(*share));
+    if let Some(first) = shares.first_mut() {
+        *first = first.saturating_add(outcome.gas_used.saturating_sub(attributed));
+    }
+    shares
+        .into_iter()
+        .map(|gas_used| TxOutcome {
+            gas_used,
+            ..*outcome
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use abacus_core::TxOutcome;
+    use ethers::types::{H256, U256};
+
+    use super::attribute_gas;
+
+    #[test]
+    fn attributes_gas_in_proportion_to_estimates() {
+        let outcome = TxOutcome {
+            txid: H256::zero(),
+            executed: true,
+            gas_used: 1000.into(),
+            gas_price: 2.into(),
+        };
+        let gas_used = |estimates: &[u64]| -> Vec<u64> {
+            let estimates: Vec<U256> = estimates.iter().map(|e| U256::from(*e)).collect();
+            attribute_gas(&outcome, &estimates)
+                .iter()
+                .map(|share| share.gas_used.as_u64())
+                .collect()
+        };
+        assert_eq!(gas_used(&[100, 300]), [250, 750]);
+        assert_eq!(gas_used(&[1, 1, 1]), [334, 333, 333]);
+        assert_eq!(gas_used(&[0, 0]), [500, 500]);
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::{BTreeMap, HashMap};
+
+use abacus_base::CoreMetrics;
+use abacus_base::{InboxContracts, InboxValidatorManagers};
+use abacus_core::{
+    AbacusContract, ChainCommunicationError, InboxValidatorManager, TxCostEstimate, TxOutcome,
+};
+use ethers::types::{H256, U256};
+use eyre::{bail, eyre, Result};
+use prometheus::IntGauge;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, info, info_span, instrument, instrument::Instrumented, warn, Instrument};
+
+use super::{balance::BalanceGuard, queues::SubmitterQueues, SubmitMessageArgs};
+
+/// How long a process transaction may go unmined before it is replaced with
+/// one paying a higher gas price, unless configured otherwise.
+const DEFAULT_STUCK_TX_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+
+/// Configuration of a ConcurrentSubmitter.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ConcurrentSubmitterConf {
+    /// The maximum number of process transactions in flight at once.
+    pub max_in_flight: usize,
+    /// How long a transaction may go unmined before it is replaced.
+    pub stuck_tx_timeout: Duration,
+}
+
+impl ConcurrentSubmitterConf {
+    pub fn new(max_in_flight: &str, stuck_tx_timeout: Option<&str>) -> Result<Self> {
+        let max_in_flight = max_in_flight
+            .parse()
+            .map_err(|e| eyre!("Invalid maxInFlight {max_in_flight}: {e}"))?;
+        if max_in_flight == 0 {
+            bail!("maxInFlight must be at least 1");
+        }
+        let stuck_tx_timeout = stuck_tx_timeout
+            .map(|secs| {
+                secs.parse()
+                    .map(Duration::from_secs)
+                    .map_err(|e| eyre!("Invalid stuckTxTimeout {secs}: {e}"))
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_STUCK_TX_TIMEOUT);
+        Ok(Self {
+            max_in_flight,
+            stuck_tx_timeout,
+        })
+    }
+}
+
+/// The messages of one origin delivered by a ConcurrentSubmitter.
+#[derive(Debug)]
+pub(crate) struct ConcurrentOrigin {
+    /// The wait, run and verification queues of messages to submit, and the handling of
+    /// failed processing attempts.
+    queues: SubmitterQueues,
+    /// The origin's Inbox / InboxValidatorManager on the destination chain.
+    inbox_contracts: InboxContracts,
+    /// Pauses broadcasting while the signer's balance is below the route's minimum.
+    balance_guard: BalanceGuard,
+    metrics: ConcurrentSubmitterMetrics,
+}
+
+impl ConcurrentOrigin {
+    pub(crate) fn new(
+        queues: SubmitterQueues,
+        inbox_contracts: InboxContracts,
+        balance_guard: BalanceGuard,
+        metrics: ConcurrentSubmitterMetrics,
+    ) -> Self {
+        Self {
+            queues,
+            inbox_contracts,
+            balance_guard,
+            metrics,
+        }
+    }
+}
+
+/// A process transaction that has been broadcast but not yet mined.
+#[derive(Debug)]
+struct InFlightTx {
+    /// The index of the message's origin in `ConcurrentSubmitter::origins`.
+    origin: usize,
+    msg: SubmitMessageArgs,
+    /// The gas limit and price of the latest broadcast.
+    tx_cost: TxCostEstimate,
+    /// Every transaction broadcast with this nonce, as any one of them may be
+    /// the one that is mined.
+    txids: Vec<H256>,
+    last_broadcast: Instant,
+}
+
+/// ConcurrentSubmitter is an alternative to the SerialSubmitter for destinations where a
+/// single in-flight transaction, and therefore one message per confirmation time, is not
+/// enough. It keeps up to `max_in_flight` process transactions in flight at once, assigning
+/// them sequential nonces which it tracks itself rather than relying on the provider. These
+/// are the only source of nonces for its transactions, which bypass the provider's nonce
+/// manager.
+///
+/// Messages move through the same wait, run and verification queues as in the
+/// SerialSubmitter (see `queues::SubmitterQueues`), and failures are retried and
+/// dead-lettered in the same way. The
+/// difference is that messages taken from the run queue are broadcast without waiting for
+/// them to be mined, and held in an in-flight set keyed by nonce. Each tick the in-flight set
+/// is reconciled with the chain:
+///
+///  *  Transactions that were mined successfully move to the verification queue.
+///  *  Transactions that were mined but reverted are retried according to the retry policy.
+///     Their nonce is consumed, so transactions with later nonces are unaffected.
+///  *  Transactions that have gone unmined for `stuck_tx_timeout` are replaced by a
+///     transaction with the same nonce and a higher gas price.
+///  *  If a nonce was consumed by a transaction we did not broadcast, e.g. because the
+///     submitting account is shared, its message goes back to the run queue.
+///  *  If the account's nonce is below the lowest nonce in flight, there is a gap that will
+///     never be filled, e.g. because a transaction was dropped from the mempool. All
+///     in-flight messages go back to the run queue and nonces are reassigned from the
+///     account's nonce, outbidding the abandoned transactions.
+///
+/// When nothing is in flight, the next nonce is re-synced from the chain. A broadcast that
+/// fails, e.g. because a transaction from before a restart is still pending with the same
+/// nonce, is retried like any other transient failure.
+///
+/// While an operator has paused submission, in-flight transactions are still reconciled and
+/// replaced if stuck, but no new transactions are broadcast. The same holds while the
+/// destination wallet's balance is below the chain's configured minimum (see `BalanceGuard`).
+///
+/// A relayer serving several origins runs a single ConcurrentSubmitter for each destination,
+/// as the origins' process transactions are signed by the same account and must not be given
+/// the same nonces. Each origin keeps its own queues, and can be paused on its own; the
+/// submitter takes runnable messages from the origins in turn.
+#[derive(Debug)]
+pub(crate) struct ConcurrentSubmitter {
+    conf: ConcurrentSubmitterConf,
+    /// The origins whose messages are delivered, of which there is at least one.
+    origins: Vec<ConcurrentOrigin>,
+    /// The index in `origins` of the origin to take the next runnable message from.
+    next_origin: usize,
+    /// Broadcast transactions that have not been mined, keyed by nonce.
+    in_flight: BTreeMap<U256, InFlightTx>,
+    /// The nonce to give the next transaction, or None if it should be synced from the chain.
+    next_nonce: Option<U256>,
+    /// The gas prices of abandoned transactions, keyed by nonce. A new transaction with one
+    /// of these nonces must outbid the abandoned one to replace it.
+    abandoned_gas_prices: HashMap<U256, U256>,
+}
+
+impl ConcurrentSubmitter {
+    pub(crate) fn new(conf: ConcurrentSubmitterConf, origins: Vec<ConcurrentOrigin>) -> Self {
+        assert!(
+            !origins.is_empty(),
+            "a concurrent submitter needs an origin"
+        );
+        Self {
+            conf,
+            origins,
+            next_origin: 0,
+            in_flight: BTreeMap::new(),
+            next_nonce: None,
+            abandoned_gas_prices: HashMap::new(),
+        }
+    }
+
+    pub fn spawn(mut self) -> Instrumented<JoinHandle<Result<()>>> {
+        tokio::spawn(async move { self.work_loop().await })
+            .instrument(info_span!("concurrent submitter work loop"))
+    }
+
+    #[instrument(skip_all, fields(ibx=self.origins[0].inbox_contracts.inbox.inbox().chain_name()))]
+    async fn work_loop(&mut self) -> Result<()> {
+        loop {
+            self.tick().await?;
+            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        }
+    }
+
+    async fn tick(&mut self) -> Result<()> {
+        for origin in self.origins.iter_mut() {
+            origin.queues.update().await?;
+        }
+
+        self.reconcile_in_flight().await;
+        let mut broadcasting = Vec::with_capacity(self.origins.len());
+        for origin in self.origins.iter_mut() {
+            broadcasting.push(if origin.queues.paused() {
+                debug!("Submission from an origin is paused by an operator");
+                false
+            } else {
+                !origin.balance_guard.insufficient_balance().await
+            });
+        }
+        self.broadcast_runnable(&broadcasting).await;
+
+        for (i, origin) in self.origins.iter().enumerate() {
+            let in_flight = self.in_flight.values().filter(|tx| tx.origin == i).count();
+            origin.metrics.in_flight_length_gauge.set(in_flight as i64);
+            origin.queues.publish();
+        }
+
+        Ok(())
+    }
+
+    /// The validator manager the message of an in-flight transaction is processed by.
+    fn validator_manager(&self, tx: &InFlightTx) -> &InboxValidatorManagers {
+        &self.origins[tx.origin].inbox_contracts.validator_manager
+    }
+
+    /// The queues of the origin of an in-flight transaction.
+    fn queues(&mut self, tx: &InFlightTx) -> &mut SubmitterQueues {
+        &mut self.origins[tx.origin].queues
+    }
+
+    /// Reconcile in-flight transactions with the chain, see the ConcurrentSubmitter docs.
+    async fn reconcile_in_flight(&mut self) {
+        // Settle mined transactions before reading the account's nonce, so that a transaction
+        // mined in between is not mistaken for a gap before the remaining ones.
+        let mut unmined = BTreeMap::new();
+        for (nonce, tx) in std::mem::take(&mut self.in_flight) {
+            match self.mined_outcome(&tx).await {
+                Ok(Some(outcome)) => self.settle_mined(nonce, tx, outcome),
+                Ok(None) => {
+                    unmined.insert(nonce, tx);
+                }
+                Err(e) => {
+                    warn!(leaf_index = tx.msg.leaf_index, error = ?e, "Unable to get process transaction outcome");
+                    self.in_flight.insert(nonce, tx);
+                }
+            }
+        }
+
+        // The origins' validator managers share the destination's signer.
+        let account_nonce = match self.origins[0]
+            .inbox_contracts
+            .validator_manager
+            .submitter_nonce()
+            .await
+        {
+            Ok(nonce) => nonce,
+            Err(e) => {
+                warn!(error = ?e, "Unable to get submitter nonce");
+                self.in_flight.append(&mut unmined);
+                return;
+            }
+        };
+        self.abandoned_gas_prices
+            .retain(|nonce, _| *nonce >= account_nonce);
+
+        for (nonce, tx) in unmined {
+            if nonce < account_nonce {
+                // The nonce was consumed, possibly by one of our transactions mined since its
+                // outcome was checked.
+                match self.mined_outcome(&tx).await {
+                    Ok(Some(outcome)) => self.settle_mined(nonce, tx, outcome),
+                    Ok(None) => {
+                        warn!(
+                            leaf_index = tx.msg.leaf_index,
+                            ?nonce,
+                            "Nonce was used by another transaction, returning message to the run queue"
+                        );
+                        self.queues(&tx).requeue(tx.msg);
+                    }
+                    Err(e) => {
+                        warn!(leaf_index = tx.msg.leaf_index, error = ?e, "Unable to get process transaction outcome");
+                        self.in_flight.insert(nonce, tx);
+                    }
+                }
+            } else if tx.last_broadcast.elapsed() > self.conf.stuck_tx_timeout {
+                self.replace_stuck(nonce, tx).await;
+            } else {
+                self.in_flight.insert(nonce, tx);
+            }
+        }
+
+        match self.in_flight.keys().next().copied() {
+            Some(lowest_nonce) if lowest_nonce > account_nonce => {
+                warn!(
+                    ?account_nonce,
+                    ?lowest_nonce,
+                    "Nonce gap before in-flight transactions, reassigning nonces"
+                );
+                for (nonce, tx) in std::mem::take(&mut self.in_flight).into_iter().rev() {
+                    self.abandoned_gas_prices
+                        .insert(nonce, tx.tx_cost.gas_price);
+                    self.queues(&tx).requeue(tx.msg);
+                }
+                self.next_nonce = Some(account_nonce);
+            }
+            Some(_) => {}
+            None => self.next_nonce = Some(account_nonce),
+        }
+    }
+
+    /// Move a message whose process transaction was mined to the verification queue, or
+    /// retry it if the transaction reverted. Its nonce is consumed either way.
+    fn settle_mined(&mut self, nonce: U256, tx: InFlightTx, outcome: TxOutcome) {
+        let queues = self.queues(&tx);
+        queues.record_relay_expense(&tx.msg, &outcome);
+        if outcome.executed {
+            queues.record_gas_used(&tx.msg, &outcome);
+            info!(leaf_index = tx.msg.leaf_index, txid = ?outcome.txid, ?nonce,
+                "Message processed, awaiting finality");
+            queues.push_to_verification_queue(tx.msg);
+        } else {
+            let e = ChainCommunicationError::Reverted(format!(
+                "process transaction {:?} reverted",
+                outcome.txid
+            ));
+            queues.handle_process_failure(tx.msg, e);
+        }
+    }
+
+    /// The outcome of whichever of the transactions broadcast for an in-flight message has
+    /// been mined, if any.
+    async fn mined_outcome(
+        &self,
+        tx: &InFlightTx,
+    ) -> Result<Option<TxOutcome>, ChainCommunicationError> {
+        for txid in tx.txids.iter().rev() {
+            if let Some(outcome) = self.validator_manager(tx).tx_outcome(*txid).await? {
+                return Ok(Some(outcome));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Replace a transaction that has not been mined with one using the same nonce and a
+    /// higher gas price.
+    async fn replace_stuck(&mut self, nonce: U256, mut tx: InFlightTx) {
+        let validator_manager = self.validator_manager(&tx);
+        let result = match validator_manager
+            .process_estimate_costs(
+                &tx.msg.checkpoint,
+                &tx.msg.committed_message.message,
+                &tx.msg.proof,
+            )
+            .await
+        {
+            Ok(mut tx_cost) => {
+                tx_cost.gas_price = tx_cost.gas_price.max(bump_gas_price(tx.tx_cost.gas_price));
+                // Keep the gas limit of the transaction being replaced.
+                tx_cost.gas_limit = tx.tx_cost.gas_limit;
+                validator_manager
+                    .send_process(
+                        &tx.msg.checkpoint,
+                        &tx.msg.committed_message.message,
+                        &tx.msg.proof,
+                        nonce,
+                        &tx_cost,
+                    )
+                    .await
+                    .map(|txid| (txid, tx_cost))
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((txid, tx_cost)) => {
+                info!(leaf_index = tx.msg.leaf_index, ?txid, ?nonce, gas_price = ?tx_cost.gas_price,
+                    "Replaced stuck process transaction");
+                tx.tx_cost = tx_cost;
+                tx.txids.push(txid);
+            }
+            // The original transaction may still be mined, so keep waiting on it.
+            Err(e) => {
+                warn!(leaf_index = tx.msg.leaf_index, ?nonce, error = ?e,
+                    "Unable to replace stuck process transaction");
+            }
+        }
+        tx.last_broadcast = Instant::now();
+        self.in_flight.insert(nonce, tx);
+    }
+
+    /// Take the next runnable message from the origins flagged in `broadcasting`, in turn.
+    async fn next_runnable(&mut self, broadcasting: &[bool]) -> Option<(usize, SubmitMessageArgs)> {
+        for i in 0..self.origins.len() {
+            let origin = (self.next_origin + i) % self.origins.len();
+            if !broadcasting[origin] {
+                continue;
+            }
+            if let Some(msg) = self.origins[origin].queues.next_runnable().await {
+                self.next_origin = (origin + 1) % self.origins.len();
+                return Some((origin, msg));
+            }
+        }
+        None
+    }
+
+    /// Broadcast process transactions for messages in the run queues of the origins flagged in
+    /// `broadcasting` until the in-flight limit is reached.
+    async fn broadcast_runnable(&mut self, broadcasting: &[bool]) {
+        while self.in_flight.len() < self.conf.max_in_flight {
+            let nonce = match self.next_nonce {
+                Some(nonce) => nonce,
+                // Nonces are synced while reconciling in-flight transactions.
+                None => return,
+            };
+            let (origin, msg) = match self.next_runnable(broadcasting).await {
+                Some(runnable) => runnable,
+                None => return,
+            };
+
+            let validator_manager = self.origins[origin]
+                .inbox_contracts
+                .validator_manager
+                .clone();
+            let estimate = match validator_manager
+                .process_estimate_costs(&msg.checkpoint, &msg.committed_message.message, &msg.proof)
+                .await
+            {
+                Ok(mut tx_cost) => self.origins[origin]
+                    .queues
+                    .gas_limit(&msg, tx_cost.gas_estimate)
+                    .map(|gas_limit| {
+                        tx_cost.gas_limit = gas_limit;
+                        tx_cost
+                    }),
+                // Only errors of the estimate, not of the gas limit policy, can be reverts.
+                Err(e) => {
+                    self.origins[origin].queues.record_revert(&e);
+                    Err(e)
+                }
+            };
+            let mut tx_cost = match estimate {
+                Ok(tx_cost) => tx_cost,
+                Err(e) => {
+                    self.origins[origin].queues.handle_process_failure(msg, e);
+                    continue;
+                }
+            };
+            if let Some(abandoned) = self.abandoned_gas_prices.get(&nonce) {
+                tx_cost.gas_price = tx_cost.gas_price.max(bump_gas_price(*abandoned));
+            }
+
+            match validator_manager
+                .send_process(
+                    &msg.checkpoint,
+                    &msg.committed_message.message,
+                    &msg.proof,
+                    nonce,
+                    &tx_cost,
+                )
+                .await
+            {
+                Ok(txid) => {
+                    info!(
+                        leaf_index = msg.leaf_index,
+                        ?txid,
+                        ?nonce,
+                        "Broadcast process transaction"
+                    );
+                    self.abandoned_gas_prices.remove(&nonce);
+                    self.next_nonce = Some(nonce + U256::one());
+                    self.in_flight.insert(
+                        nonce,
+                        InFlightTx {
+                            origin,
+                            msg,
+                            tx_cost,
+                            txids: vec![txid],
+                            last_broadcast: Instant::now(),
+                        },
+                    );
+                }
+                // Stop broadcasting so as not to skip the nonce.
+                Err(e) => {
+                    self.origins[origin].queues.handle_process_failure(msg, e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// The gas price needed to replace a pending transaction. Nodes typically
+/// require an increase of at least 10%, so this bumps by 12.5%.
+fn bump_gas_price(gas_price: U256) -> U256 {
+    gas_price.saturating_add((gas_price / 8).max(U256::one()))
+}
+
+/// Metrics of a ConcurrentSubmitter on top of those of its queues.
+#[derive(Debug)]
+pub(crate) struct ConcurrentSubmitterMetrics {
+    in_flight_length_gauge: IntGauge,
+}
+
+impl ConcurrentSubmitterMetrics {
+    pub fn new(metrics: &CoreMetrics, outbox_chain: &str, inbox_chain: &str) -> Self {
+        Self {
+            in_flight_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "in_flight",
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+    use tokio::time::Duration;
+
+    use super::{bump_gas_price, ConcurrentSubmitterConf, DEFAULT_STUCK_TX_TIMEOUT};
+
+    #[test]
+    fn bumps_gas_price_by_at_least_ten_percent() {
+        assert_eq!(bump_gas_price(U256::from(80u64)), U256::from(90u64));
+        assert_eq!(bump_gas_price(U256::from(1u64)), U256::from(2u64));
+        assert_eq!(bump_gas_price(U256::MAX), U256::MAX);
+    }
+
+    #[test]
+    fn parses_conf() {
+        let conf = ConcurrentSubmitterConf::new("8", None).unwrap();
+        assert_eq!(conf.max_in_flight, 8);
+        assert_eq!(conf.stuck_tx_timeout, DEFAULT_STUCK_TX_TIMEOUT);
+
+        let conf = ConcurrentSubmitterConf::new("8", Some("60")).unwrap();
+        assert_eq!(conf.stuck_tx_timeout, Duration::from_secs(60));
+
+        assert!(ConcurrentSubmitterConf::new("0", None).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use ethers::types::H256;
+use eyre::Result;
+use serde::Serialize;
+use tokio::sync::{mpsc, watch};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::{dead_letters::DeadLetters, SubmitMessageArgs};
+
+/// A request from an operator, made via the admin API, to a submitter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubmitterCommand {
+    /// Attempt a message as soon as possible, whether it is backing off after a failure,
+    /// waiting for gas payment or dead-lettered.
+    Retry(u32),
+    /// Give up on a message without delivering it. It is not marked as processed, so it is
+    /// picked up again after a restart.
+    Skip(u32),
+    /// Stop submitting messages. Messages are still received and verified while paused.
+    Pause,
+    /// Resume submitting messages.
+    Resume,
+}
+
+/// A message in one of a submitter's queues.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueuedMessage {
+    pub leaf_index: u32,
+    pub hash: H256,
+    pub num_retries: u32,
+    pub num_reverts: u32,
+    pub priority: u8,
+    /// Seconds until the message may be attempted again, if it is backing off.
+    pub retry_in_secs: Option<u64>,
+}
+
+impl From<&SubmitMessageArgs> for QueuedMessage {
+    fn from(msg: &SubmitMessageArgs) -> Self {
+        Self {
+            leaf_index: msg.leaf_index,
+            hash: msg.committed_message.to_leaf(),
+            num_retries: msg.num_retries,
+            num_reverts: msg.num_reverts,
+            priority: msg.priority,
+            retry_in_secs: msg
+                .next_attempt_after
+                .map(|t| t.saturating_duration_since(Instant::now()).as_secs()),
+        }
+    }
+}
+
+/// The state of a submitter's queues, as published for the admin API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueueSnapshot {
+    pub paused: bool,
+    pub run_queue: Vec<QueuedMessage>,
+    pub wait_queue: Vec<QueuedMessage>,
+}
+
+impl QueueSnapshot {
+    /// The name of the queue holding a message, if any.
+    pub fn queue_of(&self, leaf_index: u32) -> Option<&'static str> {
+        if self.run_queue.iter().any(|m| m.leaf_index == leaf_index) {
+            Some("run_queue")
+        } else if self.wait_queue.iter().any(|m| m.leaf_index == leaf_index) {
+            Some("wait_queue")
+        } else {
+            None
+        }
+    }
+}
+
+/// The submitter's end of the admin API: commands from operators and the queue snapshot
+/// published to them.
+#[derive(Debug)]
+pub(crate) struct SubmitterControl {
+    commands: mpsc::UnboundedReceiver<SubmitterCommand>,
+    snapshot: watch::Sender<QueueSnapshot>,
+    paused: bool,
+}
+
+/// The admin API's end of a submitter's control channel.
+#[derive(Debug, Clone)]
+pub(crate) struct SubmitterHandle {
+    /// The domain of the inbox the submitter delivers to.
+    pub domain: u32,
+    pub commands: mpsc::UnboundedSender<SubmitterCommand>,
+    pub snapshot: watch::Receiver<QueueSnapshot>,
+}
+
+/// Create the two ends of the control channel of the submitter for the inbox on `domain`.
+pub(crate) fn submitter_control(domain: u32) -> (SubmitterControl, SubmitterHandle) {
+    let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+    let (snapshot_tx, snapshot_rx) = watch::channel(QueueSnapshot::default());
+    (
+        SubmitterControl {
+            commands: commands_rx,
+            snapshot: snapshot_tx,
+            paused: false,
+        },
+        SubmitterHandle {
+            domain,
+            commands: commands_tx,
+            snapshot: snapshot_rx,
+        },
+    )
+}
+
+impl SubmitterControl {
+    /// Whether an operator has paused submission.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Apply the commands received since the last call to the submitter's queues.
+    pub fn apply_commands(
+        &mut self,
+        run_queue: &mut VecDeque<SubmitMessageArgs>,
+        wait_queue: &mut Vec<SubmitMessageArgs>,
+        dead_letters: &mut DeadLetters,
+    ) -> Result<()> {
+        // A disconnected channel just means no one can send commands.
+        while let Ok(command) = self.commands.try_recv() {
+            info!(?command, "Applying operator command");
+            match command {
+                SubmitterCommand::Retry(leaf_index) => {
+                    let msg = match take_queued(run_queue, wait_queue, leaf_index) {
+                        Some(msg) => Some(msg),
+                        None => dead_letters.redrive(leaf_index)?,
+                    };
+                    match msg {
+                        Some(mut msg) => {
+                            msg.next_attempt_after = None;
+                            run_queue.push_front(msg);
+                        }
+                        None => warn!(leaf_index, "Requested retry of unknown message"),
+                    }
+                }
+                SubmitterCommand::Skip(leaf_index) => {
+                    let skipped = take_queued(run_queue, wait_queue, leaf_index).is_some()
+                        || dead_letters.skip(leaf_index)?;
+                    if !skipped {
+                        warn!(leaf_index, "Requested skip of unknown message");
+                    }
+                }
+                SubmitterCommand::Pause => self.paused = true,
+                SubmitterCommand::Resume => self.paused = false,
+            }
+        }
+        Ok(())
+    }
+
+    /// Publish the current state of the submitter's queues.
+    pub fn publish(
+        &self,
+        run_queue: &VecDeque<SubmitMessageArgs>,
+        wait_queue: &[SubmitMessageArgs],
+    ) {
+        // Sending only fails if the admin API is not served.
+        let _ = self.snapshot.send(QueueSnapshot {
+            paused: self.paused,
+            run_queue: run_queue.iter().map(Into::into).collect(),
+            wait_queue: wait_queue.iter().map(Into::into).collect(),
+        });
+    }
+}
+
+/// Remove a message from the run queue or wait queue.
+fn take_queued(
+    run_queue: &mut VecDeque<SubmitMessageArgs>,
+    wait_queue: &mut Vec<SubmitMessageArgs>,
+    leaf_index: u32,
+) -> Option<SubmitMessageArgs> {
+    if let Some(idx) = run_queue.iter().position(|m| m.leaf_index == leaf_index) {
+        return run_queue.remove(idx);
+    }
+    wait_queue
+        .iter()
+        .position(|m| m.leaf_index == leaf_index)
+        .map(|idx| wait_queue.remove(idx))
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use abacus_core::db::AbacusDB;
+use abacus_core::{ChainCommunicationError, DeadLetter};
+use eyre::Result;
+use tracing::{error, info};
+
+use super::{scheduling_state::store_scheduling_state, SubmitMessageArgs};
+
+/// Messages that have exhausted their retries. They are recorded in AbacusDB,
+/// where operators can inspect them, and held aside until an operator
+/// re-drives or skips them by leaf index.
+#[derive(Debug)]
+pub(crate) struct DeadLetters {
+    db: AbacusDB,
+    /// Dead-lettered messages, keyed by leaf index.
+    held: HashMap<u32, SubmitMessageArgs>,
+}
+
+impl DeadLetters {
+    pub fn new(db: AbacusDB) -> Self {
+        Self {
+            db,
+            held: HashMap::new(),
+        }
+    }
+
+    /// Record that a message has failed for the last time and hold it until it
+    /// is re-driven.
+    pub fn insert(&mut self, msg: SubmitMessageArgs, last_error: &ChainCommunicationError) {
+        let now = unix_timestamp();
+        let dead_letter = DeadLetter {
+            leaf_index: msg.leaf_index,
+            destination: msg.committed_message.message.destination,
+            num_attempts: msg.num_retries,
+            last_error: last_error.to_string(),
+            first_failure_at: msg.first_failure_at.unwrap_or(now),
+            dead_lettered_at: now,
+        };
+        if let Err(e) = self.db.store_dead_letter(&dead_letter) {
+            error!(leaf_index=msg.leaf_index, error=?e, "Failed to store dead letter");
+        }
+        self.held.insert(msg.leaf_index, msg);
+    }
+
+    /// Hold a message received by the submitter if it was dead-lettered before a restart,
+    /// returning it otherwise.
+    pub fn restore(&mut self, msg: SubmitMessageArgs) -> Result<Option<SubmitMessageArgs>> {
+        if self.db.retrieve_dead_letter(msg.leaf_index)?.is_none() {
+            return Ok(Some(msg));
+        }
+        info!(
+            leaf_index = msg.leaf_index,
+            "Holding message dead-lettered before a restart"
+        );
+        self.held.insert(msg.leaf_index, msg);
+        Ok(None)
+    }
+
+    /// Release a dead-lettered message with a fresh retry budget, if it is held.
+    pub fn redrive(&mut self, leaf_index: u32) -> Result<Option<SubmitMessageArgs>> {
+        let mut msg = match self.held.remove(&leaf_index) {
+            Some(msg) => msg,
+            None => return Ok(None),
+        };
+        info!(leaf_index, "Re-driving dead-lettered message");
+        msg.num_retries = 0;
+        msg.num_reverts = 0;
+        msg.next_attempt_after = None;
+        msg.first_failure_at = None;
+        store_scheduling_state(&self.db, &msg)?;
+        self.db.remove_dead_letter(leaf_index)?;
+        Ok(Some(msg))
+    }
+
+    /// Give up on a dead-lettered message for good, returning whether there was a dead
+    /// letter for it, e.g. one recorded before a restart.
+    pub fn skip(&mut self, leaf_index: u32) -> Result<bool> {
+        let held = self.held.remove(&leaf_index).is_some();
+        let recorded = self.db.retrieve_dead_letter(leaf_index)?.is_some();
+        if held || recorded {
+            info!(leaf_index, "Skipping dead-lettered message");
+            self.db.remove_dead_letter(leaf_index)?;
+        }
+        Ok(held || recorded)
+    }
+
+    /// Remove any dead letter for a message that has since been processed,
+    /// e.g. one dead-lettered before a restart.
+    pub fn remove(&self, leaf_index: u32) -> Result<()> {
+        Ok(self.db.remove_dead_letter(leaf_index)?)
+    }
+}
+
+/// The current unix timestamp in seconds.
+pub(crate) fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use abacus_core::{db::AbacusDB, ChainCommunicationError, RecipientGasUsage, TxOutcome};
+use ethers::types::{Address, H256, U256};
+use eyre::{bail, eyre, Result};
+use tracing::error;
+
+use crate::settings::ProcessGasLimitConf;
+
+use super::SubmitMessageArgs;
+
+/// The margin added to gas limits that are not overridden, in percent, to account for
+/// estimation inaccuracies.
+const GAS_LIMIT_MARGIN_PERCENT: u64 = 10;
+
+/// A parsed `ProcessGasLimitConf`, which chooses the gas limit of processing a message.
+///
+/// A message to a recipient with an override is given the override. Otherwise it is given
+/// the larger of its estimate and the peak gas its recipient has recently been seen to use,
+/// plus a margin. Either way the gas limit does not exceed `max`, and a message estimated to
+/// need more fails with `GasLimitExceeded`, which dead-letters it rather than retrying it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ProcessGasLimits {
+    max: Option<U256>,
+    recipients: HashMap<H256, U256>,
+}
+
+impl TryFrom<&ProcessGasLimitConf> for ProcessGasLimits {
+    type Error = eyre::Report;
+
+    fn try_from(conf: &ProcessGasLimitConf) -> Result<Self> {
+        let parse_gas = |gas: &str| {
+            U256::from_dec_str(gas).map_err(|e| eyre!("Invalid gas limit {gas}: {e:?}"))
+        };
+        let max = conf.max.as_deref().map(parse_gas).transpose()?;
+        let recipients = conf
+            .recipients
+            .iter()
+            .map(|(recipient, gas)| {
+                let gas_limit = parse_gas(gas)?;
+                if max.map_or(false, |max| gas_limit > max) {
+                    bail!("Gas limit {gas} of recipient {recipient} exceeds the max gas limit");
+                }
+                let recipient = recipient
+                    .parse::<Address>()
+                    .map_err(|e| eyre!("Invalid recipient {recipient}: {e}"))?;
+                Ok((H256::from(recipient), gas_limit))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { max, recipients })
+    }
+}
+
+impl ProcessGasLimits {
+    /// The gas limit of processing a message, given its estimated gas used without any margin.
+    pub fn gas_limit(
+        &self,
+        db: &AbacusDB,
+        msg: &SubmitMessageArgs,
+        estimate: U256,
+    ) -> Result<U256, ChainCommunicationError> {
+        let message = &msg.committed_message.message;
+        let usage = db.retrieve_recipient_gas_usage(message.destination, message.recipient)?;
+        self.limit_for(message.recipient, estimate, usage)
+    }
+
+    fn limit_for(
+        &self,
+        recipient: H256,
+        estimate: U256,
+        usage: Option<RecipientGasUsage>,
+    ) -> Result<U256, ChainCommunicationError> {
+        if let Some(gas_limit) = self.recipients.get(&recipient) {
+            return Ok(*gas_limit);
+        }
+        let max = self.max.unwrap_or(U256::MAX);
+        if estimate > max {
+            return Err(ChainCommunicationError::GasLimitExceeded(estimate, max));
+        }
+        let learned = usage.map_or(estimate, |usage| usage.peak_gas_used.max(estimate));
+        let margin = learned.saturating_mul(GAS_LIMIT_MARGIN_PERCENT.into()) / 100;
+        Ok(learned.saturating_add(margin).min(max))
+    }
+}
+
+/// Learn the gas used by a transaction that processed a single message, so that the gas
+/// limits of later messages to its recipient account for it.
+pub(crate) fn record_gas_used(db: &AbacusDB, msg: &SubmitMessageArgs, outcome: &TxOutcome) {
+    if !outcome.executed {
+        return;
+    }
+    let message = &msg.committed_message.message;
+    if let Err(e) =
+        db.record_recipient_gas_used(message.destination, message.recipient, outcome.gas_used)
+    {
+        error!(leaf_index = msg.leaf_index, error = ?e, "Failed to record recipient gas usage");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use abacus_core::{ChainCommunicationError, RecipientGasUsage};
+    use ethers::types::{H256, U256};
+
+    use crate::settings::ProcessGasLimitConf;
+
+    use super::ProcessGasLimits;
+
+    #[test]
+    fn limits_gas_by_override_learned_usage_and_max() {
+        let limits = ProcessGasLimits::try_from(&ProcessGasLimitConf {
+            max: Some("500000".into()),
+            recipients: HashMap::from([(
+                "0x0000000000000000000000000000000000000001".into(),
+                "50000".into(),
+            )]),
+        })
+        .unwrap();
+        let overridden = H256::from_low_u64_be(1);
+        let other = H256::from_low_u64_be(2);
+        let usage = |peak: u64| RecipientGasUsage {
+            num_processed: 1,
+            peak_gas_used: peak.into(),
+        };
+        let limit = |recipient, estimate: u64, usage| {
+            limits
+                .limit_for(recipient, estimate.into(), usage)
+                .map(|limit| limit.as_u64())
+        };
+
+        assert_eq!(limit(overridden, 200_000, None).unwrap(), 50_000);
+        assert_eq!(limit(other, 100_000, None).unwrap(), 110_000);
+        assert_eq!(
+            limit(other, 100_000, Some(usage(200_000))).unwrap(),
+            220_000
+        );
+        assert_eq!(limit(other, 480_000, None).unwrap(), 500_000);
+        assert!(matches!(
+            limits.limit_for(other, U256::from(600_000u64), None),
+            Err(ChainCommunicationError::GasLimitExceeded(_, _))
+        ));
+
+        let invalid = ProcessGasLimitConf {
+            max: Some("10000".into()),
+            recipients: HashMap::from([(
+                "0x0000000000000000000000000000000000000001".into(),
+                "50000".into(),
+            )]),
+        };
+        assert!(ProcessGasLimits::try_from(&invalid).is_err());
+        assert_eq!(
+            ProcessGasLimits::try_from(&ProcessGasLimitConf::default())
+                .unwrap()
+                .limit_for(other, U256::from(100), None)
+                .unwrap(),
+            U256::from(110)
+        );
+    }
+}
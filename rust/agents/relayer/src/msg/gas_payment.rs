@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use abacus_base::InboxValidatorManagers;
+use abacus_core::{db::AbacusDB, InboxValidatorManager};
+use ethers::types::U256;
+use eyre::{eyre, Result};
+use tokio::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::settings::{GasPaymentEnforcementPolicy, PriorityConf};
+
+use super::SubmitMessageArgs;
+
+/// Multipliers are applied with a precision of basis points.
+const MULTIPLIER_PRECISION: u64 = 10_000;
+
+/// How long an estimated cost of processing a message is reused while the
+/// message's gas payment is unchanged.
+const ESTIMATE_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// A parsed `GasPaymentEnforcementPolicy`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GasPaymentRequirement {
+    /// Every message is relayed.
+    None,
+    /// The total payment, in origin chain wei, must be at least this amount.
+    Minimum(U256),
+    /// The total payment must be at least the estimated destination cost of
+    /// processing the message, converted into origin chain wei with the
+    /// route's exchange rate and multiplied by `multiplier_bps / 10000`.
+    MeetsEstimatedCost { multiplier_bps: u64 },
+}
+
+impl TryFrom<&GasPaymentEnforcementPolicy> for GasPaymentRequirement {
+    type Error = eyre::Report;
+
+    fn try_from(policy: &GasPaymentEnforcementPolicy) -> Result<Self> {
+        Ok(match policy {
+            GasPaymentEnforcementPolicy::None => Self::None,
+            GasPaymentEnforcementPolicy::Minimum { payment } => Self::Minimum(
+                U256::from_dec_str(payment)
+                    .map_err(|e| eyre!("Invalid minimum gas payment {payment}: {e:?}"))?,
+            ),
+            GasPaymentEnforcementPolicy::MeetsEstimatedCost { multiplier } => {
+                let multiplier: f64 = multiplier
+                    .parse()
+                    .map_err(|e| eyre!("Invalid gas payment multiplier {multiplier}: {e}"))?;
+                if !multiplier.is_finite() || multiplier < 0. {
+                    return Err(eyre!("Invalid gas payment multiplier {multiplier}"));
+                }
+                Self::MeetsEstimatedCost {
+                    multiplier_bps: (multiplier * MULTIPLIER_PRECISION as f64).round() as u64,
+                }
+            }
+        })
+    }
+}
+
+/// The premiums, as multipliers of the estimated cost of processing a message
+/// in basis points and in ascending order, that a gas payment must cover to
+/// earn each priority tier above the normal tier 0. Payments and costs are
+/// compared in origin chain wei, so messages on routes without an exchange
+/// rate stay in tier 0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct PriorityTiers(Vec<u64>);
+
+impl TryFrom<&PriorityConf> for PriorityTiers {
+    type Error = eyre::Report;
+
+    fn try_from(conf: &PriorityConf) -> Result<Self> {
+        let mut premiums = conf
+            .premiums
+            .iter()
+            .map(|premium| {
+                let multiplier: f64 = premium
+                    .parse()
+                    .map_err(|e| eyre!("Invalid priority premium {premium}: {e}"))?;
+                if !multiplier.is_finite() || multiplier < 0. {
+                    return Err(eyre!("Invalid priority premium {premium}"));
+                }
+                Ok((multiplier * MULTIPLIER_PRECISION as f64).round() as u64)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if premiums.len() > u8::MAX as usize {
+            return Err(eyre!("At most {} priority premiums are supported", u8::MAX));
+        }
+        premiums.sort_unstable();
+        Ok(Self(premiums))
+    }
+}
+
+impl PriorityTiers {
+    /// The highest tier whose premium the payment covers.
+    fn tier(&self, payment: U256, estimated_cost: U256) -> u8 {
+        let payment = payment.saturating_mul(MULTIPLIER_PRECISION.into());
+        self.0
+            .iter()
+            .take_while(|&&premium_bps| {
+                payment >= estimated_cost.saturating_mul(premium_bps.into())
+            })
+            .count() as u8
+    }
+}
+
+/// An estimated cost of processing a message, in origin chain wei, along with
+/// the gas payment it was estimated for.
+#[derive(Debug, Clone, Copy)]
+struct CachedEstimate {
+    payment: U256,
+    cost: U256,
+    estimated_at: Instant,
+}
+
+/// The result of checking the gas payment for a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PaymentAssessment {
+    /// Whether the payment meets the requirement for the message to be relayed.
+    pub meets_requirement: bool,
+    /// The priority tier the payment earns the message.
+    pub priority: u8,
+}
+
+/// Decides whether a message has received enough interchain gas payment on the
+/// origin chain to be eligible for delivery to its destination, and which
+/// priority tier the payment earns it.
+#[derive(Debug)]
+pub(crate) struct GasPaymentEnforcer {
+    /// The requirement a message's payment must meet before it is relayed.
+    requirement: GasPaymentRequirement,
+    /// The premiums a message's payment must cover to be prioritized.
+    priority_tiers: PriorityTiers,
+    /// The destination chain InboxValidatorManager, used to estimate the cost
+    /// of processing a message.
+    validator_manager: Arc<InboxValidatorManagers>,
+    /// The price of a destination native token in origin native tokens, used
+    /// to convert estimated costs into origin chain wei. Without it, estimates
+    /// are never compared to payments.
+    exchange_rate: Option<f64>,
+    /// The latest estimated costs of messages that have not yet met the
+    /// requirement, keyed by leaf index.
+    estimates: HashMap<u32, CachedEstimate>,
+    /// Interface to agent rocks DB, where indexed gas payments are summed per leaf.
+    db: AbacusDB,
+}
+
+impl GasPaymentEnforcer {
+    pub fn new(
+        requirement: GasPaymentRequirement,
+        priority_tiers: PriorityTiers,
+        validator_manager: Arc<InboxValidatorManagers>,
+        exchange_rate: Option<f64>,
+        db: AbacusDB,
+    ) -> Self {
+        if exchange_rate.is_none() && !priority_tiers.0.is_empty() {
+            warn!("Messages are not prioritized by gas payment without an exchange rate");
+        }
+        Self {
+            requirement,
+            priority_tiers,
+            validator_manager,
+            exchange_rate,
+            estimates: HashMap::new(),
+            db,
+        }
+    }
+
+    /// Returns whether the total gas payment indexed so far for the message
+    /// meets the requirement. Payments are indexed continuously and cost
+    /// estimates change with destination conditions, so a message that does
+    /// not meet the requirement now may do so later.
+    pub async fn message_meets_requirement(&mut self, msg: &SubmitMessageArgs) -> Result<bool> {
+        Ok(self.assess_payment(msg).await?.meets_requirement)
+    }
+
+    /// Check the total gas payment indexed so far for the message against the
+    /// requirement and the priority tiers. The cost of processing the message
+    /// is estimated at most once, and only if it is needed and can be converted
+    /// into origin chain wei. While a message does not meet the requirement,
+    /// its estimate is reused until its payment changes or the estimate is
+    /// ESTIMATE_MAX_AGE old.
+    pub async fn assess_payment(&mut self, msg: &SubmitMessageArgs) -> Result<PaymentAssessment> {
+        let payment = self.db.retrieve_gas_payment_for_leaf(msg.leaf_index)?;
+        // An estimate is never zero, so avoid the RPC round trips while
+        // nothing has been paid.
+        let needs_estimate = !self.priority_tiers.0.is_empty()
+            || matches!(
+                self.requirement,
+                GasPaymentRequirement::MeetsEstimatedCost { .. }
+            );
+        let estimated_cost = match self.exchange_rate {
+            Some(exchange_rate) if needs_estimate && !payment.is_zero() => {
+                Some(self.estimated_cost(msg, payment, exchange_rate).await?)
+            }
+            _ => None,
+        };
+
+        let meets_requirement = match (self.requirement, estimated_cost) {
+            (GasPaymentRequirement::None, _) => true,
+            (GasPaymentRequirement::Minimum(minimum), _) => payment >= minimum,
+            (GasPaymentRequirement::MeetsEstimatedCost { multiplier_bps }, Some(cost)) => {
+                payment >= cost.saturating_mul(multiplier_bps.into()) / MULTIPLIER_PRECISION
+            }
+            (GasPaymentRequirement::MeetsEstimatedCost { .. }, None) => false,
+        };
+        let priority = estimated_cost
+            .map(|cost| self.priority_tiers.tier(payment, cost))
+            .unwrap_or_default();
+        // A message that meets the requirement leaves the wait queue, so is
+        // rarely assessed again.
+        if meets_requirement {
+            self.estimates.remove(&msg.leaf_index);
+        }
+        Ok(PaymentAssessment {
+            meets_requirement,
+            priority,
+        })
+    }
+
+    /// The estimated cost of processing the message in origin chain wei,
+    /// reusing the message's cached estimate if it is still valid.
+    async fn estimated_cost(
+        &mut self,
+        msg: &SubmitMessageArgs,
+        payment: U256,
+        exchange_rate: f64,
+    ) -> Result<U256> {
+        if let Some(cached) = self.estimates.get(&msg.leaf_index) {
+            if cached.payment == payment && cached.estimated_at.elapsed() < ESTIMATE_MAX_AGE {
+                return Ok(cached.cost);
+            }
+        }
+        let estimate = self
+            .validator_manager
+            .process_estimate_costs(&msg.checkpoint, &msg.committed_message.message, &msg.proof)
+            .await?;
+        let cost = to_origin_wei(estimate.total_cost(), exchange_rate);
+        self.estimates.insert(
+            msg.leaf_index,
+            CachedEstimate {
+                payment,
+                cost,
+                estimated_at: Instant::now(),
+            },
+        );
+        Ok(cost)
+    }
+}
+
+/// Convert an amount of destination chain wei into origin chain wei, given the
+/// price of a destination native token in origin native tokens.
+fn to_origin_wei(destination_wei: U256, exchange_rate: f64) -> U256 {
+    U256::from_f64_lossy(destination_wei.to_f64_lossy() * exchange_rate)
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::U256;
+
+    use crate::settings::{GasPaymentEnforcementPolicy, PriorityConf};
+
+    use super::{to_origin_wei, GasPaymentRequirement, PriorityTiers};
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!(
+            GasPaymentRequirement::try_from(&GasPaymentEnforcementPolicy::Minimum {
+                payment: "1000000000".into()
+            })
+            .unwrap(),
+            GasPaymentRequirement::Minimum(1_000_000_000u64.into())
+        );
+        assert_eq!(
+            GasPaymentRequirement::try_from(&GasPaymentEnforcementPolicy::MeetsEstimatedCost {
+                multiplier: "1.25".into()
+            })
+            .unwrap(),
+            GasPaymentRequirement::MeetsEstimatedCost {
+                multiplier_bps: 12_500
+            }
+        );
+        assert!(GasPaymentRequirement::try_from(
+            &GasPaymentEnforcementPolicy::MeetsEstimatedCost {
+                multiplier: "-1".into()
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn converts_costs_to_origin_wei() {
+        let cost = U256::from(2_000_000_000u64);
+        assert_eq!(to_origin_wei(cost, 1.), cost);
+        assert_eq!(to_origin_wei(cost, 0.0005), U256::from(1_000_000u64));
+        assert_eq!(to_origin_wei(cost, 2000.), U256::from(4_000_000_000_000u64));
+    }
+
+    #[test]
+    fn assigns_priority_tiers() {
+        let tiers = PriorityTiers::try_from(&PriorityConf {
+            premiums: vec!["3".into(), "1.5".into()],
+        })
+        .unwrap();
+        assert_eq!(tiers, PriorityTiers(vec![15_000, 30_000]));
+
+        let cost = U256::from(1000u64);
+        assert_eq!(tiers.tier(U256::from(1499u64), cost), 0);
+        assert_eq!(tiers.tier(U256::from(1500u64), cost), 1);
+        assert_eq!(tiers.tier(U256::from(3000u64), cost), 2);
+        assert_eq!(PriorityTiers::default().tier(U256::MAX, cost), 0);
+    }
+}
@@ -0,0 +1,145 @@
+use ethers::types::{Signature, H256};
+use eyre::Result;
+use serde::Deserialize;
+
+use super::fwd_req::{ForwardRequest, SignedForwardRequest};
+
+/// Gelato's public relay endpoint.
+pub(crate) const DEFAULT_RELAY_URL: &str = "https://relay.gelato.digital";
+
+/// The lifecycle state of a Gelato task, as reported by the task status API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub(crate) enum TaskState {
+    CheckPending,
+    ExecPending,
+    WaitingForConfirmation,
+    ExecSuccess,
+    ExecReverted,
+    Blacklisted,
+    Cancelled,
+    NotFound,
+}
+
+impl TaskState {
+    /// Whether Gelato will make no further progress on the task.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TaskState::ExecSuccess
+                | TaskState::ExecReverted
+                | TaskState::Blacklisted
+                | TaskState::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TaskStatus {
+    pub task_state: TaskState,
+    #[serde(default)]
+    pub transaction_hash: Option<H256>,
+    #[serde(default)]
+    pub last_check_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForwardRequestResponse {
+    task_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TaskStatusResponse {
+    task: TaskStatus,
+}
+
+/// A client for the Gelato Relay HTTP API.
+#[derive(Debug, Clone)]
+pub(crate) struct GelatoClient {
+    http: reqwest::Client,
+    relay_url: String,
+}
+
+impl GelatoClient {
+    pub fn new(relay_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            relay_url: relay_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    /// Submit a signed forward request, returning the id of the task Gelato
+    /// created for it.
+    pub async fn send_forward_request(
+        &self,
+        request: &ForwardRequest,
+        sponsor_signature: &Signature,
+    ) -> Result<String> {
+        let url = format!("{}/metabox-relays/{}", self.relay_url, request.chain_id);
+        let response: ForwardRequestResponse = self
+            .http
+            .post(url)
+            .json(&SignedForwardRequest::new(request, sponsor_signature))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.task_id)
+    }
+
+    /// Get the current status of a task.
+    pub async fn task_status(&self, task_id: &str) -> Result<TaskStatus> {
+        let url = format!("{}/tasks/status/{}", self.relay_url, task_id);
+        let response: TaskStatusResponse = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response.task)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::{GelatoClient, TaskState};
+
+    /// Serve a single HTTP request with the given JSON body and return the
+    /// request line the client sent.
+    async fn serve_once(listener: TcpListener, body: &'static str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0u8; 4096];
+        let n = stream.read(&mut buf).await.unwrap();
+        let request = String::from_utf8_lossy(&buf[..n]).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        request.lines().next().unwrap_or_default().to_owned()
+    }
+
+    #[tokio::test]
+    async fn gets_task_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(
+            listener,
+            r#"{"task":{"chainId":5,"taskId":"0x1234","taskState":"ExecSuccess","transactionHash":"0x1111111111111111111111111111111111111111111111111111111111111111"}}"#,
+        ));
+
+        let status = GelatoClient::new(&url).task_status("0x1234").await.unwrap();
+
+        assert_eq!(status.task_state, TaskState::ExecSuccess);
+        assert!(status.transaction_hash.is_some());
+        assert_eq!(server.await.unwrap(), "GET /tasks/status/0x1234 HTTP/1.1");
+    }
+}
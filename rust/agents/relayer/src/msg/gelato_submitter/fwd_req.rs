@@ -0,0 +1,150 @@
+use std::convert::Infallible;
+
+use ethers::abi::{encode, Token};
+use ethers::types::transaction::eip712::{EIP712Domain, Eip712};
+use ethers::types::{Address, Bytes, Signature, U256};
+use ethers::utils::keccak256;
+use serde::{Serialize, Serializer};
+
+const EIP712_DOMAIN_NAME: &str = "GelatoRelayForwarder";
+const EIP712_DOMAIN_VERSION: &str = "V1";
+const FORWARD_REQUEST_TYPE: &str = "ForwardRequest(uint256 chainId,address target,bytes data,address feeToken,uint256 paymentType,uint256 maxFee,uint256 gas,address sponsor,uint256 sponsorChainId,uint256 nonce,bool enforceSponsorNonce,bool enforceSponsorNonceOrdering)";
+
+/// Fees are deducted from the sponsor's balance in Gelato's gas tank, which
+/// may be funded on a chain other than `chain_id`.
+pub(crate) const PAYMENT_TYPE_ASYNC_GAS_TANK: u64 = 1;
+
+/// A request for Gelato to call `target` with `data` on our behalf. It must be
+/// signed by the sponsor as EIP-712 typed data whose domain is the
+/// GelatoRelayForwarder contract on the destination chain.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ForwardRequest {
+    pub chain_id: u64,
+    pub target: Address,
+    pub data: Bytes,
+    pub fee_token: Address,
+    pub payment_type: u64,
+    #[serde(serialize_with = "serialize_as_dec_str")]
+    pub max_fee: U256,
+    #[serde(serialize_with = "serialize_as_dec_str")]
+    pub gas: U256,
+    pub sponsor: Address,
+    pub sponsor_chain_id: u64,
+    pub nonce: u64,
+    pub enforce_sponsor_nonce: bool,
+    pub enforce_sponsor_nonce_ordering: bool,
+    /// The GelatoRelayForwarder contract, only used as the EIP-712 verifying
+    /// contract.
+    #[serde(skip)]
+    pub forwarder: Address,
+}
+
+/// The body of a forward request submission to the Gelato Relay API.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SignedForwardRequest<'a> {
+    type_id: &'static str,
+    #[serde(flatten)]
+    request: &'a ForwardRequest,
+    sponsor_signature: Bytes,
+}
+
+impl<'a> SignedForwardRequest<'a> {
+    pub fn new(request: &'a ForwardRequest, sponsor_signature: &Signature) -> Self {
+        Self {
+            type_id: "ForwardRequest",
+            request,
+            sponsor_signature: sponsor_signature.to_vec().into(),
+        }
+    }
+}
+
+impl Eip712 for ForwardRequest {
+    type Error = Infallible;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: EIP712_DOMAIN_NAME.into(),
+            version: EIP712_DOMAIN_VERSION.into(),
+            chain_id: self.chain_id.into(),
+            verifying_contract: self.forwarder,
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(FORWARD_REQUEST_TYPE))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::Uint(self.chain_id.into()),
+            Token::Address(self.target),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+            Token::Address(self.fee_token),
+            Token::Uint(self.payment_type.into()),
+            Token::Uint(self.max_fee),
+            Token::Uint(self.gas),
+            Token::Address(self.sponsor),
+            Token::Uint(self.sponsor_chain_id.into()),
+            Token::Uint(self.nonce.into()),
+            Token::Bool(self.enforce_sponsor_nonce),
+            Token::Bool(self.enforce_sponsor_nonce_ordering),
+        ])))
+    }
+}
+
+/// The Gelato Relay API expects amounts as decimal strings rather than the
+/// hex encoding used by ethers.
+fn serialize_as_dec_str<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use ethers::types::{Address, Signature, U256};
+    use serde_json::json;
+
+    use super::{ForwardRequest, SignedForwardRequest, PAYMENT_TYPE_ASYNC_GAS_TANK};
+
+    #[test]
+    fn serializes_signed_request() {
+        let request = ForwardRequest {
+            chain_id: 5,
+            target: Address::repeat_byte(0x11),
+            data: vec![0xab, 0xcd].into(),
+            fee_token: Address::repeat_byte(0xee),
+            payment_type: PAYMENT_TYPE_ASYNC_GAS_TANK,
+            max_fee: U256::exp10(18),
+            gas: 200_000u64.into(),
+            sponsor: Address::repeat_byte(0x22),
+            sponsor_chain_id: 5,
+            nonce: 0,
+            enforce_sponsor_nonce: false,
+            enforce_sponsor_nonce_ordering: false,
+            forwarder: Address::repeat_byte(0x33),
+        };
+        let signature = Signature {
+            r: U256::one(),
+            s: U256::one(),
+            v: 27,
+        };
+
+        let body = serde_json::to_value(SignedForwardRequest::new(&request, &signature)).unwrap();
+
+        assert_eq!(body["typeId"], json!("ForwardRequest"));
+        assert_eq!(body["chainId"], json!(5));
+        assert_eq!(
+            body["target"],
+            json!("0x1111111111111111111111111111111111111111")
+        );
+        assert_eq!(body["data"], json!("0xabcd"));
+        assert_eq!(body["maxFee"], json!("1000000000000000000"));
+        assert_eq!(body["gas"], json!("200000"));
+        assert_eq!(body["paymentType"], json!(1));
+        assert!(body.get("forwarder").is_none());
+        assert_eq!(body["sponsorSignature"].as_str().unwrap().len(), 2 + 65 * 2);
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use abacus_base::InboxContracts;
+use abacus_core::{ChainCommunicationError, Inbox, InboxValidatorManager, MessageStatus};
+use ethers::signers::Signer;
+use eyre::{bail, Result};
+use tokio::time::{sleep, Instant};
+use tracing::{info, instrument, warn};
+
+use super::client::{GelatoClient, TaskState};
+use super::fwd_req::{ForwardRequest, PAYMENT_TYPE_ASYNC_GAS_TANK};
+use super::GelatoSubmitterConf;
+use crate::msg::SubmitMessageArgs;
+
+/// How often the status of a submitted task is polled.
+const TASK_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// How long we wait for a submitted task to reach a final state before
+/// treating the attempt as failed.
+const TASK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+/// How long to wait before retrying a failed attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+/// How many times getting the message status is attempted before the op fails.
+const MESSAGE_STATUS_ATTEMPTS: u32 = 5;
+
+/// Delivers a single message by submitting forward requests to Gelato until
+/// one succeeds or the retry limit is reached.
+#[derive(Debug)]
+pub(crate) struct ForwardRequestOp {
+    conf: Arc<GelatoSubmitterConf>,
+    client: GelatoClient,
+    inbox_contracts: InboxContracts,
+    msg: SubmitMessageArgs,
+}
+
+impl ForwardRequestOp {
+    pub fn new(
+        conf: Arc<GelatoSubmitterConf>,
+        client: GelatoClient,
+        inbox_contracts: InboxContracts,
+        msg: SubmitMessageArgs,
+    ) -> Self {
+        Self {
+            conf,
+            client,
+            inbox_contracts,
+            msg,
+        }
+    }
+
+    /// Run the op to completion, returning the message along with whether Gelato
+    /// reports it delivered. Failures are retried by the submitter according to
+    /// the retry policy for their kind.
+    pub async fn run(self) -> (SubmitMessageArgs, Result<(), ChainCommunicationError>) {
+        let result = self.deliver().await;
+        (self.msg, result)
+    }
+
+    #[instrument(skip(self), fields(leaf_index = self.msg.leaf_index))]
+    async fn deliver(&self) -> Result<(), ChainCommunicationError> {
+        for attempt in 0..=self.conf.max_retries {
+            if attempt > 0 {
+                sleep(RETRY_DELAY).await;
+            }
+
+            // Another relayer, or a task from an earlier attempt that we gave
+            // up waiting on, may have delivered the message in the meantime.
+            if self.already_processed().await? {
+                info!("Message already processed");
+                return Ok(());
+            }
+
+            let task_id = match self.send_forward_request().await {
+                Ok(task_id) => task_id,
+                Err(e) => {
+                    warn!(attempt, error = ?e, "Failed to submit forward request");
+                    continue;
+                }
+            };
+            info!(attempt, task_id = %task_id, "Submitted forward request");
+
+            match self.wait_for_task(&task_id).await {
+                Ok(TaskState::ExecSuccess) => {
+                    info!(task_id = %task_id, "Forward request executed");
+                    return Ok(());
+                }
+                Ok(state) => {
+                    warn!(attempt, task_id = %task_id, ?state, "Forward request failed");
+                }
+                Err(e) => {
+                    warn!(attempt, task_id = %task_id, error = ?e, "Forward request did not complete");
+                }
+            }
+        }
+        Err(ChainCommunicationError::Reverted(format!(
+            "message not delivered after {} forward request attempts",
+            self.conf.max_retries + 1
+        )))
+    }
+
+    /// Whether the message has been processed on the inbox. A failure to get
+    /// the message status is retried each poll interval, up to
+    /// MESSAGE_STATUS_ATTEMPTS times.
+    async fn already_processed(&self) -> Result<bool, ChainCommunicationError> {
+        let leaf = self.msg.committed_message.to_leaf();
+        let mut attempt = 1;
+        loop {
+            match self.inbox_contracts.inbox.message_status(leaf).await {
+                Ok(status) => return Ok(status == MessageStatus::Processed),
+                Err(e) if attempt >= MESSAGE_STATUS_ATTEMPTS => return Err(e),
+                Err(e) => warn!(attempt, error = ?e, "Failed to get message status"),
+            }
+            attempt += 1;
+            sleep(TASK_STATUS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Build, sign and submit a forward request processing the message.
+    async fn send_forward_request(&self) -> Result<String> {
+        let validator_manager = &self.inbox_contracts.validator_manager;
+        let estimate = validator_manager
+            .process_estimate_costs(
+                &self.msg.checkpoint,
+                &self.msg.committed_message.message,
+                &self.msg.proof,
+            )
+            .await?;
+        let request = ForwardRequest {
+            chain_id: self.conf.chain_id,
+            target: validator_manager.contract_address(),
+            data: validator_manager
+                .process_calldata(
+                    &self.msg.checkpoint,
+                    &self.msg.committed_message.message,
+                    &self.msg.proof,
+                )
+                .into(),
+            fee_token: self.conf.fee_token,
+            payment_type: PAYMENT_TYPE_ASYNC_GAS_TANK,
+            max_fee: self.conf.max_fee,
+            gas: estimate.gas_limit,
+            sponsor: self.conf.sponsor.address(),
+            sponsor_chain_id: self.conf.chain_id,
+            nonce: 0,
+            enforce_sponsor_nonce: false,
+            enforce_sponsor_nonce_ordering: false,
+            forwarder: self.conf.forwarder,
+        };
+        let signature = self.conf.sponsor.sign_typed_data(&request).await?;
+        self.client.send_forward_request(&request, &signature).await
+    }
+
+    /// Poll the task until it reaches a final state, returning that state.
+    async fn wait_for_task(&self, task_id: &str) -> Result<TaskState> {
+        let deadline = Instant::now() + TASK_TIMEOUT;
+        loop {
+            sleep(TASK_STATUS_POLL_INTERVAL).await;
+            match self.client.task_status(task_id).await {
+                Ok(status) if status.task_state.is_final() => return Ok(status.task_state),
+                Ok(status) => {
+                    info!(task_id, state = ?status.task_state, message = ?status.last_check_message, "Forward request pending");
+                }
+                // The task may not be queryable immediately after submission.
+                Err(e) => warn!(task_id, error = ?e, "Failed to get forward request status"),
+            }
+            if Instant::now() >= deadline {
+                bail!("Timed out waiting for task {task_id}");
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use abacus_base::{chains::GelatoConf, CoreMetrics, InboxContracts};
+use abacus_core::{db::AbacusDB, ChainCommunicationError, Inbox, MessageStatus, Signers};
+use ethers::types::{Address, U256};
+use eyre::{bail, eyre, Result};
+use futures_util::future::BoxFuture;
+use futures_util::stream::FuturesUnordered;
+use futures_util::{FutureExt, StreamExt};
+use prometheus::{Histogram, IntCounter, IntGauge};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, error, info, info_span, instrument::Instrumented, warn, Instrument};
+
+use self::client::{GelatoClient, DEFAULT_RELAY_URL};
+use self::fwd_req_op::ForwardRequestOp;
+
+use super::{
+    control::SubmitterControl,
+    dead_letters::DeadLetters,
+    gas_payment::GasPaymentEnforcer,
+    queues::{retry_or_dead_letter, PendingVerification, VERIFICATION_TIMEOUT},
+    scheduling_state::restore_scheduling_state,
+    SubmitMessageArgs,
+};
+
+mod client;
+mod fwd_req;
+mod fwd_req_op;
+
+/// The address Gelato uses to denote the native token of a chain.
+const NATIVE_FEE_TOKEN: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
+const DEFAULT_MAX_RETRIES: u32 = 5;
+const DEFAULT_MAX_IN_FLIGHT: usize = 10;
+
+/// A parsed `GelatoConf` along with the signer that sponsors forward requests.
+#[derive(Debug, Clone)]
+pub(crate) struct GelatoSubmitterConf {
+    pub chain_id: u64,
+    pub relay_url: String,
+    pub forwarder: Address,
+    pub fee_token: Address,
+    pub max_fee: U256,
+    pub max_retries: u32,
+    pub max_in_flight: usize,
+    /// Signs forward requests. Gelato fees are paid from its gas tank
+    /// balance.
+    pub sponsor: Signers,
+}
+
+impl GelatoSubmitterConf {
+    pub fn new(conf: &GelatoConf, sponsor: Signers) -> Result<Self> {
+        if !conf.enabled_for_message_submission {
+            bail!("Gelato is not enabled for message submission");
+        }
+        let required = |value: &Option<String>, key: &str| {
+            value
+                .as_deref()
+                .ok_or_else(|| eyre!("Gelato {key} is required for message submission"))
+        };
+        let chain_id = required(&conf.chain_id, "chainId")?;
+        let forwarder_address = required(&conf.forwarder_address, "forwarderAddress")?;
+        let max_fee = required(&conf.max_fee, "maxFee")?;
+        Ok(Self {
+            chain_id: chain_id
+                .parse()
+                .map_err(|e| eyre!("Invalid gelato chain id: {e}"))?,
+            relay_url: conf
+                .relay_url
+                .clone()
+                .unwrap_or_else(|| DEFAULT_RELAY_URL.into()),
+            forwarder: forwarder_address.parse()?,
+            fee_token: conf
+                .fee_token
+                .as_deref()
+                .unwrap_or(NATIVE_FEE_TOKEN)
+                .parse()?,
+            max_fee: U256::from_dec_str(max_fee)
+                .map_err(|e| eyre!("Invalid gelato max fee: {e:?}"))?,
+            max_retries: conf
+                .max_retries
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or(DEFAULT_MAX_RETRIES),
+            max_in_flight: conf
+                .max_in_flight
+                .as_deref()
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or(DEFAULT_MAX_IN_FLIGHT),
+            sponsor,
+        })
+    }
+}
+
+/// A spawned forward request op, resolving to its message and whether it was delivered.
+/// An op that panics or is cancelled resolves to an error, so that its message can be
+/// tried again.
+type ForwardRequestOpHandle =
+    BoxFuture<'static, (SubmitMessageArgs, Result<(), ChainCommunicationError>)>;
+
+#[derive(Debug)]
+pub(crate) struct GelatoSubmitter {
+    /// Source of messages to submit.
+    rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
+
+    /// Interface to Inbox / InboxValidatorManager on the destination chain.
+    /// Used to encode the process call and to determine whether or not to
+    /// re-submit a forward request to Gelato, if e.g. the message has already
+    /// been processed by some other relayer.
+    inbox_contracts: InboxContracts,
+
+    /// Interface to agent rocks DB for e.g. writing delivery status upon completion.
+    db: AbacusDB,
+
+    /// Decides whether a message has received enough gas payment to be
+    /// forwarded to Gelato.
+    gas_payment_enforcer: GasPaymentEnforcer,
+
+    conf: Arc<GelatoSubmitterConf>,
+    client: GelatoClient,
+
+    /// Messages we want to eventually submit but haven't yet, e.g. due to
+    /// insufficient gas payment or the in-flight limit.
+    wait_queue: Vec<SubmitMessageArgs>,
+
+    /// Forward request ops that have been spawned and not yet completed.
+    in_flight: FuturesUnordered<ForwardRequestOpHandle>,
+
+    /// Messages Gelato reports delivered, whose processing is not yet confirmed as final on
+    /// the destination chain.
+    verification_queue: Vec<PendingVerification>,
+
+    /// Messages whose delivery has failed too often, held until re-driven.
+    dead_letters: DeadLetters,
+
+    /// Commands from operators and the queue snapshot published to them.
+    control: SubmitterControl,
+
+    /// Number of blocks on the destination chain after which processing is considered final.
+    finality_blocks: u32,
+
+    /// The number of times delivery of a message may fail before it is given up on.
+    max_retries: Option<u32>,
+
+    metrics: GelatoSubmitterMetrics,
+}
+
+impl GelatoSubmitter {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        conf: GelatoSubmitterConf,
+        rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
+        control: SubmitterControl,
+        inbox_contracts: InboxContracts,
+        db: AbacusDB,
+        gas_payment_enforcer: GasPaymentEnforcer,
+        finality_blocks: u32,
+        max_retries: Option<u32>,
+        metrics: GelatoSubmitterMetrics,
+    ) -> Self {
+        Self {
+            rx,
+            inbox_contracts,
+            dead_letters: DeadLetters::new(db.clone()),
+            control,
+            db,
+            gas_payment_enforcer,
+            client: GelatoClient::new(&conf.relay_url),
+            conf: Arc::new(conf),
+            wait_queue: Vec::new(),
+            in_flight: FuturesUnordered::new(),
+            verification_queue: Vec::new(),
+            finality_blocks,
+            max_retries,
+            metrics,
+        }
+    }
+
+    pub fn spawn(mut self) -> Instrumented<JoinHandle<Result<()>>> {
+        tokio::spawn(async move { self.work_loop().await })
+            .instrument(info_span!("submitter work loop"))
+    }
+
+    /// The Gelato relay framework allows us to submit ops in
+    /// parallel, subject to certain retry rules. Therefore all we do
+    /// here is spin forever asking for work from the rx channel, then
+    /// spawn the work to submit to gelato in a root tokio task, up to
+    /// `max_in_flight` ops at a time.
+    ///
+    /// It is possible that there has not been sufficient interchain
+    /// gas deposited in the InterchainGasPaymaster account on the source
+    /// chain, so we also keep a wait queue of ops that we
+    /// periodically scan for any gas updates.
+    async fn work_loop(&mut self) -> Result<()> {
+        loop {
+            self.tick().await?;
+            tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+        }
+    }
+
+    /// Extracted from main loop to enable testing submitter state
+    /// after each tick, e.g. in response to a change in environment
+    /// conditions like values in InterchainGasPaymaster.
+    async fn tick(&mut self) -> Result<()> {
+        // Pull any messages sent by processor over channel.
+        loop {
+            match self.rx.try_recv() {
+                Ok(mut msg) => {
+                    // Carry over retries and backoff, or dead-lettering, from before a restart.
+                    restore_scheduling_state(&self.db, &mut msg)?;
+                    if let Some(msg) = self.dead_letters.restore(msg)? {
+                        self.wait_queue.push(msg);
+                    }
+                }
+                Err(TryRecvError::Empty) => {
+                    break;
+                }
+                Err(_) => {
+                    bail!("Disconnected rcvq or fatal err");
+                }
+            }
+        }
+
+        // Apply any commands from operators, e.g. to re-drive dead-lettered messages. Gelato
+        // has no run queue, so retried messages join the wait queue, without backoff.
+        let mut retried = VecDeque::new();
+        self.control
+            .apply_commands(&mut retried, &mut self.wait_queue, &mut self.dead_letters)?;
+        self.wait_queue.extend(retried);
+
+        // Handle any ops that have completed since the last tick. Messages Gelato reports
+        // delivered wait for their processing to be final, while messages that could not be
+        // delivered are retried or dead-lettered according to the shared retry policy.
+        while let Some(Some((msg, result))) = self.in_flight.next().now_or_never() {
+            match result {
+                Ok(()) => self.verification_queue.push(PendingVerification {
+                    msg,
+                    since: Instant::now(),
+                }),
+                Err(e) => {
+                    warn!(leaf_index = msg.leaf_index, error = ?e, "Message delivery via Gelato failed");
+                    if let Some((msg, _)) = retry_or_dead_letter(
+                        &self.db,
+                        &mut self.dead_letters,
+                        self.max_retries,
+                        msg,
+                        &e,
+                    ) {
+                        self.wait_queue.push(msg);
+                    }
+                }
+            }
+        }
+
+        self.verify_processed_messages().await?;
+
+        // Spawn ops for messages with sufficient gas payment that are not backing off, in
+        // leaf index order, until the in-flight limit is reached.
+        self.wait_queue
+            .sort_by_key(|msg| (msg.num_retries, msg.leaf_index));
+        let now = Instant::now();
+        let mut still_waiting = Vec::new();
+        for msg in std::mem::take(&mut self.wait_queue) {
+            if self.control.paused()
+                || self.in_flight.len() >= self.conf.max_in_flight
+                || msg.next_attempt_after.map_or(false, |after| after > now)
+            {
+                still_waiting.push(msg);
+                continue;
+            }
+            match self
+                .gas_payment_enforcer
+                .message_meets_requirement(&msg)
+                .await
+            {
+                Ok(true) => {
+                    debug!(leaf_index = msg.leaf_index, "Submitting message via Gelato");
+                    let op = ForwardRequestOp::new(
+                        self.conf.clone(),
+                        self.client.clone(),
+                        self.inbox_contracts.clone(),
+                        msg.clone(),
+                    );
+                    let handle = tokio::spawn(op.run());
+                    self.in_flight.push(
+                        async move {
+                            handle.await.unwrap_or_else(|e| {
+                                error!(leaf_index = msg.leaf_index, error = ?e, "Forward request op did not complete");
+                                (msg, Err(ChainCommunicationError::CustomError(
+                                    format!("Forward request op did not complete: {e}").into(),
+                                )))
+                            })
+                        }
+                        .boxed(),
+                    );
+                }
+                Ok(false) => {
+                    debug!(
+                        leaf_index = msg.leaf_index,
+                        "Insufficient gas payment, keeping message in wait queue"
+                    );
+                    still_waiting.push(msg);
+                }
+                Err(e) => {
+                    warn!(
+                        leaf_index = msg.leaf_index,
+                        error = ?e,
+                        "Unable to check gas payment, keeping message in wait queue"
+                    );
+                    still_waiting.push(msg);
+                }
+            }
+        }
+        self.wait_queue = still_waiting;
+
+        self.metrics
+            .wait_queue_length_gauge
+            .set(self.wait_queue.len() as i64);
+        self.metrics
+            .in_flight_length_gauge
+            .set(self.in_flight.len() as i64);
+        self.control.publish(&VecDeque::new(), &self.wait_queue);
+
+        Ok(())
+    }
+
+    /// Commit messages whose processing is now final on the destination chain. Messages not
+    /// confirmed within VERIFICATION_TIMEOUT go back to the wait queue, as Gelato may have
+    /// reported success for a transaction that did not process them.
+    async fn verify_processed_messages(&mut self) -> Result<()> {
+        for pending in std::mem::take(&mut self.verification_queue) {
+            let leaf = pending.msg.committed_message.to_leaf();
+            match self
+                .inbox_contracts
+                .inbox
+                .finalized_message_status(leaf, self.finality_blocks)
+                .await
+            {
+                Ok(MessageStatus::Processed) => {
+                    self.record_message_process_success(&pending.msg)?;
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(
+                        leaf_index = pending.msg.leaf_index,
+                        error = ?e,
+                        "Unable to verify message processing"
+                    );
+                }
+            }
+            if pending.since.elapsed() > VERIFICATION_TIMEOUT {
+                warn!(
+                    leaf_index = pending.msg.leaf_index,
+                    "Message processing was not confirmed in time, returning it to the wait queue"
+                );
+                self.wait_queue.push(pending.msg);
+            } else {
+                self.verification_queue.push(pending);
+            }
+        }
+        Ok(())
+    }
+
+    /// Record in AbacusDB and various metrics that the message's processing is final, after
+    /// which we will never re-attempt processing it.
+    fn record_message_process_success(&mut self, msg: &SubmitMessageArgs) -> Result<()> {
+        self.db.mark_leaf_as_processed(msg.leaf_index)?;
+        self.dead_letters.remove(msg.leaf_index)?;
+        self.db.remove_scheduling_state(msg.leaf_index)?;
+        info!(leaf_index = msg.leaf_index, "Message processed via Gelato");
+        self.metrics
+            .queue_duration_hist
+            .observe((Instant::now() - msg.enqueue_time).as_secs_f64());
+        self.metrics.max_submitted_leaf_index =
+            std::cmp::max(self.metrics.max_submitted_leaf_index, msg.leaf_index);
+        self.metrics
+            .processed_gauge
+            .set(self.metrics.max_submitted_leaf_index as i64);
+        self.metrics.messages_processed_count.inc();
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct GelatoSubmitterMetrics {
+    wait_queue_length_gauge: IntGauge,
+    in_flight_length_gauge: IntGauge,
+    queue_duration_hist: Histogram,
+    processed_gauge: IntGauge,
+    messages_processed_count: IntCounter,
+
+    /// Private state used to update actual metrics each tick.
+    max_submitted_leaf_index: u32,
+}
+
+impl GelatoSubmitterMetrics {
+    pub fn new(metrics: &CoreMetrics, outbox_chain: &str, inbox_chain: &str) -> Self {
+        Self {
+            wait_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "gelato_wait_queue",
+            ]),
+            in_flight_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "gelato_in_flight",
+            ]),
+            queue_duration_hist: metrics
+                .submitter_queue_duration_histogram()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            messages_processed_count: metrics
+                .messages_processed_count()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            processed_gauge: metrics.last_known_message_leaf_index().with_label_values(&[
+                "message_processed",
+                outbox_chain,
+                inbox_chain,
+            ]),
+            max_submitted_leaf_index: 0,
+        }
+    }
+}
@@ -1,11 +1,24 @@
 use std::cmp::Ordering;
 
-use abacus_core::{accumulator::merkle::Proof, CommittedMessage, MultisigSignedCheckpoint};
+use abacus_core::{
+    accumulator::merkle::Proof, CommittedMessage, MultisigSignedCheckpoint, ProcessCall,
+};
 
 use tokio::time::Instant;
 
+pub mod balance;
+pub mod batch;
+pub mod concurrent_submitter;
+pub mod control;
+pub mod dead_letters;
+pub mod gas_limit;
+pub mod gas_payment;
 pub mod gelato_submitter;
 pub mod processor;
+pub mod queues;
+pub mod retry;
+pub mod scheduler;
+pub mod scheduling_state;
 pub mod serial_submitter;
 
 /// Processor scans DB for new messages and sends relevant messages
@@ -17,13 +30,11 @@ pub mod serial_submitter;
 /// A SubmitMessageOp describes the message that the submitter should
 /// try to submit.
 ///
-/// Right now there are two strategies: serial and Gelato.
+/// Right now there are three strategies: serial, optionally batching messages into one
+/// transaction, concurrent and Gelato.
 ///
 /// In the future it could make sense for there to be more, some ideas are:
-///   - BatchingMessagesSubmitter
 ///   - ShardedWalletSubmitter (to get parallelism / nonce)
-///   - SpeculativeSerializedSubmitter (batches with higher optimistic
-///     nonces, recovery behavior)
 ///   - FallbackProviderSubmitter (Serialized, but if some RPC provider sucks,
 ///   switch everyone to new one)
 
@@ -35,6 +46,16 @@ pub struct SubmitMessageArgs {
     pub proof: Proof,
     pub enqueue_time: Instant,
     num_retries: u32,
+    /// The number of failed attempts, of `num_retries`, that reverted. Only these count
+    /// towards the limit on processing retries.
+    num_reverts: u32,
+    /// The priority tier earned by the message's gas payment. Messages in higher tiers are
+    /// submitted first.
+    priority: u8,
+    /// The message should not be attempted again before this time.
+    next_attempt_after: Option<Instant>,
+    /// Unix timestamp, in seconds, of the first failed processing attempt.
+    first_failure_at: Option<u64>,
 }
 
 impl SubmitMessageArgs {
@@ -52,6 +73,19 @@ impl SubmitMessageArgs {
             proof,
             enqueue_time,
             num_retries: 0,
+            num_reverts: 0,
+            priority: 0,
+            next_attempt_after: None,
+            first_failure_at: None,
+        }
+    }
+
+    /// The call processing the message, as a member of a batch.
+    pub fn process_call(&self) -> ProcessCall<'_> {
+        ProcessCall {
+            multisig_signed_checkpoint: &self.checkpoint,
+            message: &self.committed_message.message,
+            proof: &self.proof,
         }
     }
 }
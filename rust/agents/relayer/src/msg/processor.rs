@@ -1,4 +1,4 @@
-use std::{sync::Arc, time::Duration};
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
 
 use eyre::Result;
 use prometheus::IntGauge;
@@ -7,14 +7,14 @@ use tokio::{
     task::JoinHandle,
     time::{Instant, MissedTickBehavior},
 };
-use tracing::{debug, info_span, instrument, instrument::Instrumented, warn, Instrument};
+use tracing::{debug, info, info_span, instrument, instrument::Instrumented, warn, Instrument};
 
 use abacus_base::{CoreMetrics, InboxContracts, Outboxes};
 use abacus_core::{
     db::AbacusDB, AbacusCommon, AbacusContract, CommittedMessage, MultisigSignedCheckpoint, Outbox,
 };
 
-use crate::{merkle_tree_builder::MerkleTreeBuilder, settings::matching_list::MatchingList};
+use crate::{merkle_tree_builder::MerkleTreeBuilder, relay_lists::RelayLists};
 
 use super::SubmitMessageArgs;
 
@@ -23,13 +23,20 @@ pub(crate) struct MessageProcessor {
     outbox: Outboxes,
     db: AbacusDB,
     inbox_contracts: InboxContracts,
-    whitelist: Arc<MatchingList>,
-    blacklist: Arc<MatchingList>,
+    relay_lists_rx: watch::Receiver<Arc<RelayLists>>,
+    /// The relay lists messages were last checked against.
+    relay_lists: Arc<RelayLists>,
     metrics: MessageProcessorMetrics,
     tx_msg: mpsc::UnboundedSender<SubmitMessageArgs>,
     ckpt_rx: watch::Receiver<Option<MultisigSignedCheckpoint>>,
     prover_sync: MerkleTreeBuilder,
     message_leaf_index: u32,
+    /// Leaf indices of messages skipped because of the relay lists, which are re-evaluated
+    /// when the lists are replaced.
+    skipped_by_lists: BTreeSet<u32>,
+    /// Leaf indices of skipped messages that the current relay lists allow, which are sent
+    /// to the submitter before any new message.
+    newly_allowed: BTreeSet<u32>,
 }
 
 impl MessageProcessor {
@@ -38,8 +45,7 @@ impl MessageProcessor {
         outbox: Outboxes,
         db: AbacusDB,
         inbox_contracts: InboxContracts,
-        whitelist: Arc<MatchingList>,
-        blacklist: Arc<MatchingList>,
+        relay_lists_rx: watch::Receiver<Arc<RelayLists>>,
         metrics: MessageProcessorMetrics,
         tx_msg: mpsc::UnboundedSender<SubmitMessageArgs>,
         ckpt_rx: watch::Receiver<Option<MultisigSignedCheckpoint>>,
@@ -48,13 +54,15 @@ impl MessageProcessor {
             outbox,
             db: db.clone(),
             inbox_contracts,
-            whitelist,
-            blacklist,
+            relay_lists: relay_lists_rx.borrow().clone(),
+            relay_lists_rx,
             metrics,
             tx_msg,
             ckpt_rx,
             prover_sync: MerkleTreeBuilder::new(db),
             message_leaf_index: 0,
+            skipped_by_lists: BTreeSet::new(),
+            newly_allowed: BTreeSet::new(),
         }
     }
 
@@ -96,6 +104,12 @@ impl MessageProcessor {
             .processor_loop_gauge
             .set(self.message_leaf_index as i64);
 
+        self.reevaluate_skipped_messages()?;
+        if let Some(leaf_index) = self.newly_allowed.iter().next().copied() {
+            self.newly_allowed.remove(&leaf_index);
+            return self.send_newly_allowed(leaf_index).await;
+        }
+
         // Scan until we find next index without delivery confirmation.
         if self
             .db
@@ -146,38 +160,115 @@ impl MessageProcessor {
         }
 
         // Skip if not whitelisted.
-        if !self.whitelist.msg_matches(&message.message, true) {
+        if !self
+            .relay_lists
+            .whitelist
+            .msg_matches(&message.message, true)
+        {
             debug!(
                 inbox_name=?self.inbox_contracts.inbox.chain_name(),
                 local_domain=?self.inbox_contracts.inbox.local_domain(),
                 dst=?message.message.destination,
-                whitelist=?self.whitelist,
+                whitelist=?self.relay_lists.whitelist,
                 msg=?message,
                 "Message not whitelisted, skipping idx {}", self.message_leaf_index);
+            self.skipped_by_lists.insert(self.message_leaf_index);
             self.message_leaf_index += 1;
             return Ok(());
         }
 
         // skip if the message is blacklisted
-        if self.blacklist.msg_matches(&message.message, false) {
+        if self
+            .relay_lists
+            .blacklist
+            .msg_matches(&message.message, false)
+        {
             debug!(
                 inbox_name=?self.inbox_contracts.inbox.chain_name(),
                 local_domain=?self.inbox_contracts.inbox.local_domain(),
                 dst=?message.message.destination,
-                blacklist=?self.blacklist,
+                blacklist=?self.relay_lists.blacklist,
                 msg=?message,
                 "Message blacklisted, skipping idx {}", self.message_leaf_index);
+            self.skipped_by_lists.insert(self.message_leaf_index);
             self.message_leaf_index += 1;
             return Ok(());
         }
 
-        // If validator hasn't published checkpoint covering self.message_leaf_index yet, wait
-        // until it has, before forwarding the message to the submitter channel.
+        if self.send_to_submitter(message).await? {
+            self.message_leaf_index += 1;
+        }
+        Ok(())
+    }
+
+    /// If the relay lists have been replaced, find the skipped messages the new lists allow.
+    /// Messages already sent to the submitter are unaffected by the new lists.
+    fn reevaluate_skipped_messages(&mut self) -> Result<()> {
+        let latest = self.relay_lists_rx.borrow().clone();
+        if Arc::ptr_eq(&latest, &self.relay_lists) {
+            return Ok(());
+        }
+        self.relay_lists = latest;
+        let mut candidates = std::mem::take(&mut self.skipped_by_lists);
+        candidates.append(&mut self.newly_allowed);
+        for leaf_index in candidates {
+            let allowed = match self.db.message_by_leaf_index(leaf_index)? {
+                Some(raw) => self
+                    .relay_lists
+                    .allows(&CommittedMessage::try_from(raw)?.message),
+                None => false,
+            };
+            if allowed {
+                self.newly_allowed.insert(leaf_index);
+            } else {
+                self.skipped_by_lists.insert(leaf_index);
+            }
+        }
+        info!(
+            relay_lists = %self.relay_lists,
+            newly_allowed = self.newly_allowed.len(),
+            "Relay lists replaced, re-evaluated skipped messages"
+        );
+        Ok(())
+    }
+
+    /// Send a previously skipped message that the relay lists now allow to the submitter,
+    /// unless it has been processed in the meantime, e.g. by another relayer.
+    async fn send_newly_allowed(&mut self, leaf_index: u32) -> Result<()> {
+        if self
+            .db
+            .retrieve_leaf_processing_status(leaf_index)?
+            .is_some()
+        {
+            return Ok(());
+        }
+        let message = match self.db.message_by_leaf_index(leaf_index)? {
+            Some(raw) => CommittedMessage::try_from(raw)?,
+            None => return Ok(()),
+        };
+        info!(
+            leaf_index,
+            "Sending message allowed by new relay lists to submitter"
+        );
+        if !self.send_to_submitter(message).await? {
+            self.newly_allowed.insert(leaf_index);
+        }
+        Ok(())
+    }
+
+    /// Wait for a checkpoint covering the message, then send the message with a proof against
+    /// that checkpoint to the submitter. Returns false if the message's leaf is unexpectedly
+    /// missing from the DB, in which case it should be tried again.
+    async fn send_to_submitter(&mut self, message: CommittedMessage) -> Result<bool> {
+        let leaf_index = message.leaf_index;
+
+        // If validator hasn't published checkpoint covering leaf_index yet, wait until it has,
+        // before forwarding the message to the submitter channel.
         let mut ckpt;
         loop {
             ckpt = self.ckpt_rx.borrow().clone();
             match &ckpt {
-                Some(ckpt) if ckpt.checkpoint.index >= self.message_leaf_index => {
+                Some(ckpt) if ckpt.checkpoint.index >= leaf_index => {
                     break;
                 }
                 _ => {
@@ -186,7 +277,7 @@ impl MessageProcessor {
             }
         }
         let checkpoint = ckpt.unwrap();
-        assert!(checkpoint.checkpoint.index >= self.message_leaf_index);
+        assert!(checkpoint.checkpoint.index >= leaf_index);
 
         // Include proof against checkpoint for message in the args provided to the submitter.
         if checkpoint.checkpoint.index >= self.prover_sync.count() {
@@ -195,34 +286,22 @@ impl MessageProcessor {
                 .await?;
         }
         assert_eq!(checkpoint.checkpoint.index + 1, self.prover_sync.count());
-        let proof = self.prover_sync.get_proof(self.message_leaf_index)?;
+        let proof = self.prover_sync.get_proof(leaf_index)?;
 
-        if self
-            .db
-            .leaf_by_leaf_index(self.message_leaf_index)?
-            .is_some()
-        {
-            debug!(
-                "Sending message at idx {} to submitter",
-                self.message_leaf_index
-            );
+        if self.db.leaf_by_leaf_index(leaf_index)?.is_some() {
+            debug!("Sending message at idx {} to submitter", leaf_index);
             // Finally, build the submit arg and dispatch it to the submitter.
-            let submit_args = SubmitMessageArgs::new(
-                self.message_leaf_index,
-                message,
-                checkpoint,
-                proof,
-                Instant::now(),
-            );
+            let submit_args =
+                SubmitMessageArgs::new(leaf_index, message, checkpoint, proof, Instant::now());
             self.tx_msg.send(submit_args)?;
-            self.message_leaf_index += 1;
+            Ok(true)
         } else {
             warn!(
-                idx=self.message_leaf_index,
+                idx=leaf_index,
                 inbox_name=?self.inbox_contracts.inbox.chain_name(),
                 "Unexpected missing leaf_by_leaf_index");
+            Ok(false)
         }
-        Ok(())
     }
 
     /// Spawn a task to update the outbox state gauge.
@@ -0,0 +1,555 @@
+use std::collections::VecDeque;
+
+use abacus_base::{CoreMetrics, InboxContracts};
+use abacus_core::db::AbacusDB;
+use abacus_core::{
+    ChainCommunicationError, Inbox, MessageStatus, TxOutcome, PERMANENT_PROCESS_REVERTS,
+    UNDECODED_REVERT_REASON,
+};
+use ethers::types::U256;
+use eyre::{bail, Result};
+use prometheus::{Counter, Gauge, Histogram, IntCounter, IntCounterVec, IntGauge};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use super::{
+    control::SubmitterControl,
+    dead_letters::{unix_timestamp, DeadLetters},
+    gas_limit::{record_gas_used, ProcessGasLimits},
+    gas_payment::GasPaymentEnforcer,
+    retry::{RequeuePosition, RetryPolicy, SubmitErrorKind},
+    scheduler::{FairScheduler, SenderQueueMetrics},
+    scheduling_state::{record_failure, restore_scheduling_state},
+    SubmitMessageArgs,
+};
+
+/// How long a submitted message may wait for its processing to be confirmed as final before
+/// it is returned to the wait queue.
+pub(crate) const VERIFICATION_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A message whose processing is awaiting confirmation on the destination chain.
+#[derive(Debug)]
+pub(crate) struct PendingVerification {
+    pub msg: SubmitMessageArgs,
+    /// When the message was added to the verification queue.
+    pub since: Instant,
+}
+
+/// The queues the messages of one origin move through on their way to a destination chain
+/// (see the SerialSubmitter docs), together with the handling of them that does not depend on
+/// how process transactions are sent: receiving messages from the processor, promoting them
+/// once their gas payment suffices, retrying and dead-lettering failures, confirming that
+/// processing is final, and recording expenses and metrics. Submitters that send their own
+/// transactions are built on top of these queues.
+#[derive(Debug)]
+pub(crate) struct SubmitterQueues {
+    /// Receiver for new messages to submit.
+    rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
+    /// Messages we are aware of that we want to eventually submit, but haven't yet, for
+    /// whatever reason. They are not in any priority order, so are held in a vector.
+    wait_queue: Vec<SubmitMessageArgs>,
+    /// Messages that are in theory deliverable, but which are waiting in a queue for their turn
+    /// to be dispatched.
+    run_queue: VecDeque<SubmitMessageArgs>,
+    /// Messages that have been processed, but not yet confirmed as final on the destination
+    /// chain.
+    verification_queue: Vec<PendingVerification>,
+    /// Messages that have exhausted their retries, held until re-driven.
+    dead_letters: DeadLetters,
+    /// Commands from operators and the queue snapshot published to them.
+    control: SubmitterControl,
+    /// Number of blocks on the destination chain after which processing is considered final.
+    finality_blocks: u32,
+    /// The number of times processing of a message may revert before it is given up on.
+    max_retries: Option<u32>,
+    /// Inbox / InboxValidatorManager on the destination chain.
+    inbox_contracts: InboxContracts,
+    /// Interface to agent rocks DB for e.g. writing delivery status upon completion.
+    db: AbacusDB,
+    /// Decides whether a message has received enough interchain gas payment to be promoted
+    /// from the wait queue to the run queue.
+    gas_payment_enforcer: GasPaymentEnforcer,
+    /// Chooses the gas limit of processing each message.
+    gas_limits: ProcessGasLimits,
+    /// Picks the next message to submit from the run queue, sharing submission fairly
+    /// between senders within their rate limits.
+    scheduler: FairScheduler,
+    metrics: SubmitterMetrics,
+}
+
+impl SubmitterQueues {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
+        control: SubmitterControl,
+        inbox_contracts: InboxContracts,
+        db: AbacusDB,
+        gas_payment_enforcer: GasPaymentEnforcer,
+        gas_limits: ProcessGasLimits,
+        scheduler: FairScheduler,
+        finality_blocks: u32,
+        max_retries: Option<u32>,
+        metrics: SubmitterMetrics,
+    ) -> Self {
+        Self {
+            rx,
+            wait_queue: Vec::new(),
+            run_queue: VecDeque::new(),
+            verification_queue: Vec::new(),
+            dead_letters: DeadLetters::new(db.clone()),
+            control,
+            finality_blocks,
+            max_retries,
+            inbox_contracts,
+            db,
+            gas_payment_enforcer,
+            gas_limits,
+            scheduler,
+            metrics,
+        }
+    }
+
+    /// Bring the queues up to date: receive new messages from the processor, apply operator
+    /// commands, commit messages whose processing is now final and promote messages whose gas
+    /// payment now suffices.
+    pub async fn update(&mut self) -> Result<()> {
+        self.receive()?;
+
+        // Apply any commands from operators, e.g. to re-drive dead-lettered messages.
+        self.control.apply_commands(
+            &mut self.run_queue,
+            &mut self.wait_queue,
+            &mut self.dead_letters,
+        )?;
+
+        self.verify_processed_messages().await?;
+        self.promote_ready_messages().await;
+        Ok(())
+    }
+
+    /// Pull any messages sent by the processor over the channel onto the wait queue.
+    fn receive(&mut self) -> Result<()> {
+        loop {
+            match self.rx.try_recv() {
+                Ok(mut msg) => {
+                    // Carry over retries and backoff, or dead-lettering, from before a restart.
+                    restore_scheduling_state(&self.db, &mut msg)?;
+                    if let Some(msg) = self.dead_letters.restore(msg)? {
+                        self.wait_queue.push(msg);
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(_) => bail!("Disconnected rcvq or fatal err"),
+            }
+        }
+    }
+
+    /// Commit messages whose processing is now final. Any still-unverified messages that have
+    /// been in the verification queue for longer than VERIFICATION_TIMEOUT move back to the
+    /// wait queue for further processing.
+    async fn verify_processed_messages(&mut self) -> Result<()> {
+        for pending in std::mem::take(&mut self.verification_queue) {
+            let leaf = pending.msg.committed_message.to_leaf();
+            match self
+                .inbox_contracts
+                .inbox
+                .finalized_message_status(leaf, self.finality_blocks)
+                .await
+            {
+                Ok(MessageStatus::Processed) => {
+                    self.record_message_process_success(&pending.msg)?;
+                    info!(
+                        leaf_index = pending.msg.leaf_index,
+                        "Message processing is final"
+                    );
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(leaf_index = pending.msg.leaf_index, error = ?e, "Unable to verify message processing");
+                }
+            }
+            if pending.since.elapsed() > VERIFICATION_TIMEOUT {
+                warn!(
+                    leaf_index = pending.msg.leaf_index,
+                    "Message processing was not confirmed in time, returning it to the wait queue"
+                );
+                self.wait_queue.push(pending.msg);
+            } else {
+                self.verification_queue.push(pending);
+            }
+        }
+        Ok(())
+    }
+
+    /// Promote any newly-ready messages from the wait queue to the run queue.
+    /// The order of wait_messages, which includes messages asc ordered by leaf index,
+    /// is preserved and pushed at the front of the run_queue to ensure that new messages
+    /// are evaluated first. Messages without sufficient interchain gas payment stay in
+    /// the wait queue and are checked again next update, as new payments may have been
+    /// indexed by then.
+    async fn promote_ready_messages(&mut self) {
+        let mut newly_ready = Vec::new();
+        for mut msg in std::mem::take(&mut self.wait_queue) {
+            match self.gas_payment_enforcer.assess_payment(&msg).await {
+                Ok(assessment) if assessment.meets_requirement => {
+                    msg.priority = assessment.priority;
+                    newly_ready.push(msg);
+                }
+                Ok(_) => {
+                    debug!(
+                        leaf_index = msg.leaf_index,
+                        "Insufficient gas payment, keeping message in wait queue"
+                    );
+                    self.wait_queue.push(msg);
+                }
+                Err(e) => {
+                    warn!(
+                        leaf_index = msg.leaf_index,
+                        error = ?e,
+                        "Unable to check gas payment, keeping message in wait queue"
+                    );
+                    self.wait_queue.push(msg);
+                }
+            }
+        }
+        for msg in newly_ready.into_iter().rev() {
+            self.run_queue.push_front(msg);
+        }
+    }
+
+    /// Publish the state of the queues to metrics and the admin API.
+    pub fn publish(&self) {
+        self.metrics
+            .wait_queue_length_gauge
+            .set(self.wait_queue.len() as i64);
+        self.metrics
+            .run_queue_length_gauge
+            .set(self.run_queue.len() as i64);
+        self.metrics
+            .verification_queue_length_gauge
+            .set(self.verification_queue.len() as i64);
+        self.metrics
+            .sender_queue_metrics
+            .update("run_queue", &self.run_queue);
+        self.metrics
+            .sender_queue_metrics
+            .update("wait_queue", &self.wait_queue);
+        self.control.publish(&self.run_queue, &self.wait_queue);
+    }
+
+    /// Whether an operator has paused submission.
+    pub fn paused(&self) -> bool {
+        self.control.paused()
+    }
+
+    /// Pick the next message to try processing, sharing submission fairly between senders
+    /// and skipping messages that are backing off after a failure or are rate limited.
+    ///
+    /// If the message has already been processed according to the inbox, e.g. by another
+    /// relayer, it moves to the verification queue to wait for that processing to become
+    /// final, and the next message is picked instead. None is returned if no message is
+    /// runnable, or if the status of the picked message could not be checked, in which case
+    /// it is retried as a failure.
+    pub async fn next_runnable(&mut self) -> Option<SubmitMessageArgs> {
+        loop {
+            let msg = self.scheduler.next(&mut self.run_queue, Instant::now())?;
+            match self
+                .inbox_contracts
+                .inbox
+                .message_status(msg.committed_message.to_leaf())
+                .await
+            {
+                Ok(MessageStatus::Processed) => {
+                    info!(
+                        leaf_index = msg.leaf_index,
+                        "Message already processed, awaiting finality"
+                    );
+                    self.push_to_verification_queue(msg);
+                }
+                Ok(_) => return Some(msg),
+                Err(e) => {
+                    self.handle_process_failure(msg, e);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Return a message that was taken for processing but not attempted, e.g. because the
+    /// nonce of its transaction was taken, to the front of the run queue.
+    pub fn requeue(&mut self, msg: SubmitMessageArgs) {
+        self.run_queue.push_front(msg);
+    }
+
+    /// The gas limit of processing a message, given its estimated gas limit.
+    pub fn gas_limit(
+        &self,
+        msg: &SubmitMessageArgs,
+        estimate: U256,
+    ) -> Result<U256, ChainCommunicationError> {
+        self.gas_limits.gas_limit(&self.db, msg, estimate)
+    }
+
+    /// Requeue a message after a failed processing attempt according to the retry policy for
+    /// the kind of error, or dead-letter it if the policy's retries are exhausted (see
+    /// `retry_or_dead_letter`).
+    pub fn handle_process_failure(&mut self, msg: SubmitMessageArgs, e: ChainCommunicationError) {
+        if let Some((msg, position)) =
+            retry_or_dead_letter(&self.db, &mut self.dead_letters, self.max_retries, msg, &e)
+        {
+            match position {
+                RequeuePosition::Front => self.run_queue.push_front(msg),
+                RequeuePosition::Back => self.run_queue.push_back(msg),
+            }
+        }
+    }
+
+    /// Move a message whose processing has been observed on the destination chain to the
+    /// verification queue, where it waits until that processing is final.
+    pub fn push_to_verification_queue(&mut self, msg: SubmitMessageArgs) {
+        self.verification_queue.push(PendingVerification {
+            msg,
+            since: Instant::now(),
+        });
+    }
+
+    /// Record the gas spent by a `process` transaction for a message in AbacusDB and
+    /// metrics, whether or not it reverted.
+    pub fn record_relay_expense(&self, msg: &SubmitMessageArgs, outcome: &TxOutcome) {
+        let cost = outcome.cost().to_f64_lossy();
+        self.metrics
+            .relay_gas_used_count
+            .inc_by(outcome.gas_used.low_u64());
+        self.metrics.relay_cost_count.inc_by(cost);
+        if let Some((net_margin_gauge, exchange_rate)) = &self.metrics.net_margin {
+            net_margin_gauge.sub(cost * exchange_rate);
+        }
+
+        let destination = msg.committed_message.message.destination;
+        match self
+            .db
+            .record_relay_expense(msg.leaf_index, destination, outcome)
+        {
+            Ok(expense) if outcome.executed => {
+                self.metrics
+                    .delivery_cost_hist
+                    .observe(expense.cost.to_f64_lossy());
+                if let Some((net_margin_gauge, _)) = &self.metrics.net_margin {
+                    net_margin_gauge.add(expense.gas_payment.to_f64_lossy());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!(leaf_index = msg.leaf_index, error = ?e, "Failed to record relay expense")
+            }
+        }
+    }
+
+    /// Learn the gas used by a transaction that processed a single message.
+    pub fn record_gas_used(&self, msg: &SubmitMessageArgs, outcome: &TxOutcome) {
+        record_gas_used(&self.db, msg, outcome);
+    }
+
+    /// Count an error processing a message if it is a revert, by its reason.
+    pub fn record_revert(&self, e: &ChainCommunicationError) {
+        self.metrics.record_revert(e);
+    }
+
+    /// Record in AbacusDB and various metrics that this process has observed the successful
+    /// processing of a message. An Ok(()) value returned by this function is the 'commit' point
+    /// in a message's lifetime for final processing -- after this function has been seen to
+    /// return 'Ok(())', then without a wiped AbacusDB, we will never re-attempt processing for
+    /// this message again, even after the relayer restarts.
+    fn record_message_process_success(&mut self, msg: &SubmitMessageArgs) -> Result<()> {
+        self.db.mark_leaf_as_processed(msg.leaf_index)?;
+        self.dead_letters.remove(msg.leaf_index)?;
+        self.db.remove_scheduling_state(msg.leaf_index)?;
+        self.metrics
+            .queue_duration_hist
+            .observe((Instant::now() - msg.enqueue_time).as_secs_f64());
+        self.metrics.max_submitted_leaf_index =
+            std::cmp::max(self.metrics.max_submitted_leaf_index, msg.leaf_index);
+        self.metrics
+            .processed_gauge
+            .set(self.metrics.max_submitted_leaf_index as i64);
+        self.metrics.messages_processed_count.inc();
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SubmitterMetrics {
+    run_queue_length_gauge: IntGauge,
+    wait_queue_length_gauge: IntGauge,
+    verification_queue_length_gauge: IntGauge,
+    queue_duration_hist: Histogram,
+    processed_gauge: IntGauge,
+    messages_processed_count: IntCounter,
+    relay_gas_used_count: IntCounter,
+    relay_cost_count: Counter,
+    delivery_cost_hist: Histogram,
+    /// The net margin gauge and the exchange rate costs are converted to the origin's native
+    /// token at, if the route has one.
+    net_margin: Option<(Gauge, f64)>,
+    sender_queue_metrics: SenderQueueMetrics,
+    /// Labelled by reason when recording a revert.
+    message_reverts_count: IntCounterVec,
+    outbox_chain: String,
+    inbox_chain: String,
+
+    /// Private state used to update actual metrics each tick.
+    max_submitted_leaf_index: u32,
+}
+
+impl SubmitterMetrics {
+    pub fn new(
+        metrics: &CoreMetrics,
+        outbox_chain: &str,
+        inbox_chain: &str,
+        exchange_rate: Option<f64>,
+    ) -> Self {
+        Self {
+            run_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "run_queue",
+            ]),
+            wait_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "wait_queue",
+            ]),
+            verification_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
+                outbox_chain,
+                inbox_chain,
+                "verification_queue",
+            ]),
+            queue_duration_hist: metrics
+                .submitter_queue_duration_histogram()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            messages_processed_count: metrics
+                .messages_processed_count()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            processed_gauge: metrics.last_known_message_leaf_index().with_label_values(&[
+                "message_processed",
+                outbox_chain,
+                inbox_chain,
+            ]),
+            relay_gas_used_count: metrics
+                .relay_gas_used()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            relay_cost_count: metrics
+                .relay_cost()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            delivery_cost_hist: metrics
+                .relay_delivery_cost_histogram()
+                .with_label_values(&[outbox_chain, inbox_chain]),
+            net_margin: exchange_rate.map(|rate| {
+                let gauge = metrics
+                    .relay_net_margin()
+                    .with_label_values(&[outbox_chain, inbox_chain]);
+                (gauge, rate)
+            }),
+            sender_queue_metrics: SenderQueueMetrics::new(metrics, outbox_chain, inbox_chain),
+            message_reverts_count: metrics.message_reverts_count(),
+            outbox_chain: outbox_chain.to_owned(),
+            inbox_chain: inbox_chain.to_owned(),
+            max_submitted_leaf_index: 0,
+        }
+    }
+
+    /// Count an error processing a message if it is a revert, by the group of its reason (see
+    /// `revert_reason_label`).
+    fn record_revert(&self, e: &ChainCommunicationError) {
+        let (reason, permanent) = match e {
+            ChainCommunicationError::Reverted(reason) => (reason, "false"),
+            ChainCommunicationError::PermanentRevert(reason) => (reason, "true"),
+            _ => return,
+        };
+        self.message_reverts_count
+            .with_label_values(&[
+                &self.outbox_chain,
+                &self.inbox_chain,
+                revert_reason_label(reason),
+                permanent,
+            ])
+            .inc();
+    }
+}
+
+/// Record a failed processing attempt of a message and apply the retry policy for the kind of
+/// error. Returns the message, due to be retried after a backoff, with where to requeue it:
+/// reverts and fatal errors go to the back of the queue so that other messages are not held
+/// up, while transient errors are retried from the front. If the policy's retries are
+/// exhausted, the message is dead-lettered instead and None is returned.
+pub(crate) fn retry_or_dead_letter(
+    db: &AbacusDB,
+    dead_letters: &mut DeadLetters,
+    max_retries: Option<u32>,
+    mut msg: SubmitMessageArgs,
+    e: &ChainCommunicationError,
+) -> Option<(SubmitMessageArgs, RequeuePosition)> {
+    let kind = SubmitErrorKind::from(e);
+    let policy = RetryPolicy::for_error(kind, max_retries);
+    msg.num_retries += 1;
+    if kind == SubmitErrorKind::Revert {
+        msg.num_reverts += 1;
+    }
+    msg.first_failure_at.get_or_insert_with(unix_timestamp);
+    // Each kind of error is retried and backed off according to its own count, so that
+    // e.g. transient errors do not use up the retries of reverts.
+    let failures = match kind {
+        SubmitErrorKind::Transient => msg.num_retries - msg.num_reverts,
+        SubmitErrorKind::Revert => msg.num_reverts,
+        SubmitErrorKind::Fatal => msg.num_retries,
+    };
+    if policy.exhausted(failures) {
+        error!(msg=?msg, leaf_index=msg.leaf_index, error=?e, ?kind,
+            "Message processing failed, moving message to dead letters");
+        record_failure(db, &msg, e);
+        dead_letters.insert(msg, e);
+        return None;
+    }
+    let backoff = policy.backoff(failures);
+    info!(msg=?msg, leaf_index=msg.leaf_index, error=?e, ?kind, ?backoff,
+        "Message processing failed");
+    msg.next_attempt_after = Some(Instant::now() + backoff);
+    record_failure(db, &msg, e);
+    Some((msg, policy.requeue_position))
+}
+
+/// The label a revert reason is counted under. Reasons are chosen by recipients, so to bound
+/// the number of series they are grouped: the Inbox's permanent reasons are kept, other decoded
+/// reasons are attributed to the recipient, and undecoded ones are unknown. The full reason is
+/// only logged and stored as the message's last error.
+fn revert_reason_label(reason: &str) -> &'static str {
+    if let Some(label) = PERMANENT_PROCESS_REVERTS.iter().find(|r| **r == reason) {
+        *label
+    } else if reason.is_empty() || reason.starts_with(UNDECODED_REVERT_REASON) {
+        "unknown"
+    } else {
+        "recipient"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::revert_reason_label;
+
+    #[test]
+    fn groups_revert_reasons() {
+        assert_eq!(revert_reason_label("!proof"), "!proof");
+        assert_eq!(
+            revert_reason_label("Ownable: caller is not the owner"),
+            "recipient"
+        );
+        assert_eq!(
+            revert_reason_label("undecoded revert data 0x1234"),
+            "unknown"
+        );
+        assert_eq!(revert_reason_label(""), "unknown");
+    }
+}
@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use abacus_core::ChainCommunicationError;
+
+/// The number of transient failures after which a message is given up on. At
+/// the max backoff, this is more than an hour and a half of failures.
+const MAX_TRANSIENT_RETRIES: u32 = 100;
+
+/// The category of a failed attempt to submit a message, which determines how
+/// the message is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SubmitErrorKind {
+    /// The failure was likely caused by the environment rather than the
+    /// message, e.g. an RPC error, a timeout or a transaction dropped from the
+    /// mempool. Retrying soon is likely to succeed, but as errors that are not
+    /// reverts can't always be told apart, the message is dead-lettered if it
+    /// keeps failing.
+    Transient,
+    /// Processing the message reverted. It may succeed later, e.g. once the
+    /// recipient's state changes, but is unlikely to succeed soon.
+    Revert,
+    /// Retrying will not succeed, or the relayer's policy forbids submitting
+    /// the message, e.g. because it needs more than the max gas limit. The
+    /// message is dead-lettered, so it can be re-driven once the policy
+    /// changes.
+    Fatal,
+}
+
+impl From<&ChainCommunicationError> for SubmitErrorKind {
+    fn from(e: &ChainCommunicationError) -> Self {
+        match e {
+            ChainCommunicationError::ProviderError(_)
+            | ChainCommunicationError::DroppedError(_)
+            | ChainCommunicationError::TransactionTimeout()
+            | ChainCommunicationError::ContractError(_)
+            | ChainCommunicationError::CustomError(_) => SubmitErrorKind::Transient,
+            ChainCommunicationError::Reverted(_) => SubmitErrorKind::Revert,
+            ChainCommunicationError::AbacusError(_)
+            | ChainCommunicationError::DbError(_)
+            | ChainCommunicationError::PermanentRevert(_)
+            | ChainCommunicationError::GasLimitExceeded(_, _) => SubmitErrorKind::Fatal,
+        }
+    }
+}
+
+/// Where a message is put back in the run queue after a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequeuePosition {
+    Front,
+    Back,
+}
+
+/// How messages are retried after a failure of a particular kind.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RetryPolicy {
+    pub requeue_position: RequeuePosition,
+    /// The delay before the first retry, doubled with each further retry.
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// The number of retries after which the message is given up on, if any.
+    pub max_retries: Option<u32>,
+}
+
+impl RetryPolicy {
+    /// The policy for a kind of error. `max_processing_retries` applies to
+    /// reverts, transient errors are retried up to MAX_TRANSIENT_RETRIES times,
+    /// and messages that fail fatally are never retried.
+    pub fn for_error(kind: SubmitErrorKind, max_processing_retries: Option<u32>) -> Self {
+        match kind {
+            SubmitErrorKind::Transient => Self {
+                requeue_position: RequeuePosition::Front,
+                base_backoff: Duration::from_secs(1),
+                max_backoff: Duration::from_secs(60),
+                max_retries: Some(MAX_TRANSIENT_RETRIES),
+            },
+            SubmitErrorKind::Revert => Self {
+                requeue_position: RequeuePosition::Back,
+                base_backoff: Duration::from_secs(10),
+                max_backoff: Duration::from_secs(60 * 60),
+                max_retries: max_processing_retries,
+            },
+            SubmitErrorKind::Fatal => Self {
+                requeue_position: RequeuePosition::Back,
+                base_backoff: Duration::ZERO,
+                max_backoff: Duration::ZERO,
+                max_retries: Some(0),
+            },
+        }
+    }
+
+    /// Whether a message that has failed `failures` times with errors of this
+    /// policy's kind should be given up on.
+    pub fn exhausted(&self, failures: u32) -> bool {
+        self.max_retries.map_or(false, |max| failures > max)
+    }
+
+    /// The delay before retrying after failure number `failures` of this
+    /// policy's kind, starting from 1.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(31);
+        self.base_backoff
+            .saturating_mul(1u32 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use abacus_core::ChainCommunicationError;
+    use ethers::types::H256;
+
+    use super::{RetryPolicy, SubmitErrorKind, MAX_TRANSIENT_RETRIES};
+
+    #[test]
+    fn classifies_errors() {
+        assert_eq!(
+            SubmitErrorKind::from(&ChainCommunicationError::DroppedError(H256::zero())),
+            SubmitErrorKind::Transient
+        );
+        assert_eq!(
+            SubmitErrorKind::from(&ChainCommunicationError::Reverted("!pending".into())),
+            SubmitErrorKind::Revert
+        );
+        assert_eq!(
+            SubmitErrorKind::from(&ChainCommunicationError::PermanentRevert("!proof".into())),
+            SubmitErrorKind::Fatal
+        );
+        assert_eq!(
+            SubmitErrorKind::from(&ChainCommunicationError::GasLimitExceeded(
+                600_000u64.into(),
+                500_000u64.into()
+            )),
+            SubmitErrorKind::Fatal
+        );
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        let policy = RetryPolicy::for_error(SubmitErrorKind::Revert, Some(3));
+        assert_eq!(policy.backoff(1), Duration::from_secs(10));
+        assert_eq!(policy.backoff(3), Duration::from_secs(40));
+        assert_eq!(policy.backoff(100), Duration::from_secs(60 * 60));
+        assert!(!policy.exhausted(3));
+        assert!(policy.exhausted(4));
+    }
+
+    #[test]
+    fn caps_transient_retries() {
+        let policy = RetryPolicy::for_error(SubmitErrorKind::Transient, None);
+        assert!(!policy.exhausted(MAX_TRANSIENT_RETRIES));
+        assert!(policy.exhausted(MAX_TRANSIENT_RETRIES + 1));
+        assert_eq!(
+            policy.backoff(MAX_TRANSIENT_RETRIES),
+            Duration::from_secs(60)
+        );
+    }
+}
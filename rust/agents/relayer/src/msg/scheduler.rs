@@ -0,0 +1,459 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Debug;
+
+use abacus_base::CoreMetrics;
+use ethers::types::{H160, H256};
+use eyre::{bail, eyre, Result};
+use prometheus::IntGaugeVec;
+use tokio::time::Instant;
+
+use crate::settings::{RateLimit, SchedulingConf};
+
+use super::SubmitMessageArgs;
+
+/// A parsed `RateLimit`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BucketConf {
+    /// The most tokens a bucket holds.
+    capacity: f64,
+    /// Tokens added to a bucket per second.
+    refill_per_sec: f64,
+}
+
+impl TryFrom<&RateLimit> for BucketConf {
+    type Error = eyre::Report;
+
+    fn try_from(limit: &RateLimit) -> Result<Self> {
+        let per_minute: f64 = limit
+            .per_minute
+            .parse()
+            .map_err(|e| eyre!("Invalid rate limit perMinute {}: {e}", limit.per_minute))?;
+        if !per_minute.is_finite() || per_minute <= 0. {
+            bail!("Rate limit perMinute must be positive");
+        }
+        let capacity = match &limit.burst {
+            Some(burst) => burst
+                .parse::<u32>()
+                .map_err(|e| eyre!("Invalid rate limit burst {burst}: {e}"))?
+                as f64,
+            None => per_minute.floor().max(1.),
+        };
+        if capacity < 1. {
+            bail!("Rate limit burst must be at least 1");
+        }
+        Ok(Self {
+            capacity,
+            refill_per_sec: per_minute / 60.,
+        })
+    }
+}
+
+/// The tokens left in a bucket as of `updated`.
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket rate limits keyed by address. A message may be submitted if the
+/// bucket of its address holds a token, and takes that token when it is.
+#[derive(Debug)]
+struct RateLimiter {
+    conf: BucketConf,
+    /// Buckets that are not full. Any other bucket is full.
+    buckets: HashMap<H256, TokenBucket>,
+}
+
+impl RateLimiter {
+    fn new(conf: BucketConf) -> Self {
+        Self {
+            conf,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// The tokens in the bucket of an address at `now`.
+    fn tokens(&self, address: &H256, now: Instant) -> f64 {
+        match self.buckets.get(address) {
+            Some(bucket) => {
+                let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+                (bucket.tokens + elapsed * self.conf.refill_per_sec).min(self.conf.capacity)
+            }
+            None => self.conf.capacity,
+        }
+    }
+
+    fn allows(&self, address: &H256, now: Instant) -> bool {
+        self.tokens(address, now) >= 1.
+    }
+
+    fn take(&mut self, address: H256, now: Instant) {
+        let tokens = self.tokens(&address, now) - 1.;
+        self.buckets.insert(
+            address,
+            TokenBucket {
+                tokens,
+                updated: now,
+            },
+        );
+        // Forget buckets that have refilled, so that the map only grows with
+        // the number of recently active addresses.
+        let conf = self.conf;
+        self.buckets.retain(|_, b| {
+            b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * conf.refill_per_sec
+                < conf.capacity
+        });
+    }
+}
+
+/// Configuration of a FairScheduler.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FairSchedulerConf {
+    sender_limit: Option<BucketConf>,
+    recipient_limit: Option<BucketConf>,
+    sender_weights: HashMap<H256, f64>,
+}
+
+impl TryFrom<&SchedulingConf> for FairSchedulerConf {
+    type Error = eyre::Report;
+
+    fn try_from(conf: &SchedulingConf) -> Result<Self> {
+        let sender_weights = conf
+            .sender_weights
+            .iter()
+            .map(|(sender, weight)| {
+                let weight: f64 = weight
+                    .parse()
+                    .map_err(|e| eyre!("Invalid weight {weight} of sender {sender}: {e}"))?;
+                if !weight.is_finite() || weight <= 0. {
+                    bail!("Weight of sender {sender} must be positive");
+                }
+                Ok((parse_address(sender)?, weight))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            sender_limit: conf
+                .sender_limit
+                .as_ref()
+                .map(TryInto::try_into)
+                .transpose()?,
+            recipient_limit: conf
+                .recipient_limit
+                .as_ref()
+                .map(TryInto::try_into)
+                .transpose()?,
+            sender_weights,
+        })
+    }
+}
+
+/// Orders the messages in a submitter's run queue. Of the messages that may be
+/// submitted, only those ordered first are candidates to be picked next.
+/// Candidates that compare equal are picked by sharing submission fairly
+/// between their senders.
+pub(crate) trait RunQueueOrder: Debug + Send + Sync {
+    /// Compare two messages, where `Ordering::Less` means that `a` should be
+    /// submitted before `b`.
+    fn cmp(&self, a: &SubmitMessageArgs, b: &SubmitMessageArgs) -> Ordering;
+}
+
+/// Orders messages in higher priority tiers, whose gas payments covered a
+/// higher premium, before the others.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ByPriorityTier;
+
+impl RunQueueOrder for ByPriorityTier {
+    fn cmp(&self, a: &SubmitMessageArgs, b: &SubmitMessageArgs) -> Ordering {
+        b.priority.cmp(&a.priority)
+    }
+}
+
+/// Picks the next message to submit from a submitter's run queue.
+///
+/// The candidates to be picked are the messages ordered first by the
+/// scheduler's `RunQueueOrder`, e.g. those in the highest priority tier.
+/// Submission is shared between senders by weighted fair queuing, so that one
+/// application sending many messages cannot starve the others: each sender's
+/// messages are dispatched in run queue order, and the sender picked next is
+/// the one with the least virtual time, which advances by `1 / weight` with
+/// each message dispatched for it. A sender that has been idle starts at the
+/// virtual time of the last dispatched message, so it cannot save up a burst.
+///
+/// Messages whose sender or recipient has exhausted its rate limit, and
+/// messages backing off after a failure, are passed over.
+#[derive(Debug)]
+pub(crate) struct FairScheduler {
+    order: Box<dyn RunQueueOrder>,
+    sender_limiter: Option<RateLimiter>,
+    recipient_limiter: Option<RateLimiter>,
+    sender_weights: HashMap<H256, f64>,
+    /// The virtual time of each sender's next message, if it is ahead of
+    /// `virtual_time`.
+    sender_times: HashMap<H256, f64>,
+    /// The virtual time of the last dispatched message.
+    virtual_time: f64,
+}
+
+impl FairScheduler {
+    pub fn new(conf: FairSchedulerConf, order: Box<dyn RunQueueOrder>) -> Self {
+        Self {
+            order,
+            sender_limiter: conf.sender_limit.map(RateLimiter::new),
+            recipient_limiter: conf.recipient_limit.map(RateLimiter::new),
+            sender_weights: conf.sender_weights,
+            sender_times: HashMap::new(),
+            virtual_time: 0.,
+        }
+    }
+
+    /// Remove the next message to submit from the run queue, if any message in
+    /// it may be submitted at `now`.
+    pub fn next(
+        &mut self,
+        run_queue: &mut VecDeque<SubmitMessageArgs>,
+        now: Instant,
+    ) -> Option<SubmitMessageArgs> {
+        let submittable: Vec<(usize, &SubmitMessageArgs)> = run_queue
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| {
+                let message = &msg.committed_message.message;
+                msg.next_attempt_after.map_or(true, |t| t <= now)
+                    && self.allows(&message.sender, &message.recipient, now)
+            })
+            .collect();
+        let first = submittable
+            .iter()
+            .map(|(_, msg)| *msg)
+            .min_by(|a, b| self.order.cmp(a, b))?;
+
+        let mut considered = HashSet::new();
+        // The queue index and virtual time of the best candidate so far.
+        let mut best: Option<(usize, f64)> = None;
+        for &(idx, msg) in &submittable {
+            let message = &msg.committed_message.message;
+            if considered.contains(&message.sender) || self.order.cmp(msg, first) != Ordering::Equal
+            {
+                continue;
+            }
+            considered.insert(message.sender);
+            let time = self.sender_time(&message.sender);
+            if best.map_or(true, |(_, best_time)| time < best_time) {
+                best = Some((idx, time));
+            }
+        }
+
+        let (idx, time) = best?;
+        let msg = run_queue.remove(idx)?;
+        let message = &msg.committed_message.message;
+        if let Some(limiter) = &mut self.sender_limiter {
+            limiter.take(message.sender, now);
+        }
+        if let Some(limiter) = &mut self.recipient_limiter {
+            limiter.take(message.recipient, now);
+        }
+        let weight = self.sender_weights.get(&message.sender).unwrap_or(&1.);
+        self.virtual_time = time;
+        self.sender_times.insert(message.sender, time + 1. / weight);
+        let virtual_time = self.virtual_time;
+        self.sender_times.retain(|_, t| *t > virtual_time);
+        Some(msg)
+    }
+
+    fn allows(&self, sender: &H256, recipient: &H256, now: Instant) -> bool {
+        self.sender_limiter
+            .as_ref()
+            .map_or(true, |l| l.allows(sender, now))
+            && self
+                .recipient_limiter
+                .as_ref()
+                .map_or(true, |l| l.allows(recipient, now))
+    }
+
+    fn sender_time(&self, sender: &H256) -> f64 {
+        self.sender_times
+            .get(sender)
+            .map_or(self.virtual_time, |t| t.max(self.virtual_time))
+    }
+}
+
+/// Reports the number of messages from each sender in a submitter's queues.
+#[derive(Debug)]
+pub(crate) struct SenderQueueMetrics {
+    gauge: IntGaugeVec,
+    origin: String,
+    remote: String,
+    /// The senders reported per queue at the last update.
+    reported: HashMap<&'static str, HashSet<H256>>,
+}
+
+impl SenderQueueMetrics {
+    pub fn new(metrics: &CoreMetrics, outbox_chain: &str, inbox_chain: &str) -> Self {
+        Self {
+            gauge: metrics.submitter_sender_queue_length(),
+            origin: outbox_chain.into(),
+            remote: inbox_chain.into(),
+            reported: HashMap::new(),
+        }
+    }
+
+    /// Set the queue length of each sender with messages in the queue, and
+    /// remove the reported lengths of senders without any.
+    pub fn update<'a>(
+        &mut self,
+        queue_name: &'static str,
+        queue: impl IntoIterator<Item = &'a SubmitMessageArgs>,
+    ) {
+        let mut lengths: HashMap<H256, i64> = HashMap::new();
+        for msg in queue {
+            *lengths
+                .entry(msg.committed_message.message.sender)
+                .or_default() += 1;
+        }
+        let reported = self.reported.entry(queue_name).or_default();
+        for sender in reported.iter().filter(|s| !lengths.contains_key(s)) {
+            let _ = self.gauge.remove_label_values(&[
+                &self.origin,
+                &self.remote,
+                queue_name,
+                &format!("{sender:?}"),
+            ]);
+        }
+        for (sender, length) in &lengths {
+            self.gauge
+                .with_label_values(&[
+                    &self.origin,
+                    &self.remote,
+                    queue_name,
+                    &format!("{sender:?}"),
+                ])
+                .set(*length);
+        }
+        *reported = lengths.into_keys().collect();
+    }
+}
+
+fn parse_address(address: &str) -> Result<H256> {
+    if address.len() <= 42 {
+        address.parse::<H160>().map(H256::from)
+    } else {
+        address.parse::<H256>()
+    }
+    .map_err(|e| eyre!("Invalid address {address}: {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, VecDeque};
+
+    use abacus_core::{
+        accumulator::merkle::Proof, AbacusMessage, Checkpoint, CommittedMessage,
+        MultisigSignedCheckpoint,
+    };
+    use ethers::types::H256;
+    use tokio::time::{Duration, Instant};
+
+    use super::{BucketConf, ByPriorityTier, FairScheduler, FairSchedulerConf};
+    use crate::msg::SubmitMessageArgs;
+
+    fn msg(leaf_index: u32, sender: u64, recipient: u64) -> SubmitMessageArgs {
+        SubmitMessageArgs::new(
+            leaf_index,
+            CommittedMessage {
+                leaf_index,
+                message: AbacusMessage {
+                    origin: 1,
+                    sender: H256::from_low_u64_be(sender),
+                    destination: 2,
+                    recipient: H256::from_low_u64_be(recipient),
+                    body: vec![],
+                },
+            },
+            MultisigSignedCheckpoint {
+                checkpoint: Checkpoint {
+                    outbox_domain: 1,
+                    root: H256::zero(),
+                    index: leaf_index,
+                },
+                signatures: vec![],
+            },
+            Proof {
+                leaf: H256::zero(),
+                index: leaf_index as usize,
+                path: Default::default(),
+            },
+            Instant::now(),
+        )
+    }
+
+    fn drain(
+        scheduler: &mut FairScheduler,
+        queue: &mut VecDeque<SubmitMessageArgs>,
+        now: Instant,
+    ) -> Vec<u32> {
+        std::iter::from_fn(|| scheduler.next(queue, now))
+            .map(|m| m.leaf_index)
+            .collect()
+    }
+
+    #[test]
+    fn shares_submission_between_senders_by_weight() {
+        let mut conf = FairSchedulerConf::default();
+        conf.sender_weights.insert(H256::from_low_u64_be(2), 2.);
+        let mut scheduler = FairScheduler::new(conf, Box::new(ByPriorityTier));
+        // Sender 1 has flooded the queue ahead of senders 2 and 3.
+        let mut queue: VecDeque<_> = (0..6)
+            .map(|i| msg(i, 1, 9))
+            .chain((6..10).map(|i| msg(i, 2, 9)))
+            .chain([msg(10, 3, 9)])
+            .collect();
+        assert_eq!(
+            drain(&mut scheduler, &mut queue, Instant::now()),
+            vec![0, 6, 10, 7, 1, 8, 9, 2, 3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn limits_senders_and_recipients() {
+        let limit = BucketConf {
+            capacity: 2.,
+            refill_per_sec: 1.,
+        };
+        let mut scheduler = FairScheduler::new(
+            FairSchedulerConf {
+                sender_limit: Some(limit),
+                recipient_limit: Some(limit),
+                sender_weights: HashMap::new(),
+            },
+            Box::new(ByPriorityTier),
+        );
+        let mut queue: VecDeque<_> = (0..3)
+            .map(|i| msg(i, 1, 8))
+            .chain((3..6).map(|i| msg(i, 2, 9)))
+            .chain([msg(6, 3, 9)])
+            .collect();
+        let now = Instant::now();
+        // Senders 2 and 3 share the two tokens of recipient 9, and sender 1
+        // uses up its own two.
+        assert_eq!(drain(&mut scheduler, &mut queue, now), vec![0, 3, 6, 1]);
+        // One token is refilled per second.
+        let now = now + Duration::from_secs(1);
+        assert_eq!(drain(&mut scheduler, &mut queue, now), vec![4, 2]);
+        let now = now + Duration::from_secs(1);
+        assert_eq!(drain(&mut scheduler, &mut queue, now), vec![5]);
+    }
+
+    #[test]
+    fn picks_higher_priority_tiers_first() {
+        let mut scheduler =
+            FairScheduler::new(FairSchedulerConf::default(), Box::new(ByPriorityTier));
+        let mut queue: VecDeque<_> = (0..4).map(|i| msg(i, i as u64 % 2, 9)).collect();
+        queue[2].priority = 1;
+        queue[3].priority = 2;
+        assert_eq!(
+            drain(&mut scheduler, &mut queue, Instant::now()),
+            vec![3, 2, 0, 1]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use abacus_core::db::AbacusDB;
+use abacus_core::{ChainCommunicationError, MessageSchedulingState};
+use eyre::Result;
+use tokio::time::{Duration, Instant};
+use tracing::{error, info};
+
+use super::{dead_letters::unix_timestamp, SubmitMessageArgs};
+
+/// Restore the scheduling state persisted for a message received by a submitter, e.g. before
+/// a restart, so that its retry count and backoff carry over. If the message has no persisted
+/// state, its fresh state is persisted instead.
+pub(crate) fn restore_scheduling_state(db: &AbacusDB, msg: &mut SubmitMessageArgs) -> Result<()> {
+    let state = match db.retrieve_scheduling_state(msg.leaf_index)? {
+        Some(state) => state,
+        None => return store_scheduling_state(db, msg),
+    };
+    let now = unix_timestamp();
+    let since = |timestamp: u64| Duration::from_secs(now.saturating_sub(timestamp));
+    if let Some(enqueue_time) = Instant::now().checked_sub(since(state.first_seen_at)) {
+        msg.enqueue_time = enqueue_time;
+    }
+    msg.num_retries = state.num_retries;
+    msg.num_reverts = state.num_reverts;
+    msg.first_failure_at = state.first_failure_at;
+    msg.next_attempt_after = state
+        .next_attempt_at
+        .map(|at| Instant::now() + Duration::from_secs(at.saturating_sub(now)));
+    if state.num_retries > 0 {
+        info!(
+            leaf_index = msg.leaf_index,
+            num_retries = state.num_retries,
+            num_reverts = state.num_reverts,
+            last_error = ?state.last_error,
+            "Restored scheduling state of failing message"
+        );
+    }
+    Ok(())
+}
+
+/// Persist the scheduling state of a message, e.g. after its retries are reset.
+pub(crate) fn store_scheduling_state(db: &AbacusDB, msg: &SubmitMessageArgs) -> Result<()> {
+    Ok(db.store_scheduling_state(&scheduling_state(msg, None))?)
+}
+
+/// Persist the scheduling state of a message after a failed processing attempt.
+pub(crate) fn record_failure(db: &AbacusDB, msg: &SubmitMessageArgs, e: &ChainCommunicationError) {
+    if let Err(e) = db.store_scheduling_state(&scheduling_state(msg, Some(e))) {
+        error!(leaf_index = msg.leaf_index, error = ?e, "Failed to store scheduling state");
+    }
+}
+
+fn scheduling_state(
+    msg: &SubmitMessageArgs,
+    last_error: Option<&ChainCommunicationError>,
+) -> MessageSchedulingState {
+    let now = unix_timestamp();
+    MessageSchedulingState {
+        leaf_index: msg.leaf_index,
+        first_seen_at: now.saturating_sub(msg.enqueue_time.elapsed().as_secs()),
+        num_retries: msg.num_retries,
+        num_reverts: msg.num_reverts,
+        last_attempt_at: last_error.map(|_| now),
+        last_error: last_error.map(ToString::to_string),
+        first_failure_at: msg.first_failure_at,
+        next_attempt_at: msg
+            .next_attempt_after
+            .map(|t| now + t.saturating_duration_since(Instant::now()).as_secs()),
+    }
+}
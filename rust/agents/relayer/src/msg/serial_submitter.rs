@@ -1,23 +1,26 @@
-use std::collections::VecDeque;
+use std::sync::Arc;
 
-use abacus_base::CoreMetrics;
 use abacus_base::InboxContracts;
-use abacus_core::db::AbacusDB;
 use abacus_core::AbacusContract;
+use abacus_core::ChainCommunicationError;
 use abacus_core::Inbox;
 use abacus_core::InboxValidatorManager;
 use abacus_core::MessageStatus;
-use eyre::{bail, Result};
-use prometheus::{Histogram, IntCounter, IntGauge};
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::error::TryRecvError;
+use ethers::types::U256;
+use eyre::Result;
+use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
-use tokio::time::Instant;
 use tracing::debug;
 use tracing::instrument;
+use tracing::warn;
 use tracing::{info, info_span, instrument::Instrumented, Instrument};
 
-use super::SubmitMessageArgs;
+use super::{
+    balance::BalanceGuard,
+    batch::{attribute_gas, BatchConf},
+    queues::SubmitterQueues,
+    SubmitMessageArgs,
+};
 
 /// SerialSubmitter accepts undelivered messages over a channel from a MessageProcessor.  It is
 /// responsible for executing the right strategy to deliver those messages to the destination
@@ -68,6 +71,11 @@ use super::SubmitMessageArgs;
 ///     <num_retries, leaf_idx>
 /// picking the lexicographically least element in the runnable set to execute next.
 ///
+/// Ahead of this key, messages are ordered by a pluggable comparator (see
+/// `scheduler::RunQueueOrder`). By default this gives a "fast lane" to messages whose gas
+/// payment covered a configured premium over their estimated delivery cost: they are put in
+/// a higher priority tier, and all runnable messages in higher tiers are picked first.
+///
 ///
 /// Implementation
 /// --------------
@@ -82,7 +90,7 @@ use super::SubmitMessageArgs;
 ///  *  Wrong destination chain (currently checked by processor)
 ///  *  Checkpoint index < leaf index (currently checked by processor)
 ///
-/// Therefore, we maintain two queues of messages:
+/// Therefore, we maintain three queues of messages:
 ///
 ///   1.  run_queue: messages which are eligible for submission but waiting for
 ///       their turn to run, since we can only do one at a time.
@@ -90,11 +98,61 @@ use super::SubmitMessageArgs;
 ///   2.  wait_queue: messages currently ineligible for submission, due to one of the
 ///       reasons listed above (e.g. index not covered by checkpoint, insufficient gas, etc).
 ///
+///   3.  verification_queue: messages which have been submitted (or observed as processed),
+///       but whose processing is not yet final on the destination chain. Once the inbox
+///       reports the message as processed `finality_blocks` behind the chain tip, delivery is
+///       committed to AbacusDB. Messages which are not confirmed within
+///       `VERIFICATION_TIMEOUT` go back to the wait queue, e.g. because a re-org dropped the
+///       processing transaction.
+///
+/// These queues, and the handling of messages in them described below, are shared with the
+/// ConcurrentSubmitter (see `queues::SubmitterQueues`).
+///
 /// Note that there is no retry queue. This is because if submission fails for a retriable
 /// reason, the message instead goes directly back on to the runnable queue.
-/// If submission fails, the message is sent to the back of the queue. This is to ensure
-/// all messages are retried frequently, and not just those that happen to have a lower
-/// retry count.
+///
+/// Where a message goes after a failure depends on the kind of failure (see
+/// `retry::RetryPolicy`): after a transient failure, e.g. a dropped transaction, the message
+/// goes to the front of the queue, while a message whose processing reverted goes to the back.
+/// This is to ensure all messages are retried frequently, and not just those that happen to
+/// have a lower retry count. Either way the message is not attempted again until an
+/// exponentially growing backoff has elapsed, during which other messages are picked instead.
+///
+/// Before a process transaction is sent, the call is simulated. If it would revert, no
+/// transaction is sent and the decoded revert reason is counted in metrics and stored as the
+/// message's last error. Reverts that retrying cannot change, e.g. an invalid proof, are
+/// permanent: the message is dead-lettered straight away rather than retried.
+///
+/// Messages whose retries are exhausted are dead-lettered: they are recorded in AbacusDB, where
+/// operators can inspect them, and held aside until an operator re-drives them by leaf index,
+/// at which point they go to the front of the run queue with a fresh retry budget.
+///
+/// The retry count, backoff and last error of each message are persisted in AbacusDB (see
+/// `scheduling_state`). When the processor sends a message again after a restart, this state
+/// is restored, and a message that was dead-lettered is held aside again, so that backoff and
+/// dead-lettering survive deploys.
+///
+/// The next message is picked from the run queue by weighted fair queuing across senders
+/// (see `scheduler::FairScheduler`), so that one application flooding the destination with
+/// messages cannot starve the others. Messages whose sender or recipient has exhausted its
+/// configured rate limit are passed over until the limit allows them.
+///
+/// Operators can also use the admin API to retry or skip individual queued messages, and to
+/// pause submission altogether. While paused, the queues are still updated each tick but no
+/// message is submitted. Submission is likewise paused, with messages staying queued, while
+/// the destination wallet's balance is below the chain's configured minimum (see
+/// `BalanceGuard`), and resumes once the wallet is topped up.
+///
+/// A relayer serving several origins runs a SerialSubmitter per origin for each destination.
+/// As these share the destination's signer, they also share a `SubmissionSlot`, so that only
+/// one of them has a transaction in flight at a time and their nonces don't collide.
+///
+/// With a `BatchConf`, each tick picks up to `max_batch_size` messages and processes them in
+/// one multicall transaction rather than one transaction each. Each member is still proven
+/// against its own checkpoint. The batch is simulated first, and members whose processing
+/// would fail are processed individually instead, so that they fail with their own error and
+/// are retried like any other message. The gas used by a batch is attributed to its members
+/// in proportion to their estimated gas limits when recording relay expenses.
 ///
 /// To summarize: each scheduler `tick()`, new messages from the processor are inserted onto
 /// the wait queue.  We then scan the wait_queue, looking for messages which can be promoted to
@@ -106,40 +164,39 @@ use super::SubmitMessageArgs;
 // TODO(webbhorn): Do we also want to await finality_blocks on source chain before attempting
 // submission? Does this already happen?
 
-#[allow(dead_code)]
+/// The single transaction execution slot of a destination chain, shared by the serial
+/// submitters delivering to it.
+pub(crate) type SubmissionSlot = Arc<Mutex<()>>;
+
 #[derive(Debug)]
 pub(crate) struct SerialSubmitter {
-    /// Receiver for new messages to submit.
-    rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
-    /// Messages we are aware of that we want to eventually submit, but haven't yet, for
-    /// whatever reason. They are not in any priority order, so are held in a vector.
-    wait_queue: Vec<SubmitMessageArgs>,
-    /// Messages that are in theory deliverable, but which are waiting in a queue for their turn
-    /// to be dispatched. The SerialSubmitter can only dispatch one message at a time, so this
-    /// queue could grow.
-    run_queue: VecDeque<SubmitMessageArgs>,
+    /// The wait, run and verification queues of messages to submit, and the handling of
+    /// failed processing attempts.
+    queues: SubmitterQueues,
     /// Inbox / InboxValidatorManager on the destination chain.
     inbox_contracts: InboxContracts,
-    /// Interface to agent rocks DB for e.g. writing delivery status upon completion.
-    db: AbacusDB,
-    /// Metrics for serial submitter.
-    metrics: SerialSubmitterMetrics,
+    /// Held while a message is submitted, as other submitters may share the signer.
+    submission_slot: SubmissionSlot,
+    /// Pauses submission while the signer's balance is too low.
+    balance_guard: BalanceGuard,
+    /// If provided, several messages are processed per transaction.
+    batch_conf: Option<BatchConf>,
 }
 
 impl SerialSubmitter {
     pub(crate) fn new(
-        rx: mpsc::UnboundedReceiver<SubmitMessageArgs>,
+        queues: SubmitterQueues,
         inbox_contracts: InboxContracts,
-        db: AbacusDB,
-        metrics: SerialSubmitterMetrics,
+        submission_slot: SubmissionSlot,
+        balance_guard: BalanceGuard,
+        batch_conf: Option<BatchConf>,
     ) -> Self {
         Self {
-            rx,
-            wait_queue: Vec::new(),
-            run_queue: VecDeque::new(),
+            queues,
             inbox_contracts,
-            db,
-            metrics,
+            submission_slot,
+            balance_guard,
+            batch_conf,
         }
     }
 
@@ -157,166 +214,247 @@ impl SerialSubmitter {
     }
 
     /// Tick represents a single round of scheduling wherein we will process each queue and
-    /// await at most one message submission.  It is extracted from the main loop to allow for
+    /// await at most one message submission, or batch submission when batching.  It is extracted from the main loop to allow for
     /// testing the state of the scheduler at particular points without having to worry about
     /// concurrent access.
     async fn tick(&mut self) -> Result<()> {
-        // Pull any messages sent by processor over channel.
-        loop {
-            match self.rx.try_recv() {
-                Ok(msg) => {
-                    self.wait_queue.push(msg);
-                }
-                Err(TryRecvError::Empty) => {
-                    break;
-                }
-                Err(_) => {
-                    bail!("Disconnected rcvq or fatal err");
-                }
-            }
-        }
-
-        // TODO(webbhorn): Scan verification queue, dropping messages that have been confirmed
-        // processed by the inbox indexer observing it.  For any still-unverified messages that
-        // have been in the verification queue for > threshold_time, move them back to the wait
-        // queue for further processing.
+        self.queues.update().await?;
+        self.queues.publish();
 
-        // Promote any newly-ready messages from the wait queue to the run queue.
-        // The order of wait_messages, which includes messages asc ordered by leaf index,
-        // is preserved and pushed at the front of the run_queue to ensure that new messages
-        // are evaluated first.
-        for msg in self.wait_queue.drain(..).rev() {
-            // TODO(webbhorn): Check against interchain gas paymaster.  If now enough payment,
-            // promote to run queue.
-            self.run_queue.push_front(msg);
+        if self.queues.paused() {
+            debug!("Submission is paused by an operator");
+            return Ok(());
+        }
+        if self.balance_guard.insufficient_balance().await {
+            return Ok(());
         }
 
-        self.metrics
-            .wait_queue_length_gauge
-            .set(self.wait_queue.len() as i64);
-        self.metrics
-            .run_queue_length_gauge
-            .set(self.run_queue.len() as i64);
-
-        // Pick the next message to try processing.
-        let mut msg = match self.run_queue.pop_front() {
-            Some(m) => m,
-            None => return Ok(()),
-        };
+        // Pick the next messages to try processing. Unless batching, a single message is
+        // picked.
+        let max_batch_size = self.batch_conf.map_or(1, |conf| conf.max_batch_size);
+        let mut msgs = Vec::new();
+        while msgs.len() < max_batch_size {
+            match self.queues.next_runnable().await {
+                Some(msg) => msgs.push(msg),
+                None => break,
+            }
+        }
 
-        // If the message has already been processed according to message_status call on
-        // inbox, e.g. due to another relayer having already processed, then mark it as
-        // already-processed, and move on to the next tick.
-        // TODO(webbhorn): Make this robust to re-orgs on inbox.
-        if let MessageStatus::Processed = self
-            .inbox_contracts
-            .inbox
-            .message_status(msg.committed_message.to_leaf())
-            .await?
-        {
-            info!(
-                "Unexpected status for message with leaf index '{}' (already processed): '{:?}'",
-                msg.leaf_index, msg
-            );
-            self.record_message_process_success(&msg)?;
-            return Ok(());
+        // Go ahead and attempt processing of messages to destination chain.
+        match self.batch_conf {
+            Some(conf) if msgs.len() > 1 => self.process_batch(msgs, conf).await,
+            _ => {
+                for msg in msgs {
+                    self.process_single(msg).await;
+                }
+            }
         }
 
-        // Go ahead and attempt processing of message to destination chain.
+        Ok(())
+    }
+
+    /// Attempt processing of a single message, moving it to the verification queue if its
+    /// processing succeeds.
+    async fn process_single(&mut self, msg: SubmitMessageArgs) {
         debug!(msg=?msg, "Ready to process message");
-        // TODO: consider differentiating types of processing errors, and pushing to the front of the
-        // run queue for intermittent types of errors that can occur even if a message's processing isn't
-        // reverting, e.g. timeouts or txs being dropped from the mempool. To avoid consistently retrying
-        // only these messages, the number of retries could be considered.
         match self.process_message(&msg).await {
             Ok(()) => {
                 info!(msg=?msg, "Message processed");
+                self.queues.push_to_verification_queue(msg);
             }
+            Err(e) => self.queues.handle_process_failure(msg, e),
+        }
+    }
+
+    /// Attempt processing of several messages in one multicall transaction. Members whose
+    /// processing fails in simulation, or whose gas cannot be estimated, are processed
+    /// individually instead.
+    async fn process_batch(&mut self, msgs: Vec<SubmitMessageArgs>, conf: BatchConf) {
+        let validator_manager = self.inbox_contracts.validator_manager.clone();
+        let calls: Vec<_> = msgs.iter().map(SubmitMessageArgs::process_call).collect();
+        let successes = match validator_manager
+            .simulate_batch_process(conf.multicall_address, &calls)
+            .await
+        {
+            Ok(successes) => successes,
             Err(e) => {
-                info!(msg=?msg, leaf_index=msg.leaf_index, error=?e, "Message processing failed");
-                msg.num_retries += 1;
-                self.run_queue.push_back(msg);
+                warn!(error=?e, batch_size=msgs.len(),
+                    "Unable to simulate batch, processing its messages individually");
+                Vec::new()
             }
+        };
+
+        let mut batch = Vec::new();
+        let mut gas_estimates = Vec::new();
+        let mut individual = Vec::new();
+        for (i, msg) in msgs.into_iter().enumerate() {
+            if !successes.get(i).copied().unwrap_or(false) {
+                individual.push(msg);
+                continue;
+            }
+            let gas_limit = match validator_manager
+                .process_estimate_costs(&msg.checkpoint, &msg.committed_message.message, &msg.proof)
+                .await
+            {
+                Ok(estimate) => self.queues.gas_limit(&msg, estimate.gas_estimate),
+                Err(e) => Err(e),
+            };
+            match gas_limit {
+                Ok(gas_limit) => {
+                    gas_estimates.push(gas_limit);
+                    batch.push(msg);
+                }
+                Err(e) => {
+                    debug!(leaf_index=msg.leaf_index, error=?e, "Unable to estimate gas of batch member");
+                    individual.push(msg);
+                }
+            }
+        }
+        if !individual.is_empty() {
+            info!(leaf_indices=?individual.iter().map(|msg| msg.leaf_index).collect::<Vec<_>>(),
+                "Processing messages that would fail in a batch individually");
         }
 
-        Ok(())
+        if batch.len() > 1 {
+            self.send_batch(batch, &gas_estimates, conf).await;
+        } else {
+            individual.extend(batch);
+        }
+        for msg in individual {
+            self.process_single(msg).await;
+        }
+    }
+
+    /// Process a batch of messages in one multicall transaction, and move each member to the
+    /// verification queue or requeue it depending on whether the inbox reports it processed.
+    async fn send_batch(
+        &mut self,
+        batch: Vec<SubmitMessageArgs>,
+        gas_estimates: &[U256],
+        conf: BatchConf,
+    ) {
+        // Members that run out of gas fail without reverting the batch, so the gas limit
+        // covers their individual limits.
+        let gas_limit = gas_estimates.iter().fold(U256::zero(), |total, estimate| {
+            total.saturating_add(*estimate)
+        });
+        let result = {
+            let _slot = self.submission_slot.lock().await;
+            let calls: Vec<_> = batch.iter().map(SubmitMessageArgs::process_call).collect();
+            self.inbox_contracts
+                .validator_manager
+                .batch_process(conf.multicall_address, &calls, gas_limit)
+                .await
+        };
+        let outcome = match result {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                for msg in batch {
+                    self.queues
+                        .handle_process_failure(msg, batch_member_error(&e));
+                }
+                return;
+            }
+        };
+        info!(hash=?outcome.txid, batch_size=batch.len(), executed=outcome.executed,
+            "Batch transaction mined");
+
+        for (msg, mut share) in batch
+            .into_iter()
+            .zip(attribute_gas(&outcome, gas_estimates))
+        {
+            let status = if outcome.executed {
+                self.inbox_contracts
+                    .inbox
+                    .message_status(msg.committed_message.to_leaf())
+                    .await
+            } else {
+                Ok(MessageStatus::None)
+            };
+            match status {
+                Ok(MessageStatus::Processed) => {
+                    self.queues.record_relay_expense(&msg, &share);
+                    info!(leaf_index=?msg.leaf_index, hash=?outcome.txid,
+                        "Message successfully processed in batch, awaiting finality");
+                    self.queues.push_to_verification_queue(msg);
+                }
+                Ok(_) => {
+                    share.executed = false;
+                    self.queues.record_relay_expense(&msg, &share);
+                    self.queues.handle_process_failure(
+                        msg,
+                        ChainCommunicationError::Reverted(format!(
+                            "message not processed by batch transaction {:?}",
+                            outcome.txid
+                        )),
+                    );
+                }
+                Err(e) => {
+                    // Whether the message was processed is unknown, so leave it to
+                    // verification, which returns it to the wait queue if it was not.
+                    warn!(leaf_index=msg.leaf_index, error=?e,
+                        "Unable to check processing of batch member");
+                    self.queues.record_relay_expense(&msg, &share);
+                    self.queues.push_to_verification_queue(msg);
+                }
+            }
+        }
     }
 
     // TODO(webbhorn): Move the process() call below into a function defined over SubmitMessageArgs
     // or wrapped Schedulable(SubmitMessageArgs) so that we can fake submit in test.
-    // TODO(webbhorn): Instead of immediately marking as processed, move to a verification
-    // queue, which will wait for finality and indexing by the inbox indexer and then mark
-    // as processed (or eventually retry if no confirmation is ever seen).
-    async fn process_message(&mut self, msg: &SubmitMessageArgs) -> Result<()> {
-        let result = self
-            .inbox_contracts
-            .validator_manager
-            .process(&msg.checkpoint, &msg.committed_message.message, &msg.proof)
-            .await?;
-        self.record_message_process_success(msg)?;
+    async fn process_message(
+        &mut self,
+        msg: &SubmitMessageArgs,
+    ) -> Result<(), ChainCommunicationError> {
+        let _slot = self.submission_slot.lock().await;
+        let validator_manager = &self.inbox_contracts.validator_manager;
+        let record_revert = |e| {
+            self.queues.record_revert(&e);
+            e
+        };
+        let estimate = validator_manager
+            .process_estimate_costs(&msg.checkpoint, &msg.committed_message.message, &msg.proof)
+            .await
+            .map_err(record_revert)?;
+        let gas_limit = self.queues.gas_limit(msg, estimate.gas_estimate)?;
+        let result = validator_manager
+            .process(
+                &msg.checkpoint,
+                &msg.committed_message.message,
+                &msg.proof,
+                Some(gas_limit),
+            )
+            .await
+            .map_err(record_revert)?;
+        self.queues.record_relay_expense(msg, &result);
+        self.queues.record_gas_used(msg, &result);
+        if !result.executed {
+            return Err(ChainCommunicationError::Reverted(format!(
+                "process transaction {:?} reverted",
+                result.txid
+            )));
+        }
         info!(leaf_index=?msg.leaf_index, hash=?result.txid,
-            wq_sz=?self.wait_queue.len(), rq_sz=?self.run_queue.len(),
-            "Message successfully processed");
-        Ok(())
-    }
-
-    /// Record in AbacusDB and various metrics that this process has observed the successful
-    /// processing of a message. An Ok(()) value returned by this function is the 'commit' point
-    /// in a message's lifetime for final processing -- after this function has been seen to
-    /// return 'Ok(())', then without a wiped AbacusDB, we will never re-attempt processing for
-    /// this message again, even after the relayer restarts.
-    fn record_message_process_success(&mut self, msg: &SubmitMessageArgs) -> Result<()> {
-        self.db.mark_leaf_as_processed(msg.leaf_index)?;
-        self.metrics
-            .queue_duration_hist
-            .observe((Instant::now() - msg.enqueue_time).as_secs_f64());
-        self.metrics.max_submitted_leaf_index =
-            std::cmp::max(self.metrics.max_submitted_leaf_index, msg.leaf_index);
-        self.metrics
-            .processed_gauge
-            .set(self.metrics.max_submitted_leaf_index as i64);
-        self.metrics.messages_processed_count.inc();
+            "Message successfully processed, awaiting finality");
         Ok(())
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct SerialSubmitterMetrics {
-    run_queue_length_gauge: IntGauge,
-    wait_queue_length_gauge: IntGauge,
-    queue_duration_hist: Histogram,
-    processed_gauge: IntGauge,
-    messages_processed_count: IntCounter,
-
-    /// Private state used to update actual metrics each tick.
-    max_submitted_leaf_index: u32,
-}
-
-impl SerialSubmitterMetrics {
-    pub fn new(metrics: &CoreMetrics, outbox_chain: &str, inbox_chain: &str) -> Self {
-        Self {
-            run_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
-                outbox_chain,
-                inbox_chain,
-                "run_queue",
-            ]),
-            wait_queue_length_gauge: metrics.submitter_queue_length().with_label_values(&[
-                outbox_chain,
-                inbox_chain,
-                "wait_queue",
-            ]),
-            queue_duration_hist: metrics
-                .submitter_queue_duration_histogram()
-                .with_label_values(&[outbox_chain, inbox_chain]),
-            messages_processed_count: metrics
-                .messages_processed_count()
-                .with_label_values(&[outbox_chain, inbox_chain]),
-            processed_gauge: metrics.last_known_message_leaf_index().with_label_values(&[
-                "message_processed",
-                outbox_chain,
-                inbox_chain,
-            ]),
-            max_submitted_leaf_index: 0,
+/// A copy of an error that failed a whole batch for one of its members, as errors cannot be
+/// cloned. Errors that cannot be copied are reported as custom errors, and retried as such.
+fn batch_member_error(e: &ChainCommunicationError) -> ChainCommunicationError {
+    match e {
+        ChainCommunicationError::Reverted(reason) => {
+            ChainCommunicationError::Reverted(reason.clone())
+        }
+        ChainCommunicationError::DroppedError(txid) => ChainCommunicationError::DroppedError(*txid),
+        ChainCommunicationError::TransactionTimeout() => {
+            ChainCommunicationError::TransactionTimeout()
+        }
+        ChainCommunicationError::GasLimitExceeded(estimate, max) => {
+            ChainCommunicationError::GasLimitExceeded(*estimate, *max)
         }
+        e => ChainCommunicationError::CustomError(e.to_string().into()),
     }
 }
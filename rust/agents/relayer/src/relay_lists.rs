@@ -0,0 +1,133 @@
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use abacus_core::AbacusMessage;
+use eyre::Result;
+use serde::Deserialize;
+use tokio::{sync::watch, task::JoinHandle, time::sleep};
+use tracing::{error, info, info_span, instrument::Instrumented, Instrument};
+
+use crate::settings::matching_list::MatchingList;
+
+/// How often the relay lists file is checked for changes.
+const FILE_POLLING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The whitelist and blacklist that decide which messages are relayed.
+///
+/// When loaded from JSON, e.g. `{"whitelist": [...], "blacklist": [...]}`, a
+/// missing list matches as if it were not configured.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub(crate) struct RelayLists {
+    pub whitelist: MatchingList,
+    pub blacklist: MatchingList,
+}
+
+impl RelayLists {
+    /// Parse relay lists from JSON.
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Whether a message is whitelisted and not blacklisted.
+    pub fn allows(&self, msg: &AbacusMessage) -> bool {
+        self.whitelist.msg_matches(msg, true) && !self.blacklist.msg_matches(msg, false)
+    }
+}
+
+impl Display for RelayLists {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{whitelist: {}, blacklist: {}}}",
+            self.whitelist, self.blacklist
+        )
+    }
+}
+
+/// Replaces the relay lists used by message processors at runtime.
+#[derive(Debug, Clone)]
+pub(crate) struct RelayListsUpdater {
+    tx: Arc<watch::Sender<Arc<RelayLists>>>,
+    /// Keeps the channel open, so updates are applied even before any message
+    /// processor subscribes.
+    rx: watch::Receiver<Arc<RelayLists>>,
+}
+
+impl RelayListsUpdater {
+    pub fn new(initial: RelayLists) -> Self {
+        let (tx, rx) = watch::channel(Arc::new(initial));
+        Self {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    /// A receiver of the current relay lists.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<RelayLists>> {
+        self.rx.clone()
+    }
+
+    /// The current relay lists.
+    pub fn current(&self) -> Arc<RelayLists> {
+        self.rx.borrow().clone()
+    }
+
+    /// Replace the relay lists with ones parsed from JSON. If parsing fails, the
+    /// current lists are kept.
+    pub fn update_from_json(&self, json: &str) -> Result<()> {
+        let lists = RelayLists::from_json(json)?;
+        info!(relay_lists = %lists, "Replacing relay lists");
+        // Cannot fail, as `self.rx` keeps the channel open.
+        let _ = self.tx.send(Arc::new(lists));
+        Ok(())
+    }
+
+    /// Spawn a task that replaces the relay lists with the contents of the
+    /// file at `path` whenever they change. `loaded` is the contents the
+    /// current lists were loaded from.
+    pub fn watch_file(
+        self,
+        path: PathBuf,
+        mut loaded: String,
+    ) -> Instrumented<JoinHandle<Result<()>>> {
+        let span = info_span!("RelayListsFileWatcher", path = %path.display());
+        tokio::spawn(async move {
+            loop {
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(contents) if contents != loaded => {
+                        if let Err(e) = self.update_from_json(&contents) {
+                            error!(error = ?e, "Invalid relay lists file, keeping current relay lists");
+                        }
+                        // Only report each invalid version of the file once.
+                        loaded = contents;
+                    }
+                    Ok(_) => {}
+                    Err(e) => error!(error = ?e, "Unable to read relay lists file"),
+                }
+                sleep(FILE_POLLING_INTERVAL).await;
+            }
+        })
+        .instrument(span)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RelayLists, RelayListsUpdater};
+
+    #[test]
+    fn keeps_current_lists_on_invalid_update() {
+        let updater = RelayListsUpdater::new(RelayLists::default());
+        updater
+            .update_from_json(r#"{"blacklist": [{"destinationDomain": 1}]}"#)
+            .unwrap();
+        assert_eq!(updater.current().whitelist.to_string(), "null");
+        assert_ne!(updater.current().blacklist.to_string(), "null");
+
+        assert!(updater.update_from_json(r#"{"blacklist": 1}"#).is_err());
+        assert_ne!(updater.current().blacklist.to_string(), "null");
+    }
+}
@@ -1,35 +1,69 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use eyre::Result;
+use ethers::types::U256;
+use eyre::{bail, eyre, Result};
 use tokio::{
-    sync::mpsc,
     sync::watch::{Receiver, Sender},
+    sync::{mpsc, watch},
     task::JoinHandle,
 };
-use tracing::{info, info_span, instrument::Instrumented, Instrument};
+use tracing::{info, info_span, instrument::Instrumented, warn, Instrument};
 
 use abacus_base::{
-    chains::GelatoConf, AbacusAgentCore, Agent, CachingInterchainGasPaymaster, ContractSyncMetrics,
-    InboxContracts, MultisigCheckpointSyncer,
+    AbacusAgentCore, Agent, CachingInterchainGasPaymaster, ContractSyncMetrics, InboxContracts,
+    MultisigCheckpointSyncer, OriginContracts,
 };
-use abacus_core::{AbacusContract, MultisigSignedCheckpoint};
+use abacus_core::{AbacusCommon, AbacusContract, MultisigSignedCheckpoint};
 
-use crate::msg::gelato_submitter::GelatoSubmitter;
+use crate::admin::{run_admin_server, AdminOrigin};
+use crate::checkpoint_fetcher::CheckpointFetcher;
+use crate::msg::balance::BalanceGuard;
+use crate::msg::batch::BatchConf;
+use crate::msg::concurrent_submitter::{
+    ConcurrentOrigin, ConcurrentSubmitter, ConcurrentSubmitterConf, ConcurrentSubmitterMetrics,
+};
+use crate::msg::control::{submitter_control, SubmitterControl};
+use crate::msg::gas_limit::ProcessGasLimits;
+use crate::msg::gas_payment::{GasPaymentEnforcer, GasPaymentRequirement, PriorityTiers};
+use crate::msg::gelato_submitter::{GelatoSubmitter, GelatoSubmitterConf, GelatoSubmitterMetrics};
 use crate::msg::processor::{MessageProcessor, MessageProcessorMetrics};
-use crate::msg::serial_submitter::SerialSubmitter;
+use crate::msg::queues::{SubmitterMetrics, SubmitterQueues};
+use crate::msg::scheduler::{ByPriorityTier, FairScheduler, FairSchedulerConf};
+use crate::msg::serial_submitter::{SerialSubmitter, SubmissionSlot};
+use crate::relay_lists::{RelayLists, RelayListsUpdater};
 use crate::settings::matching_list::MatchingList;
-use crate::settings::RelayerSettings;
-use crate::{checkpoint_fetcher::CheckpointFetcher, msg::serial_submitter::SerialSubmitterMetrics};
+use crate::settings::{RelayerSettings, SubmissionStrategy};
 
 /// A relayer agent
 #[derive(Debug)]
 pub struct Relayer {
     signed_checkpoint_polling_interval: u64,
-    multisig_checkpoint_syncer: MultisigCheckpointSyncer,
+    /// The multisig checkpoint syncer of each origin, keyed by outbox chain name.
+    multisig_checkpoint_syncers: HashMap<String, MultisigCheckpointSyncer>,
     core: AbacusAgentCore,
-    whitelist: Arc<MatchingList>,
-    blacklist: Arc<MatchingList>,
+    relay_lists: RelayListsUpdater,
+    /// The file the relay lists are reloaded from, and the contents they were loaded from.
+    relay_lists_file: Option<(PathBuf, String)>,
+    gas_payment_requirements: HashMap<String, GasPaymentRequirement>,
+    /// Gas limit policies keyed by inbox chain name.
+    process_gas_limits: HashMap<String, ProcessGasLimits>,
+    priority_tiers: PriorityTiers,
+    /// Gelato configurations keyed by outbox and inbox chain name.
+    gelato_confs: HashMap<(String, String), GelatoSubmitterConf>,
+    /// Minimum wallet balances keyed by outbox and inbox chain name.
+    min_balances: HashMap<(String, String), U256>,
+    /// Prices of inbox native tokens in outbox native tokens, keyed by outbox and inbox chain
+    /// name.
+    exchange_rates: HashMap<(String, String), f64>,
+    concurrent_confs: HashMap<String, ConcurrentSubmitterConf>,
+    batch_confs: HashMap<String, BatchConf>,
+    max_processing_retries: Option<u32>,
+    scheduler_conf: FairSchedulerConf,
+    admin_port: Option<u16>,
+    admin_token: Option<String>,
 }
 
 impl AsRef<AbacusAgentCore> for Relayer {
@@ -49,26 +83,217 @@ impl Agent for Relayer {
     where
         Self: Sized,
     {
-        let multisig_checkpoint_syncer: MultisigCheckpointSyncer = settings
-            .multisigcheckpointsyncer
-            .try_into_multisig_checkpoint_syncer()?;
+        let origins = settings.as_ref().all_origins();
+
+        let mut multisig_checkpoint_syncers = HashMap::new();
+        multisig_checkpoint_syncers.insert(
+            settings.as_ref().outbox.name.clone(),
+            settings
+                .multisigcheckpointsyncer
+                .try_into_multisig_checkpoint_syncer()?,
+        );
+        for origin_name in settings.as_ref().origins.keys() {
+            let conf = settings
+                .origincheckpointsyncers
+                .get(origin_name)
+                .ok_or_else(|| {
+                    eyre!("No origincheckpointsyncers entry for origin {origin_name}")
+                })?;
+            multisig_checkpoint_syncers.insert(
+                origin_name.clone(),
+                conf.try_into_multisig_checkpoint_syncer()?,
+            );
+        }
+
+        let (relay_lists, relay_lists_file) = match &settings.relaylistsfile {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .map_err(|e| eyre!("Unable to read relaylistsfile {path}: {e}"))?;
+                let relay_lists = RelayLists::from_json(&contents)
+                    .map_err(|e| eyre!("Invalid relaylistsfile {path}: {e}"))?;
+                (relay_lists, Some((PathBuf::from(path), contents)))
+            }
+            None => (
+                RelayLists {
+                    whitelist: parse_matching_list(&settings.whitelist)?,
+                    blacklist: parse_matching_list(&settings.blacklist)?,
+                },
+                None,
+            ),
+        };
+        info!(relay_lists = %relay_lists, "Whitelist configuration");
+
+        let gas_payment_requirements = origins
+            .iter()
+            .flat_map(|origin| origin.inboxes.keys())
+            .map(|inbox_name| {
+                let policy = settings.gaspaymentenforcement.policy_for(inbox_name);
+                Ok((inbox_name.clone(), GasPaymentRequirement::try_from(policy)?))
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+        info!(gas_payment_requirements = ?gas_payment_requirements, "Gas payment configuration");
+
+        let process_gas_limits = settings
+            .processgaslimits
+            .iter()
+            .map(|(inbox_name, conf)| Ok((inbox_name.clone(), ProcessGasLimits::try_from(conf)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        info!(process_gas_limits = ?process_gas_limits, "Process gas limit configuration");
+
+        let priority_tiers = PriorityTiers::try_from(&settings.priority)?;
+        info!(priority_tiers = ?priority_tiers, "Priority configuration");
+
+        let max_processing_retries = match settings.maxprocessingretries.as_str() {
+            "" => None,
+            retries => Some(
+                retries
+                    .parse()
+                    .map_err(|e| eyre!("Invalid maxprocessingretries {retries}: {e}"))?,
+            ),
+        };
+
+        let scheduler_conf = FairSchedulerConf::try_from(&settings.scheduling)?;
+        info!(scheduler_conf = ?scheduler_conf, "Scheduling configuration");
+
+        let admin_port = settings
+            .adminport
+            .as_deref()
+            .map(|port| {
+                port.parse()
+                    .map_err(|e| eyre!("Invalid adminport {port}: {e}"))
+            })
+            .transpose()?;
+        let admin_token = settings.admintoken.clone();
+        if admin_port.is_some() && admin_token.is_none() {
+            warn!("No admintoken configured, admin API POST endpoints are disabled");
+        }
+
+        let mut gelato_confs = HashMap::new();
+        for origin in origins.iter() {
+            for (inbox_name, inbox_setup) in origin.inboxes.iter() {
+                match &inbox_setup.gelato_conf {
+                    Some(conf)
+                        if conf.enabled_for_message_submission
+                            && inbox_setup.disabled.is_none() =>
+                    {
+                        let sponsor =
+                            settings
+                                .as_ref()
+                                .get_signer(inbox_name)
+                                .await
+                                .ok_or_else(|| {
+                                    eyre!(
+                                        "A signer is required to submit to {inbox_name} via Gelato"
+                                    )
+                                })?;
+                        gelato_confs.insert(
+                            (origin.outbox.name.clone(), inbox_name.clone()),
+                            GelatoSubmitterConf::new(conf, sponsor)?,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
 
-        let whitelist = parse_matching_list(&settings.whitelist);
-        let blacklist = parse_matching_list(&settings.blacklist);
-        info!(whitelist = %whitelist, blacklist = %blacklist, "Whitelist configuration");
+        let mut min_balances = HashMap::new();
+        for origin in origins.iter() {
+            for (inbox_name, inbox_setup) in origin.inboxes.iter() {
+                if let Some(min_balance) = inbox_setup.min_balance()? {
+                    min_balances.insert(
+                        (origin.outbox.name.clone(), inbox_name.clone()),
+                        min_balance,
+                    );
+                }
+            }
+        }
+        info!(min_balances = ?min_balances, "Minimum wallet balance configuration");
+
+        let mut exchange_rates = HashMap::new();
+        for (outbox_name, rates) in settings.exchangerates.iter() {
+            for (inbox_name, rate) in rates.iter() {
+                let parsed: f64 = rate
+                    .parse()
+                    .map_err(|e| eyre!("Invalid exchange rate {rate}: {e}"))?;
+                if !parsed.is_finite() || parsed <= 0. {
+                    bail!("Invalid exchange rate {rate}");
+                }
+                exchange_rates.insert((outbox_name.clone(), inbox_name.clone()), parsed);
+            }
+        }
+        info!(exchange_rates = ?exchange_rates, "Exchange rate configuration");
+
+        // Estimated costs are in destination chain wei, so can only be compared to payments on
+        // routes that can convert them into origin chain wei.
+        for origin in origins.iter() {
+            for inbox_name in origin.inboxes.keys() {
+                let route = (origin.outbox.name.clone(), inbox_name.clone());
+                if matches!(
+                    gas_payment_requirements.get(inbox_name),
+                    Some(GasPaymentRequirement::MeetsEstimatedCost { .. })
+                ) && !exchange_rates.contains_key(&route)
+                {
+                    bail!(
+                        "Gas payment policy meetsEstimatedCost for {} -> {} requires an exchange rate",
+                        route.0,
+                        route.1
+                    );
+                }
+            }
+        }
+
+        let mut concurrent_confs = HashMap::new();
+        let mut batch_confs = HashMap::new();
+        for inbox_name in origins.iter().flat_map(|origin| origin.inboxes.keys()) {
+            match settings.submissionstrategy.strategy_for(inbox_name) {
+                SubmissionStrategy::Serial => {}
+                SubmissionStrategy::Concurrent {
+                    max_in_flight,
+                    stuck_tx_timeout,
+                } => {
+                    concurrent_confs.insert(
+                        inbox_name.clone(),
+                        ConcurrentSubmitterConf::new(max_in_flight, stuck_tx_timeout.as_deref())?,
+                    );
+                }
+                SubmissionStrategy::Batching {
+                    max_batch_size,
+                    multicall_address,
+                } => {
+                    batch_confs.insert(
+                        inbox_name.clone(),
+                        BatchConf::new(max_batch_size, multicall_address.as_deref())?,
+                    );
+                }
+            }
+        }
+        info!(concurrent_confs = ?concurrent_confs, "Concurrent submission configuration");
+        info!(batch_confs = ?batch_confs, "Batch submission configuration");
 
         Ok(Self {
             signed_checkpoint_polling_interval: settings
                 .signedcheckpointpollinginterval
                 .parse()
                 .unwrap_or(5),
-            multisig_checkpoint_syncer,
+            multisig_checkpoint_syncers,
             core: settings
                 .as_ref()
                 .try_into_abacus_core(Self::AGENT_NAME, true)
                 .await?,
-            whitelist,
-            blacklist,
+            relay_lists: RelayListsUpdater::new(relay_lists),
+            relay_lists_file,
+            gas_payment_requirements,
+            process_gas_limits,
+            priority_tiers,
+            gelato_confs,
+            min_balances,
+            exchange_rates,
+            concurrent_confs,
+            batch_confs,
+            max_processing_retries,
+            scheduler_conf,
+            admin_port,
+            admin_token,
         })
     }
 }
@@ -76,79 +301,176 @@ impl Agent for Relayer {
 impl Relayer {
     fn run_outbox_sync(
         &self,
+        origin: &OriginContracts,
         sync_metrics: ContractSyncMetrics,
     ) -> Instrumented<JoinHandle<Result<()>>> {
-        let outbox = self.outbox();
-        let sync = outbox.sync(self.as_ref().indexer.clone(), sync_metrics);
+        let outbox = origin.outbox.clone();
+        let sync = outbox.sync(origin.indexer.clone(), sync_metrics);
         sync
     }
 
     fn run_interchain_gas_paymaster_sync(
         &self,
+        origin: &OriginContracts,
         paymaster: Arc<CachingInterchainGasPaymaster>,
         sync_metrics: ContractSyncMetrics,
     ) -> Instrumented<JoinHandle<Result<()>>> {
-        paymaster.sync(self.as_ref().indexer.clone(), sync_metrics)
+        paymaster.sync(origin.indexer.clone(), sync_metrics)
     }
 
     fn run_checkpoint_fetcher(
         &self,
+        origin: &OriginContracts,
         signed_checkpoint_sender: Sender<Option<MultisigSignedCheckpoint>>,
     ) -> Instrumented<JoinHandle<Result<()>>> {
+        let outbox = origin.outbox.outbox();
         let checkpoint_fetcher = CheckpointFetcher::new(
-            self.outbox().outbox(),
+            outbox.clone(),
             self.signed_checkpoint_polling_interval,
-            self.multisig_checkpoint_syncer.clone(),
+            self.multisig_checkpoint_syncers[outbox.chain_name()].clone(),
             signed_checkpoint_sender,
             self.core.metrics.last_known_message_leaf_index(),
         );
         checkpoint_fetcher.spawn()
     }
 
-    #[tracing::instrument(fields(inbox=%inbox_contracts.inbox.chain_name()))]
+    /// Run the message processor and submitter delivering the messages of `origin` to an
+    /// inbox. If the inbox is submitted to concurrently, the origin is instead added to
+    /// `concurrent_origins`, for one submitter to deliver the messages of all origins.
+    #[tracing::instrument(skip(concurrent_origins), fields(outbox=%origin.outbox.outbox().chain_name(), inbox=%inbox_contracts.inbox.chain_name()))]
+    #[allow(clippy::too_many_arguments)]
     fn run_inbox(
         &self,
+        origin: &OriginContracts,
         inbox_contracts: InboxContracts,
         signed_checkpoint_receiver: Receiver<Option<MultisigSignedCheckpoint>>,
-        gelato_conf: Option<GelatoConf>,
+        gelato_conf: Option<GelatoSubmitterConf>,
+        control: SubmitterControl,
+        submission_slot: SubmissionSlot,
+        concurrent_origins: &mut HashMap<String, Vec<ConcurrentOrigin>>,
     ) -> Instrumented<JoinHandle<Result<()>>> {
-        let outbox = self.outbox().outbox();
+        let outbox = origin.outbox.outbox();
+        let finality_blocks = self
+            .core
+            .settings
+            .inbox_setup(outbox.chain_name(), inbox_contracts.inbox.chain_name())
+            .expect("inbox contracts are built from settings")
+            .finality_blocks();
         let metrics = MessageProcessorMetrics::new(
             &self.core.metrics,
             outbox.chain_name(),
             inbox_contracts.inbox.chain_name(),
         );
         let (new_messages_send_channel, new_messages_receive_channel) = mpsc::unbounded_channel();
+        let exchange_rate = self
+            .exchange_rates
+            .get(&(
+                outbox.chain_name().to_owned(),
+                inbox_contracts.inbox.chain_name().to_owned(),
+            ))
+            .copied();
+        let gas_payment_enforcer = GasPaymentEnforcer::new(
+            self.gas_payment_requirements
+                .get(inbox_contracts.inbox.chain_name())
+                .copied()
+                .unwrap_or(GasPaymentRequirement::None),
+            self.priority_tiers.clone(),
+            inbox_contracts.validator_manager.clone(),
+            exchange_rate,
+            origin.outbox.db(),
+        );
+        let gas_limits = self
+            .process_gas_limits
+            .get(inbox_contracts.inbox.chain_name())
+            .cloned()
+            .unwrap_or_default();
+        // Gelato pays for its relays, so the wallet balance only guards our own submitters.
+        let balance_guard = BalanceGuard::new(
+            self.min_balances
+                .get(&(
+                    outbox.chain_name().to_owned(),
+                    inbox_contracts.inbox.chain_name().to_owned(),
+                ))
+                .copied(),
+            inbox_contracts.validator_manager.clone(),
+        );
         let submit_fut = match gelato_conf {
-            Some(cfg) if cfg.enabled_for_message_submission => {
+            Some(cfg) => {
                 let gelato_submitter = GelatoSubmitter::new(
                     cfg,
                     new_messages_receive_channel,
+                    control,
                     inbox_contracts.clone(),
-                    self.outbox().db(),
+                    origin.outbox.db(),
+                    gas_payment_enforcer,
+                    finality_blocks,
+                    self.max_processing_retries,
+                    GelatoSubmitterMetrics::new(
+                        &self.core.metrics,
+                        outbox.chain_name(),
+                        inbox_contracts.inbox.chain_name(),
+                    ),
                 );
-                gelato_submitter.spawn()
+                Some(gelato_submitter.spawn())
             }
-            _ => {
-                let serial_submitter = SerialSubmitter::new(
+            None => {
+                let queues = SubmitterQueues::new(
                     new_messages_receive_channel,
+                    control,
                     inbox_contracts.clone(),
-                    self.outbox().db(),
-                    SerialSubmitterMetrics::new(
+                    origin.outbox.db(),
+                    gas_payment_enforcer,
+                    gas_limits,
+                    FairScheduler::new(self.scheduler_conf.clone(), Box::new(ByPriorityTier)),
+                    finality_blocks,
+                    self.max_processing_retries,
+                    SubmitterMetrics::new(
                         &self.core.metrics,
                         outbox.chain_name(),
                         inbox_contracts.inbox.chain_name(),
+                        exchange_rate,
                     ),
                 );
-                serial_submitter.spawn()
+                match self
+                    .concurrent_confs
+                    .get(inbox_contracts.inbox.chain_name())
+                {
+                    Some(_) => {
+                        concurrent_origins
+                            .entry(inbox_contracts.inbox.chain_name().to_owned())
+                            .or_default()
+                            .push(ConcurrentOrigin::new(
+                                queues,
+                                inbox_contracts.clone(),
+                                balance_guard,
+                                ConcurrentSubmitterMetrics::new(
+                                    &self.core.metrics,
+                                    outbox.chain_name(),
+                                    inbox_contracts.inbox.chain_name(),
+                                ),
+                            ));
+                        None
+                    }
+                    None => {
+                        let serial_submitter = SerialSubmitter::new(
+                            queues,
+                            inbox_contracts.clone(),
+                            submission_slot,
+                            balance_guard,
+                            self.batch_confs
+                                .get(inbox_contracts.inbox.chain_name())
+                                .copied(),
+                        );
+                        Some(serial_submitter.spawn())
+                    }
+                }
             }
         };
         let message_processor = MessageProcessor::new(
             outbox,
-            self.outbox().db(),
+            origin.outbox.db(),
             inbox_contracts,
-            self.whitelist.clone(),
-            self.blacklist.clone(),
+            self.relay_lists.subscribe(),
             metrics,
             new_messages_send_channel,
             signed_checkpoint_receiver,
@@ -159,52 +481,117 @@ impl Relayer {
         );
         let process_fut = message_processor.spawn();
         tokio::spawn(async move {
-            let res = tokio::try_join!(submit_fut, process_fut)?;
-            info!(?res, "try_join finished for inbox");
+            match submit_fut {
+                Some(submit_fut) => {
+                    let res = tokio::try_join!(submit_fut, process_fut)?;
+                    info!(?res, "try_join finished for inbox");
+                }
+                None => {
+                    let res = process_fut.await?;
+                    info!(?res, "message processor finished for inbox");
+                }
+            }
             Ok(())
         })
         .instrument(info_span!("run inbox"))
     }
 
     pub fn run(&self) -> Instrumented<JoinHandle<Result<()>>> {
-        let (signed_checkpoint_sender, signed_checkpoint_receiver) =
-            tokio::sync::watch::channel::<Option<MultisigSignedCheckpoint>>(None);
+        let mut tasks: Vec<Instrumented<JoinHandle<Result<()>>>> = vec![];
+        let mut admin_origins = HashMap::new();
+        // Serial submitters delivering to the same destination for different origins share
+        // its signer, so take turns submitting.
+        let mut submission_slots: HashMap<String, SubmissionSlot> = HashMap::new();
+        // The origins delivering to each concurrently submitted inbox, keyed by inbox name.
+        let mut concurrent_origins: HashMap<String, Vec<ConcurrentOrigin>> = HashMap::new();
+        let sync_metrics = ContractSyncMetrics::new(self.metrics());
 
-        let mut tasks: Vec<Instrumented<JoinHandle<Result<()>>>> = self
-            .inboxes()
-            .iter()
-            .map(|(inbox_name, inbox_contracts)| {
-                self.run_inbox(
-                    inbox_contracts.clone(),
-                    signed_checkpoint_receiver.clone(),
-                    self.core.settings.inboxes[inbox_name].gelato_conf.clone(),
-                )
-            })
-            .collect();
+        for origin in self.origins() {
+            let (signed_checkpoint_sender, signed_checkpoint_receiver) =
+                watch::channel::<Option<MultisigSignedCheckpoint>>(None);
+            let outbox_name = origin.outbox.outbox().chain_name().to_owned();
+            let mut admin_origin =
+                AdminOrigin::new(origin.outbox.db(), signed_checkpoint_receiver.clone());
 
-        tasks.push(self.run_checkpoint_fetcher(signed_checkpoint_sender));
+            for (inbox_name, inbox_contracts) in origin.inboxes.iter() {
+                let (control, handle) = submitter_control(inbox_contracts.inbox.local_domain());
+                admin_origin.add_submitter(inbox_name.clone(), handle);
+                tasks.push(
+                    self.run_inbox(
+                        &origin,
+                        inbox_contracts.clone(),
+                        signed_checkpoint_receiver.clone(),
+                        self.gelato_confs
+                            .get(&(outbox_name.clone(), inbox_name.clone()))
+                            .cloned(),
+                        control,
+                        submission_slots
+                            .entry(inbox_name.clone())
+                            .or_default()
+                            .clone(),
+                        &mut concurrent_origins,
+                    ),
+                );
+            }
+            admin_origins.insert(outbox_name.clone(), admin_origin);
 
-        let sync_metrics = ContractSyncMetrics::new(self.metrics());
-        tasks.push(self.run_outbox_sync(sync_metrics.clone()));
+            tasks.push(self.run_checkpoint_fetcher(&origin, signed_checkpoint_sender));
+            tasks.push(self.run_outbox_sync(&origin, sync_metrics.clone()));
+
+            if let Some(paymaster) = origin.interchain_gas_paymaster.clone() {
+                tasks.push(self.run_interchain_gas_paymaster_sync(
+                    &origin,
+                    paymaster,
+                    sync_metrics.clone(),
+                ));
+            } else {
+                info!(outbox = %outbox_name, "Interchain Gas Paymaster not provided, not running sync");
+            }
+        }
+
+        for (inbox_name, origins) in concurrent_origins {
+            let concurrent_submitter = ConcurrentSubmitter::new(
+                *self
+                    .concurrent_confs
+                    .get(&inbox_name)
+                    .expect("concurrent origins are only added for configured inboxes"),
+                origins,
+            );
+            tasks.push(concurrent_submitter.spawn());
+        }
+
+        if let Some(port) = self.admin_port {
+            let admin_server = run_admin_server(
+                port,
+                self.admin_token.clone(),
+                admin_origins,
+                self.relay_lists.clone(),
+            );
+            tasks.push(
+                tokio::spawn(async move { Ok(admin_server.await?) })
+                    .instrument(info_span!("admin server")),
+            );
+        }
 
-        if let Some(paymaster) = self.interchain_gas_paymaster() {
-            tasks.push(self.run_interchain_gas_paymaster_sync(paymaster, sync_metrics));
-        } else {
-            info!("Interchain Gas Paymaster not provided, not running sync");
+        if let Some((path, contents)) = &self.relay_lists_file {
+            tasks.push(
+                self.relay_lists
+                    .clone()
+                    .watch_file(path.clone(), contents.clone()),
+            );
         }
 
         self.run_all(tasks)
     }
 }
 
-fn parse_matching_list(list: &Option<String>) -> Arc<MatchingList> {
-    Arc::new(
-        list.as_deref()
-            .map(serde_json::from_str)
-            .transpose()
-            .expect("Invalid matching list received")
-            .unwrap_or_default(),
-    )
+fn parse_matching_list(list: &Option<String>) -> Result<MatchingList> {
+    Ok(list
+        .as_deref()
+        .map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| eyre!("Invalid matching list: {e}"))?
+        .unwrap_or_default())
 }
 
 #[cfg(test)]
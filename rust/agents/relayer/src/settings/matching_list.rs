@@ -1,7 +1,7 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
-use std::num::ParseIntError;
+use std::ops::RangeInclusive;
 
 use ethers::prelude::*;
 use serde::de::{Error, SeqAccess, Visitor};
@@ -17,6 +17,21 @@ use abacus_core::AbacusMessage;
 /// - wildcard "*"
 /// - single value in decimal or hex (must start with `0x`) format
 /// - list of values in decimal or hex format
+///
+/// Domains may also be given as inclusive ranges, e.g. `"1000-1999"`, and
+/// addresses as hex prefixes ending in `*`, e.g. `"0x9d44*"`. A prefix of up
+/// to 40 hex digits matches 20 byte addresses, a longer one matches the full 32
+/// bytes.
+///
+/// Elements may additionally restrict the message body:
+/// - `bodySelector`: one or a list of 4 byte function selectors, one of which
+///   the body must start with
+/// - `bodyPrefix`: one or a list of hex byte strings, one of which the body must
+///   start with
+/// - `maxBodyLength`: the maximum length of the body in bytes
+///
+/// An element with `"negate": true` matches exactly the messages it would not
+/// match otherwise.
 #[derive(Debug, Deserialize, Default, Clone)]
 #[serde(transparent)]
 pub struct MatchingList(Option<Vec<ListElement>>);
@@ -25,6 +40,8 @@ pub struct MatchingList(Option<Vec<ListElement>>);
 enum Filter<T> {
     Wildcard,
     Enumerated(Vec<T>),
+    /// Values within any of the inclusive ranges.
+    Ranges(Vec<RangeInclusive<T>>),
 }
 
 impl<T> Default for Filter<T> {
@@ -33,11 +50,22 @@ impl<T> Default for Filter<T> {
     }
 }
 
-impl<T: PartialEq> Filter<T> {
+impl<T: PartialOrd> Filter<T> {
+    /// A filter for the ranges, which enumerates their values if every range
+    /// holds a single value.
+    fn from_ranges(ranges: Vec<RangeInclusive<T>>) -> Self {
+        if ranges.iter().all(|r| r.start() == r.end()) {
+            Self::Enumerated(ranges.into_iter().map(|r| r.into_inner().0).collect())
+        } else {
+            Self::Ranges(ranges)
+        }
+    }
+
     fn matches(&self, v: &T) -> bool {
         match self {
             Filter::Wildcard => true,
             Filter::Enumerated(list) => list.iter().any(|i| i == v),
+            Filter::Ranges(ranges) => ranges.iter().any(|r| r.contains(v)),
         }
     }
 }
@@ -54,6 +82,13 @@ impl<T: Display> Display for Filter<T> {
                 }
                 write!(f, "]")
             }
+            Self::Ranges(l) => {
+                write!(f, "[")?;
+                for r in l {
+                    write!(f, "{}-{},", r.start(), r.end())?;
+                }
+                write!(f, "]")
+            }
         }
     }
 }
@@ -65,13 +100,13 @@ enum StrOrInt<'a> {
     Int(u32),
 }
 
-impl TryFrom<StrOrInt<'_>> for u32 {
-    type Error = ParseIntError;
+impl TryFrom<StrOrInt<'_>> for RangeInclusive<u32> {
+    type Error = String;
 
     fn try_from(v: StrOrInt) -> Result<Self, Self::Error> {
         match v {
-            StrOrInt::Str(s) => s.parse(),
-            StrOrInt::Int(i) => Ok(i),
+            StrOrInt::Str(s) => parse_domain_range(s),
+            StrOrInt::Int(i) => Ok(i..=i),
         }
     }
 }
@@ -81,7 +116,7 @@ impl<'de> Visitor<'de> for FilterVisitor<u32> {
     type Value = Filter<u32>;
 
     fn expecting(&self, fmt: &mut Formatter) -> fmt::Result {
-        write!(fmt, "Expecting either a wildcard \"*\", decimal/hex value string, or list of decimal/hex value strings")
+        write!(fmt, "Expecting either a wildcard \"*\", decimal/hex value or range string, or list of decimal/hex value or range strings")
     }
 
     fn visit_u32<E>(self, v: u32) -> Result<Self::Value, E>
@@ -109,7 +144,7 @@ impl<'de> Visitor<'de> for FilterVisitor<u32> {
         Ok(if v == "*" {
             Self::Value::Wildcard
         } else {
-            Self::Value::Enumerated(vec![v.parse::<u32>().map_err(to_serde_err)?])
+            Self::Value::from_ranges(vec![parse_domain_range(v).map_err(to_serde_err)?])
         })
     }
 
@@ -121,7 +156,7 @@ impl<'de> Visitor<'de> for FilterVisitor<u32> {
         while let Some(i) = seq.next_element::<StrOrInt>()? {
             values.push(i.try_into().map_err(to_serde_err)?);
         }
-        Ok(Self::Value::Enumerated(values))
+        Ok(Self::Value::from_ranges(values))
     }
 }
 
@@ -131,7 +166,7 @@ impl<'de> Visitor<'de> for FilterVisitor<H256> {
     fn expecting(&self, fmt: &mut Formatter) -> fmt::Result {
         write!(
             fmt,
-            "Expecting either a wildcard \"*\", hex address string, or list of hex address strings"
+            "Expecting either a wildcard \"*\", hex address or prefix string, or list of hex address or prefix strings"
         )
     }
 
@@ -142,7 +177,7 @@ impl<'de> Visitor<'de> for FilterVisitor<H256> {
         Ok(if v == "*" {
             Self::Value::Wildcard
         } else {
-            Self::Value::Enumerated(vec![parse_addr(v)?])
+            Self::Value::from_ranges(vec![parse_addr_range(v)?])
         })
     }
 
@@ -152,9 +187,9 @@ impl<'de> Visitor<'de> for FilterVisitor<H256> {
     {
         let mut values = Vec::new();
         while let Some(i) = seq.next_element::<&str>()? {
-            values.push(parse_addr(i)?)
+            values.push(parse_addr_range(i)?)
         }
-        Ok(Self::Value::Enumerated(values))
+        Ok(Self::Value::from_ranges(values))
     }
 }
 
@@ -176,6 +211,43 @@ impl<'de> Deserialize<'de> for Filter<H256> {
     }
 }
 
+/// Byte strings, one of which a message body must start with.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(from = "OneOrMany<Bytes>")]
+struct BodyPrefixes(Vec<Bytes>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl From<OneOrMany<Bytes>> for BodyPrefixes {
+    fn from(v: OneOrMany<Bytes>) -> Self {
+        match v {
+            OneOrMany::One(prefix) => Self(vec![prefix]),
+            OneOrMany::Many(prefixes) => Self(prefixes),
+        }
+    }
+}
+
+impl BodyPrefixes {
+    fn matches(&self, body: &[u8]) -> bool {
+        self.0.iter().any(|prefix| body.starts_with(prefix))
+    }
+}
+
+impl Display for BodyPrefixes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for i in &self.0 {
+            write!(f, "{i},")?;
+        }
+        write!(f, "]")
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(tag = "type")]
 struct ListElement {
@@ -187,11 +259,60 @@ struct ListElement {
     dst_domain: Filter<u32>,
     #[serde(default, rename = "destinationAddress")]
     dst_address: Filter<H256>,
+    #[serde(
+        default,
+        rename = "bodySelector",
+        deserialize_with = "deserialize_selectors"
+    )]
+    body_selector: Option<BodyPrefixes>,
+    #[serde(default, rename = "bodyPrefix")]
+    body_prefix: Option<BodyPrefixes>,
+    #[serde(default, rename = "maxBodyLength")]
+    max_body_len: Option<usize>,
+    /// Match exactly the messages the rest of the element does not match.
+    #[serde(default)]
+    negate: bool,
+}
+
+impl ListElement {
+    fn matches(&self, info: MatchInfo) -> bool {
+        let matches = self.src_domain.matches(&info.src_domain)
+            && self.src_address.matches(info.src_addr)
+            && self.dst_domain.matches(&info.dst_domain)
+            && self.dst_address.matches(info.dst_addr)
+            && self
+                .body_selector
+                .as_ref()
+                .map_or(true, |s| s.matches(info.body))
+            && self
+                .body_prefix
+                .as_ref()
+                .map_or(true, |p| p.matches(info.body))
+            && self.max_body_len.map_or(true, |max| info.body.len() <= max);
+        matches != self.negate
+    }
 }
 
 impl Display for ListElement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "{{sourceDomain: {}, sourceAddress: {}, destinationDomain: {}, destinationAddress: {}}}", self.src_domain, self.src_address, self.dst_domain, self.dst_address)
+        write!(
+            f,
+            "{{sourceDomain: {}, sourceAddress: {}, destinationDomain: {}, destinationAddress: {}",
+            self.src_domain, self.src_address, self.dst_domain, self.dst_address
+        )?;
+        if let Some(selector) = &self.body_selector {
+            write!(f, ", bodySelector: {selector}")?;
+        }
+        if let Some(prefix) = &self.body_prefix {
+            write!(f, ", bodyPrefix: {prefix}")?;
+        }
+        if let Some(max) = self.max_body_len {
+            write!(f, ", maxBodyLength: {max}")?;
+        }
+        if self.negate {
+            write!(f, ", negate: true")?;
+        }
+        write!(f, "}}")
     }
 }
 
@@ -201,6 +322,7 @@ struct MatchInfo<'a> {
     src_addr: &'a H256,
     dst_domain: u32,
     dst_addr: &'a H256,
+    body: &'a [u8],
 }
 
 impl<'a> From<&'a AbacusMessage> for MatchInfo<'a> {
@@ -210,6 +332,7 @@ impl<'a> From<&'a AbacusMessage> for MatchInfo<'a> {
             src_addr: &msg.sender,
             dst_domain: msg.destination,
             dst_addr: &msg.recipient,
+            body: &msg.body,
         }
     }
 }
@@ -233,12 +356,7 @@ impl MatchingList {
 }
 
 fn matches_any_rule<'a>(mut rules: impl Iterator<Item = &'a ListElement>, info: MatchInfo) -> bool {
-    rules.any(|rule| {
-        rule.src_domain.matches(&info.src_domain)
-            && rule.src_address.matches(info.src_addr)
-            && rule.dst_domain.matches(&info.dst_domain)
-            && rule.dst_address.matches(info.dst_addr)
-    })
+    rules.any(|rule| rule.matches(info))
 }
 
 impl Display for MatchingList {
@@ -268,6 +386,58 @@ fn parse_addr<E: Error>(addr_str: &str) -> Result<H256, E> {
     .map_err(to_serde_err)
 }
 
+/// Parse a domain, e.g. `"1000"`, or an inclusive range of domains, e.g.
+/// `"1000-1999"`.
+fn parse_domain_range(s: &str) -> Result<RangeInclusive<u32>, String> {
+    let parse = |v: &str| v.trim().parse::<u32>().map_err(|e| e.to_string());
+    match s.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse(start)?, parse(end)?);
+            if start > end {
+                return Err(format!("Domain range {s} is empty"));
+            }
+            Ok(start..=end)
+        }
+        None => parse(s).map(|v| v..=v),
+    }
+}
+
+/// Parse an address, or a hex prefix ending in `*` as the range of addresses
+/// starting with it.
+fn parse_addr_range<E: Error>(s: &str) -> Result<RangeInclusive<H256>, E> {
+    let prefix = match s.strip_suffix('*') {
+        Some(prefix) => prefix.strip_prefix("0x").unwrap_or(prefix),
+        None => return parse_addr(s).map(|addr| addr..=addr),
+    };
+    if !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(E::custom(format!("Invalid hex address prefix {s}")));
+    }
+    // Prefixes of 20 byte addresses are matched after the 12 bytes of zero
+    // padding they have as a H256.
+    let padding = match prefix.len() {
+        0..=40 => 24,
+        41..=64 => 0,
+        _ => return Err(E::custom(format!("Address prefix {s} is too long"))),
+    };
+    let fill = 64 - padding - prefix.len();
+    let start = format!("{}{prefix}{}", "0".repeat(padding), "0".repeat(fill));
+    let end = format!("{}{prefix}{}", "0".repeat(padding), "f".repeat(fill));
+    Ok(start.parse().map_err(to_serde_err)?..=end.parse().map_err(to_serde_err)?)
+}
+
+fn deserialize_selectors<'de, D>(d: D) -> Result<Option<BodyPrefixes>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let selectors = BodyPrefixes::deserialize(d)?;
+    if let Some(s) = selectors.0.iter().find(|s| s.len() != 4) {
+        return Err(D::Error::custom(format!(
+            "Function selector {s} is not 4 bytes long"
+        )));
+    }
+    Ok(Some(selectors))
+}
+
 #[cfg(test)]
 mod test {
     use crate::settings::matching_list::MatchInfo;
@@ -297,7 +467,8 @@ mod test {
                 src_domain: 0,
                 src_addr: &H256::default(),
                 dst_domain: 0,
-                dst_addr: &H256::default()
+                dst_addr: &H256::default(),
+                body: &[],
             },
             false
         ));
@@ -310,7 +481,8 @@ mod test {
                     .unwrap()
                     .into(),
                 dst_domain: 5456,
-                dst_addr: &H256::default()
+                dst_addr: &H256::default(),
+                body: &[],
             },
             false
         ))
@@ -350,7 +522,8 @@ mod test {
                 dst_addr: &"9d4454B023096f34B160D6B654540c56A1F81688"
                     .parse::<H160>()
                     .unwrap()
-                    .into()
+                    .into(),
+                body: &[],
             },
             false
         ));
@@ -363,7 +536,8 @@ mod test {
                     .unwrap()
                     .into(),
                 dst_domain: 5456,
-                dst_addr: &H256::default()
+                dst_addr: &H256::default(),
+                body: &[],
             },
             false
         ));
@@ -389,10 +563,65 @@ mod test {
             src_addr: &H256::default(),
             dst_domain: 0,
             dst_addr: &H256::default(),
+            body: &[],
         };
         // whitelist use
         assert!(MatchingList(None).matches(info, true));
         // blacklist use
         assert!(!MatchingList(None).matches(info, false));
     }
+
+    #[test]
+    fn config_with_ranges_and_prefixes() {
+        let list: MatchingList = serde_json::from_str(
+            r#"[{"sourceDomain": ["1", "1000-1999"], "destinationAddress": "0x9d44*"}]"#,
+        )
+        .unwrap();
+        let elem = &list.0.as_ref().unwrap()[0];
+        assert_eq!(elem.src_domain, Ranges(vec![1..=1, 1000..=1999]));
+
+        let recipient: H256 = "0x9d4454B023096f34B160D6B654540c56A1F81688"
+            .parse::<H160>()
+            .unwrap()
+            .into();
+        let zero = H256::zero();
+        let info = |src_domain, dst_addr| MatchInfo {
+            src_domain,
+            src_addr: &zero,
+            dst_domain: 0,
+            dst_addr,
+            body: &[],
+        };
+        assert!(list.matches(info(1, &recipient), false));
+        assert!(list.matches(info(1999, &recipient), false));
+        assert!(!list.matches(info(2, &recipient), false));
+        assert!(!list.matches(info(1, &zero), false));
+
+        assert!(serde_json::from_str::<MatchingList>(r#"[{"sourceDomain": "5-1"}]"#).is_err());
+        assert!(serde_json::from_str::<MatchingList>(r#"[{"sourceAddress": "0xzz*"}]"#).is_err());
+    }
+
+    #[test]
+    fn config_with_body_and_negation() {
+        let list: MatchingList = serde_json::from_str(
+            r#"[{"bodySelector": ["0xa9059cbb", "0x095ea7b3"], "maxBodyLength": 8}, {"bodyPrefix": "0xff", "negate": true}]"#,
+        )
+        .unwrap();
+        let zero = H256::zero();
+        let info = |body: &'static [u8]| MatchInfo {
+            src_domain: 0,
+            src_addr: &zero,
+            dst_domain: 0,
+            dst_addr: &zero,
+            body,
+        };
+        // The first element matches selected bodies that are short enough.
+        assert!(list.matches(info(&[0xa9, 0x05, 0x9c, 0xbb, 0xff]), false));
+        assert!(!list.matches(info(&[0xff, 0x05, 0x9c, 0xbb, 0, 0, 0, 0, 0]), false));
+        // The negated second element matches bodies not starting with 0xff.
+        assert!(list.matches(info(&[0x01]), false));
+        assert!(!list.matches(info(&[0xff]), false));
+
+        assert!(serde_json::from_str::<MatchingList>(r#"[{"bodySelector": "0xa9059c"}]"#).is_err());
+    }
 }
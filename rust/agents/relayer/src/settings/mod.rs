@@ -1,20 +1,227 @@
 //! Configuration
 
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
 use abacus_base::decl_settings;
 
 pub mod matching_list;
 
+/// A policy that determines whether a message has received enough interchain
+/// gas payment to be relayed. Integer and decimal values are specified as
+/// strings so that they can be set via env vars.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GasPaymentEnforcementPolicy {
+    /// No requirement - all messages are relayed regardless of gas payment.
+    None,
+    /// Messages that have received at least `payment` origin chain wei are
+    /// relayed.
+    Minimum { payment: String },
+    /// Messages whose payment covers the estimated cost of processing them on
+    /// the destination, scaled by `multiplier`, are relayed. The estimate is
+    /// converted into origin chain wei with the route's rate in
+    /// `exchangerates`, which every route with this policy must have.
+    MeetsEstimatedCost { multiplier: String },
+}
+
+impl Default for GasPaymentEnforcementPolicy {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// Configuration of the gas payment enforcement policies applied by the
+/// relayer.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasPaymentEnforcementConf {
+    /// The policy used for destinations without an entry in `destinations`.
+    #[serde(default)]
+    pub policy: GasPaymentEnforcementPolicy,
+    /// Policies for specific destinations, keyed by destination chain name.
+    #[serde(default)]
+    pub destinations: HashMap<String, GasPaymentEnforcementPolicy>,
+}
+
+impl GasPaymentEnforcementConf {
+    /// Get the policy applied to messages sent to the destination.
+    pub fn policy_for(&self, destination: &str) -> &GasPaymentEnforcementPolicy {
+        self.destinations.get(destination).unwrap_or(&self.policy)
+    }
+}
+
+/// The strategy used to submit messages to a destination, unless Gelato is
+/// enabled for it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SubmissionStrategy {
+    /// One process transaction is in flight at a time.
+    Serial,
+    /// Up to `max_in_flight` process transactions are in flight at a time,
+    /// with nonces managed by the relayer. Transactions that have not been
+    /// mined after `stuck_tx_timeout` seconds are replaced with a higher gas
+    /// price. Messages from all origins share the in-flight limit.
+    #[serde(rename_all = "camelCase")]
+    Concurrent {
+        max_in_flight: String,
+        #[serde(default)]
+        stuck_tx_timeout: Option<String>,
+    },
+    /// One process transaction is in flight at a time, processing up to
+    /// `max_batch_size` messages through a Multicall3 contract. If not
+    /// provided, `multicall_address` is the address Multicall3 is deployed at
+    /// on most chains.
+    #[serde(rename_all = "camelCase")]
+    Batching {
+        max_batch_size: String,
+        #[serde(default)]
+        multicall_address: Option<String>,
+    },
+}
+
+impl Default for SubmissionStrategy {
+    fn default() -> Self {
+        Self::Serial
+    }
+}
+
+/// Configuration of the strategies used to submit messages.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionStrategyConf {
+    /// The strategy used for destinations without an entry in `destinations`.
+    #[serde(default)]
+    pub strategy: SubmissionStrategy,
+    /// Strategies for specific destinations, keyed by destination chain name.
+    #[serde(default)]
+    pub destinations: HashMap<String, SubmissionStrategy>,
+}
+
+impl SubmissionStrategyConf {
+    /// Get the strategy used to submit messages to the destination.
+    pub fn strategy_for(&self, destination: &str) -> &SubmissionStrategy {
+        self.destinations.get(destination).unwrap_or(&self.strategy)
+    }
+}
+
+/// Gas limits of process transactions delivering messages to a destination.
+/// Gas amounts are specified as strings so that they can be set via env vars.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessGasLimitConf {
+    /// The most gas a process transaction may be given. Messages estimated to
+    /// need more are not processed. If not provided, gas limits are unbounded.
+    #[serde(default)]
+    pub max: Option<String>,
+    /// Gas limits used instead of estimates for messages to specific
+    /// recipients, keyed by recipient address.
+    #[serde(default)]
+    pub recipients: HashMap<String, String>,
+}
+
+/// A token bucket rate limit. Values are specified as strings so that they can
+/// be set via env vars.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    /// The number of messages per minute the limit allows on average.
+    pub per_minute: String,
+    /// The number of messages that may be submitted in a burst. Defaults to
+    /// `per_minute`.
+    #[serde(default)]
+    pub burst: Option<String>,
+}
+
+/// Configuration of how each submitter shares its destination between
+/// applications. Limits apply separately to each destination.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulingConf {
+    /// The rate limit of messages from each sender. If not provided, senders
+    /// are not limited.
+    #[serde(default)]
+    pub sender_limit: Option<RateLimit>,
+    /// The rate limit of messages to each recipient. If not provided,
+    /// recipients are not limited.
+    #[serde(default)]
+    pub recipient_limit: Option<RateLimit>,
+    /// The weights of senders when sharing submission fairly between them,
+    /// keyed by sender address. Senders without an entry have a weight of 1.
+    #[serde(default)]
+    pub sender_weights: HashMap<String, String>,
+}
+
+/// Configuration of the priority tiers that messages paying a premium over
+/// the estimated cost of processing them are submitted in.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriorityConf {
+    /// The premiums, as decimal multipliers of the estimated cost of processing
+    /// a message, that its gas payment must cover to earn each priority tier,
+    /// e.g. `["1.5", "3"]`. Messages in higher tiers are submitted first.
+    /// Messages on routes without a rate in `exchangerates` are not
+    /// prioritized.
+    #[serde(default)]
+    pub premiums: Vec<String>,
+}
+
 decl_settings!(Relayer {
     /// The polling interval to check for new signed checkpoints in seconds
     signedcheckpointpollinginterval: String,
-    /// The maximum number of times a relayer will try to process a message
+    /// The maximum number of times a relayer will retry processing a message that reverts
+    /// before giving up on it. Empty for no limit.
     maxprocessingretries: String,
     /// The multisig checkpoint syncer configuration
     multisigcheckpointsyncer: abacus_base::MultisigCheckpointSyncerConf,
+    /// The multisig checkpoint syncer configurations of the origins in `origins`, keyed by
+    /// outbox chain name. Required for each of those origins.
+    #[serde(default)]
+    origincheckpointsyncers: HashMap<String, abacus_base::MultisigCheckpointSyncerConf>,
     /// This is optional. If no whitelist is provided ALL messages will be considered on the
     /// whitelist.
     whitelist: Option<String>,
     /// This is optional. If no blacklist is provided ALL will be considered to not be on
     /// the blacklist.
     blacklist: Option<String>,
+    /// This is optional. A file containing `{"whitelist": ..., "blacklist": ...}`, which replaces
+    /// `whitelist` and `blacklist` and is reloaded whenever it changes.
+    relaylistsfile: Option<String>,
+    /// This is optional. The gas payment enforcement policies. If not provided, messages are
+    /// relayed regardless of gas payment.
+    #[serde(default)]
+    gaspaymentenforcement: GasPaymentEnforcementConf,
+    /// This is optional. The localhost port on which to serve the admin API for inspecting
+    /// queues and messages and controlling submission. If not provided, the admin API is not
+    /// served.
+    adminport: Option<String>,
+    /// This is optional. The bearer token required by the admin API's POST endpoints. If not
+    /// provided, the POST endpoints are disabled.
+    admintoken: Option<String>,
+    /// This is optional. The strategies used to submit messages. If not provided, messages are
+    /// submitted serially.
+    #[serde(default)]
+    submissionstrategy: SubmissionStrategyConf,
+    /// This is optional. Gas limits of process transactions, keyed by destination chain name.
+    /// If not provided for a destination, gas limits are learned from estimates and the gas
+    /// used by earlier messages to each recipient, and are unbounded.
+    #[serde(default)]
+    processgaslimits: HashMap<String, ProcessGasLimitConf>,
+    /// This is optional. Rate limits per sender and recipient and the weights of senders in
+    /// fair scheduling. If not provided, messages are not rate limited and senders are weighted
+    /// equally.
+    #[serde(default)]
+    scheduling: SchedulingConf,
+    /// This is optional. The priority tiers messages can pay for. If not provided, messages
+    /// are not prioritized by their gas payment.
+    #[serde(default)]
+    priority: PriorityConf,
+    /// This is optional. The prices of destination native tokens in origin native tokens, as
+    /// decimals keyed by outbox and then inbox chain name, e.g. `{"ethereum": {"polygon":
+    /// "0.0005"}}`. Relay costs are paid in the destination's native token and gas payments in
+    /// the origin's, so the net margin of a route is only reported, and gas payments are only
+    /// compared to estimated costs, if it has a rate.
+    #[serde(default)]
+    exchangerates: HashMap<String, HashMap<String, String>>,
 });
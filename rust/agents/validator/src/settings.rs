@@ -0,0 +1,15 @@
+//! Configuration
+
+use abacus_base::decl_settings;
+
+decl_settings!(Validator {
+    /// The signer of the checkpoints this validator attests to
+    validator: abacus_base::SignerConf,
+    /// The checkpoint syncer the signed checkpoints are written to
+    checkpointsyncer: abacus_base::CheckpointSyncerConf,
+    /// The number of blocks the Outbox's latest checkpoint is read behind the chain tip, so
+    /// that checkpoints which may be re-orged out are not signed
+    reorgperiod: String,
+    /// The polling interval to check for new checkpoints in seconds
+    interval: String,
+});
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Result;
+use prometheus::IntGauge;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{debug, info, info_span, instrument, instrument::Instrumented, Instrument};
+
+use abacus_base::{CachingOutbox, CheckpointSyncer, CheckpointSyncers, CoreMetrics};
+use abacus_core::{AbacusContract, Outbox, Signers};
+
+/// Signs the Outbox's latest checkpoint whenever it has advanced and writes it to the
+/// checkpoint syncer.
+pub(crate) struct ValidatorSubmitter {
+    interval: u64,
+    reorg_period: u64,
+    outbox: Arc<CachingOutbox>,
+    signer: Arc<Signers>,
+    checkpoint_syncer: Arc<CheckpointSyncers>,
+    metrics: ValidatorSubmitterMetrics,
+}
+
+impl ValidatorSubmitter {
+    pub(crate) fn new(
+        interval: u64,
+        reorg_period: u64,
+        outbox: Arc<CachingOutbox>,
+        signer: Arc<Signers>,
+        checkpoint_syncer: Arc<CheckpointSyncers>,
+        metrics: ValidatorSubmitterMetrics,
+    ) -> Self {
+        Self {
+            interval,
+            reorg_period,
+            outbox,
+            signer,
+            checkpoint_syncer,
+            metrics,
+        }
+    }
+
+    #[instrument(err, skip(self), fields(outbox = self.outbox.chain_name()))]
+    async fn main_loop(self) -> Result<()> {
+        // The Outbox's latest checkpoint is the index of its last message, so does not exist
+        // until a message has been dispatched.
+        while self.outbox.count().await? == 0 {
+            info!("Waiting for the first message to be dispatched to the outbox");
+            sleep(Duration::from_secs(self.interval)).await;
+        }
+
+        // Resume from the checkpoint written before a restart, if any.
+        let mut latest_signed_index = self.checkpoint_syncer.latest_index().await?;
+        info!(latest_signed_index = ?latest_signed_index, "Starting ValidatorSubmitter");
+
+        let lag = Some(self.reorg_period).filter(|period| *period > 0);
+        loop {
+            let checkpoint = self.outbox.latest_checkpoint(lag).await?;
+            self.metrics
+                .latest_checkpoint_observed
+                .set(checkpoint.index as i64);
+
+            if latest_signed_index.map_or(true, |index| index < checkpoint.index) {
+                let signed_checkpoint = checkpoint.sign_with(self.signer.as_ref()).await?;
+                info!(
+                    index = signed_checkpoint.checkpoint.index,
+                    root = ?signed_checkpoint.checkpoint.root,
+                    "Signed latest checkpoint"
+                );
+                self.checkpoint_syncer
+                    .write_checkpoint(signed_checkpoint)
+                    .await?;
+                latest_signed_index = Some(checkpoint.index);
+                self.metrics
+                    .latest_checkpoint_processed
+                    .set(checkpoint.index as i64);
+            } else {
+                debug!(
+                    index = checkpoint.index,
+                    "Latest checkpoint is already signed, continuing"
+                );
+            }
+
+            sleep(Duration::from_secs(self.interval)).await;
+        }
+    }
+
+    pub(crate) fn spawn(self) -> Instrumented<JoinHandle<Result<()>>> {
+        let span = info_span!("ValidatorSubmitter");
+        tokio::spawn(self.main_loop()).instrument(span)
+    }
+}
+
+pub(crate) struct ValidatorSubmitterMetrics {
+    latest_checkpoint_observed: IntGauge,
+    latest_checkpoint_processed: IntGauge,
+}
+
+impl ValidatorSubmitterMetrics {
+    pub fn new(metrics: &CoreMetrics, outbox_chain: &str) -> Self {
+        Self {
+            // Checkpoints are not remote-specific
+            latest_checkpoint_observed: metrics
+                .last_known_message_leaf_index()
+                .with_label_values(&["validator_observed", outbox_chain, "unknown"]),
+            latest_checkpoint_processed: metrics
+                .last_known_message_leaf_index()
+                .with_label_values(&["validator_processed", outbox_chain, "unknown"]),
+        }
+    }
+}
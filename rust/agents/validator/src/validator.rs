@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::{eyre, Result};
+use tokio::task::JoinHandle;
+use tracing::instrument::Instrumented;
+
+use abacus_base::{AbacusAgentCore, Agent, CheckpointSyncers};
+use abacus_core::{AbacusContract, Signers};
+
+use crate::settings::ValidatorSettings;
+use crate::submit::{ValidatorSubmitter, ValidatorSubmitterMetrics};
+
+/// A validator agent
+#[derive(Debug)]
+pub struct Validator {
+    signer: Arc<Signers>,
+    reorg_period: u64,
+    interval: u64,
+    checkpoint_syncer: Arc<CheckpointSyncers>,
+    core: AbacusAgentCore,
+}
+
+impl AsRef<AbacusAgentCore> for Validator {
+    fn as_ref(&self) -> &AbacusAgentCore {
+        &self.core
+    }
+}
+
+#[async_trait]
+#[allow(clippy::unit_arg)]
+impl Agent for Validator {
+    const AGENT_NAME: &'static str = "validator";
+
+    type Settings = ValidatorSettings;
+
+    async fn from_settings(settings: Self::Settings) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let signer = settings.validator.try_into_signer().await?;
+        let reorg_period = settings
+            .reorgperiod
+            .parse()
+            .map_err(|e| eyre!("Invalid reorgperiod {}: {e}", settings.reorgperiod))?;
+        let interval = settings
+            .interval
+            .parse()
+            .map_err(|e| eyre!("Invalid interval {}: {e}", settings.interval))?;
+        let checkpoint_syncer = settings.checkpointsyncer.try_into_checkpoint_syncer()?;
+
+        Ok(Self {
+            signer: Arc::new(signer),
+            reorg_period,
+            interval,
+            checkpoint_syncer: Arc::new(checkpoint_syncer),
+            core: settings
+                .as_ref()
+                .try_into_abacus_core(Self::AGENT_NAME, false)
+                .await?,
+        })
+    }
+}
+
+impl Validator {
+    pub fn run(&self) -> Instrumented<JoinHandle<Result<()>>> {
+        let submitter = ValidatorSubmitter::new(
+            self.interval,
+            self.reorg_period,
+            self.outbox(),
+            self.signer.clone(),
+            self.checkpoint_syncer.clone(),
+            ValidatorSubmitterMetrics::new(&self.core.metrics, self.outbox().chain_name()),
+        );
+
+        self.run_all(vec![submitter.spawn()])
+    }
+}
@@ -123,6 +123,28 @@ where
         let status = self.contract.messages(leaf.into()).call().await?;
         Ok(MessageStatus::try_from(status).expect("Bad status from solidity"))
     }
+
+    #[tracing::instrument(err)]
+    async fn finalized_message_status(
+        &self,
+        leaf: H256,
+        finality_blocks: u32,
+    ) -> Result<MessageStatus, ChainCommunicationError> {
+        let finalized_block = self
+            .contract
+            .client()
+            .get_block_number()
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn StdError + Send + Sync>)?
+            .saturating_sub(finality_blocks.into());
+        let status = self
+            .contract
+            .messages(leaf.into())
+            .block(finalized_block)
+            .call()
+            .await?;
+        Ok(MessageStatus::try_from(status).expect("Bad status from solidity"))
+    }
 }
 
 pub struct EthereumInboxAbi;
@@ -10,15 +10,16 @@ use eyre::Result;
 use tracing::instrument;
 
 use abacus_core::{
-    AbacusAbi, AbacusContract, ContractLocator, Indexer, InterchainGasPaymaster,
-    InterchainGasPaymasterIndexer, InterchainGasPayment, InterchainGasPaymentMeta,
-    InterchainGasPaymentWithMeta,
+    AbacusAbi, AbacusContract, ChainCommunicationError, ContractLocator, Indexer,
+    InterchainGasPaymaster, InterchainGasPaymasterIndexer, InterchainGasPayment,
+    InterchainGasPaymentMeta, InterchainGasPaymentWithMeta, TxOutcome,
 };
 
 use crate::contracts::interchain_gas_paymaster::{
     InterchainGasPaymaster as EthereumInterchainGasPaymasterInternal, INTERCHAINGASPAYMASTER_ABI,
 };
 use crate::trait_builder::MakeableWithProvider;
+use crate::tx::{report_tx, GasEscalator, TransactionOverrides};
 
 impl<M> Display for EthereumInterchainGasPaymasterInternal<M>
 where
@@ -150,7 +151,10 @@ where
     }
 }
 
-pub struct InterchainGasPaymasterBuilder {}
+pub struct InterchainGasPaymasterBuilder {
+    pub gas_escalator: Option<GasEscalator>,
+    pub transaction_overrides: TransactionOverrides,
+}
 
 impl MakeableWithProvider for InterchainGasPaymasterBuilder {
     type Output = Box<dyn InterchainGasPaymaster>;
@@ -163,6 +167,8 @@ impl MakeableWithProvider for InterchainGasPaymasterBuilder {
         Box::new(EthereumInterchainGasPaymaster::new(
             Arc::new(provider),
             locator,
+            self.gas_escalator,
+            self.transaction_overrides,
         ))
     }
 }
@@ -173,13 +179,13 @@ pub struct EthereumInterchainGasPaymaster<M>
 where
     M: Middleware,
 {
-    #[allow(dead_code)]
     contract: Arc<EthereumInterchainGasPaymasterInternal<M>>,
     chain_name: String,
     #[allow(dead_code)]
     domain: u32,
-    #[allow(dead_code)]
     provider: Arc<M>,
+    gas_escalator: Option<GasEscalator>,
+    transaction_overrides: TransactionOverrides,
 }
 
 impl<M> EthereumInterchainGasPaymaster<M>
@@ -188,7 +194,12 @@ where
 {
     /// Create a reference to a outbox at a specific Ethereum address on some
     /// chain
-    pub fn new(provider: Arc<M>, locator: &ContractLocator) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        locator: &ContractLocator,
+        gas_escalator: Option<GasEscalator>,
+        transaction_overrides: TransactionOverrides,
+    ) -> Self {
         Self {
             contract: Arc::new(EthereumInterchainGasPaymasterInternal::new(
                 &locator.address,
@@ -197,6 +208,8 @@ where
             domain: locator.domain,
             chain_name: locator.chain_name.to_owned(),
             provider,
+            gas_escalator,
+            transaction_overrides,
         }
     }
 }
@@ -211,7 +224,31 @@ where
 }
 
 #[async_trait]
-impl<M> InterchainGasPaymaster for EthereumInterchainGasPaymaster<M> where M: Middleware + 'static {}
+impl<M> InterchainGasPaymaster for EthereumInterchainGasPaymaster<M>
+where
+    M: Middleware + 'static,
+{
+    #[tracing::instrument(err, skip(self))]
+    async fn pay_gas_for(
+        &self,
+        outbox: H256,
+        leaf_index: u32,
+        destination_domain: u32,
+        amount: U256,
+    ) -> Result<TxOutcome, ChainCommunicationError> {
+        let mut tx = self
+            .contract
+            .pay_gas_for(outbox.into(), leaf_index.into(), destination_domain)
+            .value(amount);
+        self.transaction_overrides
+            .apply(&mut tx, &self.provider)
+            .await?;
+
+        Ok(report_tx(tx, &self.provider, self.gas_escalator.as_ref())
+            .await?
+            .into())
+    }
+}
 
 pub struct EthereumInterchainGasPaymasterAbi;
 
@@ -11,6 +11,10 @@ use std::collections::HashMap;
 
 use abacus_core::*;
 pub use retrying::{RetryingProvider, RetryingProviderError};
+#[cfg(not(doctest))]
+pub use tx::{
+    GasEscalator, GasEscalatorConf, TransactionOverrides, TransactionOverridesConf, TxType,
+};
 
 use crate::abi::FunctionExt;
 #[cfg(not(doctest))]
@@ -98,3 +102,20 @@ fn extract_fn_map(abi: &'static Lazy<abi::Abi>) -> HashMap<Selector, &'static st
         .map(|f| (f.selector(), f.name.as_str()))
         .collect()
 }
+
+/// Decodes the reason from the return data of a reverted call to contracts with
+/// the given ABIs, which may be a custom error of one of the contracts.
+fn decode_revert_data(abis: &[&'static Lazy<abi::Abi>], data: &[u8]) -> Option<String> {
+    if let Some(reason) = abacus_core::utils::decode_revert_reason(data) {
+        return Some(reason);
+    }
+    let (selector, args) = (data.get(..4)?, data.get(4..)?);
+    abis.iter()
+        .flat_map(|abi| abi.errors())
+        .find(|error| &error.signature().as_bytes()[..4] == selector)
+        .and_then(|error| {
+            let tokens = error.decode(args).ok()?;
+            let args: Vec<_> = tokens.iter().map(ToString::to_string).collect();
+            Some(format!("{}({})", error.name, args.join(", ")))
+        })
+}
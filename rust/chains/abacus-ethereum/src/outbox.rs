@@ -5,19 +5,21 @@ use std::collections::HashMap;
 use std::{error::Error as StdError, sync::Arc};
 
 use async_trait::async_trait;
+use ethers::abi::RawLog;
+use ethers::contract::EthEvent;
 use ethers::prelude::*;
 use eyre::Result;
 use tracing::instrument;
 
 use abacus_core::{
     AbacusAbi, AbacusCommon, AbacusContract, ChainCommunicationError, Checkpoint, CheckpointMeta,
-    CheckpointWithMeta, ContractLocator, Indexer, Message, Outbox, OutboxIndexer, OutboxState,
-    RawCommittedMessage, TxOutcome,
+    CheckpointWithMeta, ContractLocator, DispatchOutcome, Indexer, Message, Outbox, OutboxIndexer,
+    OutboxState, RawCommittedMessage, TxOutcome,
 };
 
-use crate::contracts::outbox::{Outbox as EthereumOutboxInternal, OUTBOX_ABI};
+use crate::contracts::outbox::{DispatchFilter, Outbox as EthereumOutboxInternal, OUTBOX_ABI};
 use crate::trait_builder::MakeableWithProvider;
-use crate::tx::report_tx;
+use crate::tx::{report_tx, GasEscalator, TransactionOverrides};
 
 impl<M> std::fmt::Display for EthereumOutboxInternal<M>
 where
@@ -179,7 +181,10 @@ where
     }
 }
 
-pub struct OutboxBuilder {}
+pub struct OutboxBuilder {
+    pub gas_escalator: Option<GasEscalator>,
+    pub transaction_overrides: TransactionOverrides,
+}
 
 impl MakeableWithProvider for OutboxBuilder {
     type Output = Box<dyn Outbox>;
@@ -189,7 +194,12 @@ impl MakeableWithProvider for OutboxBuilder {
         provider: M,
         locator: &ContractLocator,
     ) -> Self::Output {
-        Box::new(EthereumOutbox::new(Arc::new(provider), locator))
+        Box::new(EthereumOutbox::new(
+            Arc::new(provider),
+            locator,
+            self.gas_escalator,
+            self.transaction_overrides,
+        ))
     }
 }
 
@@ -203,6 +213,8 @@ where
     domain: u32,
     chain_name: String,
     provider: Arc<M>,
+    gas_escalator: Option<GasEscalator>,
+    transaction_overrides: TransactionOverrides,
 }
 
 impl<M> EthereumOutbox<M>
@@ -211,7 +223,12 @@ where
 {
     /// Create a reference to a outbox at a specific Ethereum address on some
     /// chain
-    pub fn new(provider: Arc<M>, locator: &ContractLocator) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        locator: &ContractLocator,
+        gas_escalator: Option<GasEscalator>,
+        transaction_overrides: TransactionOverrides,
+    ) -> Self {
         Self {
             contract: Arc::new(EthereumOutboxInternal::new(
                 &locator.address,
@@ -220,6 +237,8 @@ where
             domain: locator.domain,
             chain_name: locator.chain_name.to_owned(),
             provider,
+            gas_escalator,
+            transaction_overrides,
         }
     }
 }
@@ -266,14 +285,36 @@ where
     M: Middleware + 'static,
 {
     #[tracing::instrument(err, skip(self))]
-    async fn dispatch(&self, message: &Message) -> Result<TxOutcome, ChainCommunicationError> {
-        let tx = self.contract.dispatch(
+    async fn dispatch(
+        &self,
+        message: &Message,
+    ) -> Result<DispatchOutcome, ChainCommunicationError> {
+        let mut tx = self.contract.dispatch(
             message.destination,
             message.recipient.to_fixed_bytes(),
             message.body.clone().into(),
         );
+        self.transaction_overrides
+            .apply(&mut tx, &self.provider)
+            .await?;
 
-        Ok(report_tx(tx).await?.into())
+        let receipt = report_tx(tx, &self.provider, self.gas_escalator.as_ref()).await?;
+        let leaf_index = receipt
+            .logs
+            .iter()
+            .filter(|log| log.address == self.contract.address())
+            .find_map(|log| {
+                <DispatchFilter as EthEvent>::decode_log(&RawLog {
+                    topics: log.topics.clone(),
+                    data: log.data.to_vec(),
+                })
+                .ok()
+            })
+            .map(|event| event.leaf_index.as_u32());
+        Ok(DispatchOutcome {
+            outcome: receipt.into(),
+            leaf_index,
+        })
     }
 
     #[tracing::instrument(err, skip(self))]
@@ -289,9 +330,14 @@ where
 
     #[tracing::instrument(err, skip(self))]
     async fn cache_checkpoint(&self) -> Result<TxOutcome, ChainCommunicationError> {
-        let tx = self.contract.cache_checkpoint();
+        let mut tx = self.contract.cache_checkpoint();
+        self.transaction_overrides
+            .apply(&mut tx, &self.provider)
+            .await?;
 
-        Ok(report_tx(tx).await?.into())
+        Ok(report_tx(tx, &self.provider, self.gas_escalator.as_ref())
+            .await?
+            .into())
     }
 
     #[tracing::instrument(err, skip(self))]
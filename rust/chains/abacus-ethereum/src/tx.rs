@@ -2,16 +2,331 @@ use std::time::Duration;
 
 use ethers::abi::Detokenize;
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers_contract::builders::ContractCall;
-use tracing::{error, info};
+use eyre::{bail, eyre};
+use serde::Deserialize;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
 
 use abacus_core::ChainCommunicationError;
 
 use crate::Middleware;
 
-/// Dispatches a transaction, logs the tx id, and returns the result
+/// How long to wait for a transaction to be mined.
+const TX_TIMEOUT: Duration = Duration::from_secs(300);
+/// Fee multipliers are applied with a precision of basis points.
+const MULTIPLIER_PRECISION: u64 = 10_000;
+
+/// Configuration for replacing transactions that have not been mined with ones
+/// paying higher fees. Values are specified as strings so that they can be set
+/// via env vars.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GasEscalatorConf {
+    /// Seconds to wait for a transaction to be mined before replacing it
+    pub interval: String,
+    /// The factor fees are multiplied by with each replacement, e.g. "1.125".
+    /// Nodes typically reject replacements that raise fees by less than 10%.
+    pub multiplier: String,
+    /// The ceiling on the gas price, or on the max fee per gas of EIP-1559
+    /// transactions, in wei
+    pub max_gas_price: String,
+}
+
+/// A parsed `GasEscalatorConf`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GasEscalator {
+    /// How long to wait for a transaction to be mined before replacing it
+    pub interval: Duration,
+    /// The factor fees are multiplied by with each replacement, in basis points
+    pub multiplier_bps: u64,
+    /// The ceiling on the gas price or max fee per gas, in wei
+    pub max_gas_price: U256,
+}
+
+impl TryFrom<&GasEscalatorConf> for GasEscalator {
+    type Error = eyre::Report;
+
+    fn try_from(conf: &GasEscalatorConf) -> eyre::Result<Self> {
+        let interval: u64 = conf
+            .interval
+            .parse()
+            .map_err(|e| eyre!("Invalid gas escalator interval {}: {e}", conf.interval))?;
+        let multiplier_bps = parse_multiplier_bps("gas escalator multiplier", &conf.multiplier)?;
+        if multiplier_bps <= MULTIPLIER_PRECISION {
+            bail!(
+                "Gas escalator multiplier must be greater than 1, got {}",
+                conf.multiplier
+            );
+        }
+        Ok(Self {
+            interval: Duration::from_secs(interval),
+            multiplier_bps,
+            max_gas_price: parse_wei("gas escalator max gas price", &conf.max_gas_price)?,
+        })
+    }
+}
+
+impl GasEscalator {
+    fn escalate_price(&self, price: U256) -> U256 {
+        (price.saturating_mul(self.multiplier_bps.into()) / MULTIPLIER_PRECISION)
+            .min(self.max_gas_price)
+    }
+
+    /// Raise the fees of a transaction for a replacement, returning false if
+    /// they are already at the ceiling
+    fn escalate(&self, tx: &mut TypedTransaction) -> bool {
+        match tx {
+            TypedTransaction::Eip1559(inner) => {
+                let max_fee = inner.max_fee_per_gas.unwrap_or_default();
+                if max_fee >= self.max_gas_price {
+                    return false;
+                }
+                let max_fee = self.escalate_price(max_fee);
+                inner.max_fee_per_gas = Some(max_fee);
+                inner.max_priority_fee_per_gas = inner
+                    .max_priority_fee_per_gas
+                    .map(|fee| self.escalate_price(fee).min(max_fee));
+                true
+            }
+            _ => {
+                let gas_price = tx.gas_price().unwrap_or_default();
+                if gas_price >= self.max_gas_price {
+                    return false;
+                }
+                tx.set_gas_price(self.escalate_price(gas_price));
+                true
+            }
+        }
+    }
+}
+
+/// The type of transaction sent by contract writes
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TxType {
+    /// Transactions with a single gas price
+    Legacy,
+    /// EIP-1559 transactions with a max fee and priority fee per gas
+    Eip1559,
+}
+
+impl Default for TxType {
+    fn default() -> Self {
+        Self::Legacy
+    }
+}
+
+/// Per-chain overrides of how contract write transactions are built, e.g.
+/// chains like BSC that do not support EIP-1559 or chains whose fee
+/// estimates need capping. Values are specified as strings so that they can
+/// be set via env vars.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionOverridesConf {
+    /// The type of transaction to send. Defaults to legacy.
+    #[serde(default)]
+    pub tx_type: TxType,
+    /// A factor applied to gas limit estimates, e.g. "1.2"
+    #[serde(default)]
+    pub gas_limit_multiplier: Option<String>,
+    /// A fixed priority fee per gas, in wei, used instead of the estimate.
+    /// EIP-1559 only.
+    #[serde(default)]
+    pub priority_fee: Option<String>,
+    /// The ceiling on the estimated priority fee per gas, in wei. EIP-1559
+    /// only.
+    #[serde(default)]
+    pub max_priority_fee: Option<String>,
+    /// The ceiling on the gas price, or on the max fee per gas of EIP-1559
+    /// transactions, in wei
+    #[serde(default)]
+    pub max_fee: Option<String>,
+}
+
+/// A parsed `TransactionOverridesConf`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TransactionOverrides {
+    /// The type of transaction to send
+    pub tx_type: TxType,
+    /// A factor applied to gas limit estimates, in basis points
+    pub gas_limit_multiplier_bps: Option<u64>,
+    /// A fixed priority fee per gas
+    pub priority_fee: Option<U256>,
+    /// The ceiling on the estimated priority fee per gas
+    pub max_priority_fee: Option<U256>,
+    /// The ceiling on the gas price or max fee per gas
+    pub max_fee: Option<U256>,
+}
+
+impl TryFrom<&TransactionOverridesConf> for TransactionOverrides {
+    type Error = eyre::Report;
+
+    fn try_from(conf: &TransactionOverridesConf) -> eyre::Result<Self> {
+        let gas_limit_multiplier_bps = conf
+            .gas_limit_multiplier
+            .as_deref()
+            .map(|multiplier| parse_multiplier_bps("gas limit multiplier", multiplier))
+            .transpose()?;
+        if gas_limit_multiplier_bps == Some(0) {
+            bail!("Gas limit multiplier must be greater than 0");
+        }
+        let parse_fee =
+            |name, fee: &Option<String>| fee.as_deref().map(|fee| parse_wei(name, fee)).transpose();
+        Ok(Self {
+            tx_type: conf.tx_type,
+            gas_limit_multiplier_bps,
+            priority_fee: parse_fee("priority fee", &conf.priority_fee)?,
+            max_priority_fee: parse_fee("max priority fee", &conf.max_priority_fee)?,
+            max_fee: parse_fee("max fee", &conf.max_fee)?,
+        })
+    }
+}
+
+impl TransactionOverrides {
+    /// The gas limit to use given an estimate
+    pub(crate) fn gas_limit(&self, estimate: U256) -> U256 {
+        match self.gas_limit_multiplier_bps {
+            Some(bps) => estimate.saturating_mul(bps.into()) / MULTIPLIER_PRECISION,
+            None => estimate,
+        }
+    }
+
+    /// A gas price, or max fee per gas, subject to the max fee ceiling
+    pub(crate) fn cap_gas_price(&self, gas_price: U256) -> U256 {
+        match self.max_fee {
+            Some(max_fee) => gas_price.min(max_fee),
+            None => gas_price,
+        }
+    }
+
+    /// Apply the overrides to a contract call whose gas limit and fees have
+    /// not been set
+    pub(crate) async fn apply<M, D>(
+        &self,
+        call: &mut ContractCall<M, D>,
+        provider: &M,
+    ) -> Result<(), ChainCommunicationError>
+    where
+        M: Middleware + 'static,
+        D: Detokenize,
+    {
+        if self.gas_limit_multiplier_bps.is_some() {
+            let gas = self.gas_limit(call.estimate_gas().await?);
+            call.tx.set_gas(gas);
+        }
+        self.apply_fees(&mut call.tx, provider, None).await
+    }
+
+    /// Set the fees of a transaction according to the overrides, converting it
+    /// to the configured transaction type. `gas_price`, if provided, is used
+    /// as the gas price or max fee per gas instead of the network's estimate.
+    pub(crate) async fn apply_fees<M>(
+        &self,
+        tx: &mut TypedTransaction,
+        provider: &M,
+        gas_price: Option<U256>,
+    ) -> Result<(), ChainCommunicationError>
+    where
+        M: Middleware + 'static,
+    {
+        match self.tx_type {
+            TxType::Legacy => {
+                let gas_price = match gas_price {
+                    Some(gas_price) => gas_price,
+                    None => provider
+                        .get_gas_price()
+                        .await
+                        .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?,
+                };
+                self.set_legacy_fees(tx, gas_price);
+            }
+            TxType::Eip1559 => {
+                let (estimated_max_fee, estimated_priority_fee) = provider
+                    .estimate_eip1559_fees(None)
+                    .await
+                    .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+                self.set_eip1559_fees(
+                    tx,
+                    gas_price.unwrap_or(estimated_max_fee),
+                    estimated_priority_fee,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Make a transaction a legacy transaction paying the gas price, subject to
+    /// the max fee ceiling
+    fn set_legacy_fees(&self, tx: &mut TypedTransaction, gas_price: U256) {
+        let mut request = TransactionRequest::new();
+        request.from = tx.from().copied();
+        request.to = tx.to().cloned();
+        request.gas = tx.gas().copied();
+        request.value = tx.value().copied();
+        request.data = tx.data().cloned();
+        request.nonce = tx.nonce().copied();
+        request.gas_price = Some(self.cap_gas_price(gas_price));
+        *tx = request.into();
+    }
+
+    /// Make a transaction an EIP-1559 transaction paying the max fee and
+    /// priority fee per gas, subject to the overrides
+    fn set_eip1559_fees(&self, tx: &mut TypedTransaction, max_fee: U256, priority_fee: U256) {
+        let max_fee = self.cap_gas_price(max_fee);
+        let mut priority_fee = self.priority_fee.unwrap_or(priority_fee);
+        if let Some(max_priority_fee) = self.max_priority_fee {
+            priority_fee = priority_fee.min(max_priority_fee);
+        }
+
+        let mut request = Eip1559TransactionRequest::new();
+        request.from = tx.from().copied();
+        request.to = tx.to().cloned();
+        request.gas = tx.gas().copied();
+        request.value = tx.value().copied();
+        request.data = tx.data().cloned();
+        request.nonce = tx.nonce().copied();
+        request.max_fee_per_gas = Some(max_fee);
+        request.max_priority_fee_per_gas = Some(priority_fee.min(max_fee));
+        *tx = request.into();
+    }
+}
+
+fn parse_multiplier_bps(name: &str, multiplier: &str) -> eyre::Result<u64> {
+    let parsed: f64 = multiplier
+        .parse()
+        .map_err(|e| eyre!("Invalid {name} {multiplier}: {e}"))?;
+    if !parsed.is_finite() || parsed < 0. {
+        bail!("Invalid {name} {multiplier}");
+    }
+    Ok((parsed * MULTIPLIER_PRECISION as f64).round() as u64)
+}
+
+fn parse_wei(name: &str, amount: &str) -> eyre::Result<U256> {
+    U256::from_dec_str(amount).map_err(|e| eyre!("Invalid {name} {amount}: {e:?}"))
+}
+
+/// Dispatches a transaction, logs the tx id, and returns the result. If an
+/// escalator is provided, the transaction is replaced by ones with the same
+/// nonce and increasing fees for as long as it goes unmined.
 pub(crate) async fn report_tx<M, D>(
     tx: ContractCall<M, D>,
+    provider: &M,
+    escalator: Option<&GasEscalator>,
+) -> Result<TransactionReceipt, ChainCommunicationError>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    match escalator {
+        Some(escalator) => report_escalating_tx(tx, provider, escalator).await,
+        None => report_single_tx(tx).await,
+    }
+}
+
+async fn report_single_tx<M, D>(
+    tx: ContractCall<M, D>,
 ) -> Result<TransactionReceipt, ChainCommunicationError>
 where
     M: Middleware + 'static,
@@ -47,7 +362,7 @@ where
         "Dispatched tx"
     );
 
-    match tokio::time::timeout(Duration::from_secs(300), dispatched).await {
+    match tokio::time::timeout(TX_TIMEOUT, dispatched).await {
         // all good
         Ok(Ok(Some(receipt))) => {
             info!(
@@ -79,3 +394,235 @@ where
         }
     }
 }
+
+/// Dispatches a transaction and, every `escalator.interval` that none of its
+/// broadcasts have been mined, replaces it with one paying higher fees, until
+/// the fee ceiling is reached or TX_TIMEOUT elapses.
+async fn report_escalating_tx<M, D>(
+    mut tx: ContractCall<M, D>,
+    provider: &M,
+    escalator: &GasEscalator,
+) -> Result<TransactionReceipt, ChainCommunicationError>
+where
+    M: Middleware + 'static,
+    D: Detokenize,
+{
+    // Fill in the nonce and fees up front so that every replacement shares
+    // the nonce.
+    provider
+        .fill_transaction(&mut tx.tx, tx.block)
+        .await
+        .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+    let to = tx
+        .tx
+        .to()
+        .cloned()
+        .unwrap_or_else(|| NameOrAddress::Address(Default::default()));
+    let nonce = tx.tx.nonce().cloned();
+
+    let mut tx_hashes: Vec<H256> = vec![*tx.send().await?];
+    info!(to = ?to, ?nonce, tx_hash = ?tx_hashes[0], "Dispatched tx");
+
+    let deadline = Instant::now() + TX_TIMEOUT;
+    let mut next_escalation = Instant::now() + escalator.interval;
+    while Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        for (replacement, tx_hash) in tx_hashes.iter().enumerate() {
+            match provider.get_transaction_receipt(*tx_hash).await {
+                Ok(Some(receipt)) => {
+                    info!(tx_hash = ?tx_hash, ?nonce, replacement, "confirmed transaction");
+                    return Ok(receipt);
+                }
+                Ok(None) => {}
+                // Any of the broadcasts may still be mined, so keep polling rather than
+                // giving up on the transaction.
+                Err(e) => {
+                    warn!(tx_hash = ?tx_hash, ?nonce, error = ?e, "Failed to get transaction receipt")
+                }
+            }
+        }
+
+        if Instant::now() < next_escalation {
+            continue;
+        }
+        next_escalation = Instant::now() + escalator.interval;
+        if !escalator.escalate(&mut tx.tx) {
+            continue;
+        }
+        match tx.send().await {
+            Ok(pending) => {
+                let tx_hash = *pending;
+                info!(
+                    to = ?to,
+                    ?nonce,
+                    tx_hash = ?tx_hash,
+                    replacement = tx_hashes.len(),
+                    gas_price = ?tx.tx.gas_price(),
+                    "Dispatched replacement tx"
+                );
+                tx_hashes.push(tx_hash);
+            }
+            // An earlier broadcast may have been mined since we last checked,
+            // in which case the replacement is rejected.
+            Err(e) => warn!(?nonce, error = ?e, "Failed to dispatch replacement tx"),
+        }
+    }
+
+    error!(?nonce, tx_hashes = ?tx_hashes, "waiting for receipt timed out");
+    Err(ChainCommunicationError::TransactionTimeout())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Eip1559TransactionRequest, TransactionRequest, U256};
+
+    use super::{GasEscalator, TransactionOverrides, TxType};
+
+    fn gwei(amount: u64) -> U256 {
+        U256::from(amount) * 1_000_000_000u64
+    }
+
+    fn legacy_tx() -> TypedTransaction {
+        TransactionRequest::new().gas(100_000).nonce(7).into()
+    }
+
+    fn eip1559_tx() -> TypedTransaction {
+        Eip1559TransactionRequest::new()
+            .gas(100_000)
+            .nonce(7)
+            .into()
+    }
+
+    fn escalator() -> GasEscalator {
+        GasEscalator {
+            interval: Duration::from_secs(60),
+            multiplier_bps: 12_500,
+            max_gas_price: gwei(100),
+        }
+    }
+
+    #[test]
+    fn escalates_legacy_gas_price_up_to_ceiling() {
+        let escalator = escalator();
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(gwei(64)).into();
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(tx.gas_price(), Some(gwei(80)));
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(tx.gas_price(), Some(gwei(100)));
+        assert!(!escalator.escalate(&mut tx));
+        assert_eq!(tx.gas_price(), Some(gwei(100)));
+
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(gwei(90)).into();
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(tx.gas_price(), Some(gwei(100)));
+        assert!(!escalator.escalate(&mut tx));
+    }
+
+    #[test]
+    fn escalates_eip1559_fees_up_to_ceiling() {
+        let escalator = escalator();
+        let fees = |tx: &TypedTransaction| match tx {
+            TypedTransaction::Eip1559(inner) => (
+                inner.max_fee_per_gas.unwrap(),
+                inner.max_priority_fee_per_gas.unwrap(),
+            ),
+            tx => panic!("Expected an EIP-1559 transaction, got {tx:?}"),
+        };
+
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(gwei(64))
+            .max_priority_fee_per_gas(gwei(2))
+            .into();
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(fees(&tx), (gwei(80), gwei(2) * 5 / 4));
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(fees(&tx), (gwei(100), gwei(2) * 25 / 16));
+        assert!(!escalator.escalate(&mut tx));
+        assert_eq!(fees(&tx), (gwei(100), gwei(2) * 25 / 16));
+
+        // The priority fee is raised with the max fee, but never beyond it.
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(gwei(90))
+            .max_priority_fee_per_gas(gwei(90))
+            .into();
+        assert!(escalator.escalate(&mut tx));
+        assert_eq!(fees(&tx), (gwei(100), gwei(100)));
+        assert!(!escalator.escalate(&mut tx));
+    }
+
+    #[test]
+    fn sets_legacy_fees() {
+        let uncapped = TransactionOverrides::default();
+        let capped = TransactionOverrides {
+            max_fee: Some(gwei(30)),
+            ..uncapped
+        };
+        for tx in [legacy_tx(), eip1559_tx()] {
+            let mut uncapped_tx = tx.clone();
+            uncapped.set_legacy_fees(&mut uncapped_tx, gwei(50));
+            assert!(matches!(uncapped_tx, TypedTransaction::Legacy(_)));
+            assert_eq!(uncapped_tx.gas_price(), Some(gwei(50)));
+            assert_eq!(uncapped_tx.gas(), Some(&U256::from(100_000)));
+            assert_eq!(uncapped_tx.nonce(), Some(&U256::from(7)));
+
+            let mut capped_tx = tx;
+            capped.set_legacy_fees(&mut capped_tx, gwei(50));
+            assert!(matches!(capped_tx, TypedTransaction::Legacy(_)));
+            assert_eq!(capped_tx.gas_price(), Some(gwei(30)));
+        }
+    }
+
+    #[test]
+    fn sets_eip1559_fees() {
+        let fees = |overrides: TransactionOverrides, max_fee: u64, priority_fee: u64| {
+            let mut tx = legacy_tx();
+            overrides.set_eip1559_fees(&mut tx, gwei(max_fee), gwei(priority_fee));
+            match tx {
+                TypedTransaction::Eip1559(inner) => {
+                    assert_eq!(inner.gas, Some(100_000.into()));
+                    assert_eq!(inner.nonce, Some(7.into()));
+                    (
+                        inner.max_fee_per_gas.unwrap(),
+                        inner.max_priority_fee_per_gas.unwrap(),
+                    )
+                }
+                tx => panic!("Expected an EIP-1559 transaction, got {tx:?}"),
+            }
+        };
+        let none = TransactionOverrides {
+            tx_type: TxType::Eip1559,
+            ..Default::default()
+        };
+        let max_fee = TransactionOverrides {
+            max_fee: Some(gwei(30)),
+            ..none
+        };
+        let priority_fee = TransactionOverrides {
+            priority_fee: Some(gwei(3)),
+            ..none
+        };
+        let max_priority_fee = TransactionOverrides {
+            max_priority_fee: Some(gwei(1)),
+            ..none
+        };
+        let all = TransactionOverrides {
+            max_fee: Some(gwei(30)),
+            priority_fee: Some(gwei(3)),
+            max_priority_fee: Some(gwei(1)),
+            ..none
+        };
+
+        assert_eq!(fees(none, 50, 2), (gwei(50), gwei(2)));
+        assert_eq!(fees(max_fee, 50, 2), (gwei(30), gwei(2)));
+        assert_eq!(fees(max_fee, 20, 2), (gwei(20), gwei(2)));
+        assert_eq!(fees(priority_fee, 50, 2), (gwei(50), gwei(3)));
+        assert_eq!(fees(max_priority_fee, 50, 2), (gwei(50), gwei(1)));
+        assert_eq!(fees(all, 50, 2), (gwei(30), gwei(1)));
+        // The priority fee never exceeds the max fee.
+        assert_eq!(fees(max_fee, 50, 40), (gwei(30), gwei(30)));
+    }
+}
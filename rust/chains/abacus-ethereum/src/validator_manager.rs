@@ -7,18 +7,39 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use ethers::prelude::*;
+use ethers_contract::builders::ContractCall;
 use eyre::Result;
 
 use abacus_core::{
     accumulator::merkle::Proof, AbacusAbi, AbacusMessage, ChainCommunicationError, ContractLocator,
-    Encode, InboxValidatorManager, MultisigSignedCheckpoint, TxOutcome,
+    Encode, InboxValidatorManager, MultisigSignedCheckpoint, ProcessCall, TxCostEstimate,
+    TxOutcome, PERMANENT_PROCESS_REVERTS, UNDECODED_REVERT_REASON,
 };
 
+use crate::contracts::inbox::INBOX_ABI;
 use crate::contracts::inbox_validator_manager::{
     InboxValidatorManager as EthereumInboxValidatorManagerInternal, INBOXVALIDATORMANAGER_ABI,
 };
+use crate::contracts::multicall::Multicall as MulticallInternal;
 use crate::trait_builder::MakeableWithProvider;
-use crate::tx::report_tx;
+use crate::tx::{report_tx, GasEscalator, TransactionOverrides};
+
+/// Gas added on top of the estimate when processing a message without a given
+/// gas limit, to account for estimation inaccuracies.
+const PROCESS_GAS_BUFFER: u64 = 100_000;
+
+/// Mark a revert of processing as permanent if retrying cannot change its
+/// reason.
+fn classify_process_revert(e: ChainCommunicationError) -> ChainCommunicationError {
+    match e {
+        ChainCommunicationError::Reverted(reason)
+            if PERMANENT_PROCESS_REVERTS.contains(&reason.as_str()) =>
+        {
+            ChainCommunicationError::PermanentRevert(reason)
+        }
+        e => e,
+    }
+}
 
 impl<M> Display for EthereumInboxValidatorManagerInternal<M>
 where
@@ -31,6 +52,8 @@ where
 
 pub struct InboxValidatorManagerBuilder {
     pub inbox_address: Address,
+    pub gas_escalator: Option<GasEscalator>,
+    pub transaction_overrides: TransactionOverrides,
 }
 
 impl MakeableWithProvider for InboxValidatorManagerBuilder {
@@ -45,10 +68,45 @@ impl MakeableWithProvider for InboxValidatorManagerBuilder {
             Arc::new(provider),
             locator,
             self.inbox_address,
+            self.gas_escalator,
+            self.transaction_overrides,
         ))
     }
 }
 
+/// Builds the InboxValidatorManagers of several inboxes on one chain, e.g. those
+/// receiving messages from different origins, on top of a single provider. As
+/// they share its signer, sharing the provider means their transactions draw
+/// nonces from the same nonce manager.
+pub struct SharedInboxValidatorManagersBuilder {
+    /// The location and builder of each InboxValidatorManager.
+    pub validator_managers: Vec<(ContractLocator, InboxValidatorManagerBuilder)>,
+}
+
+impl MakeableWithProvider for SharedInboxValidatorManagersBuilder {
+    type Output = Vec<Box<dyn InboxValidatorManager>>;
+
+    fn make_with_provider<M: Middleware + 'static>(
+        &self,
+        provider: M,
+        _locator: &ContractLocator,
+    ) -> Self::Output {
+        let provider = Arc::new(provider);
+        self.validator_managers
+            .iter()
+            .map(|(locator, builder)| {
+                Box::new(EthereumInboxValidatorManager::new(
+                    provider.clone(),
+                    locator,
+                    builder.inbox_address,
+                    builder.gas_escalator,
+                    builder.transaction_overrides,
+                )) as Box<dyn InboxValidatorManager>
+            })
+            .collect()
+    }
+}
+
 /// A struct that provides access to an Ethereum InboxValidatorManager contract
 #[derive(Debug)]
 pub struct EthereumInboxValidatorManager<M>
@@ -60,9 +118,10 @@ where
     domain: u32,
     #[allow(unused)]
     chain_name: String,
-    #[allow(unused)]
     provider: Arc<M>,
     inbox_address: Address,
+    gas_escalator: Option<GasEscalator>,
+    transaction_overrides: TransactionOverrides,
 }
 
 impl<M> EthereumInboxValidatorManager<M>
@@ -71,7 +130,13 @@ where
 {
     /// Create a reference to a inbox at a specific Ethereum address on some
     /// chain
-    pub fn new(provider: Arc<M>, locator: &ContractLocator, inbox_address: Address) -> Self {
+    pub fn new(
+        provider: Arc<M>,
+        locator: &ContractLocator,
+        inbox_address: Address,
+        gas_escalator: Option<GasEscalator>,
+        transaction_overrides: TransactionOverrides,
+    ) -> Self {
         Self {
             contract: Arc::new(EthereumInboxValidatorManagerInternal::new(
                 &locator.address,
@@ -81,29 +146,58 @@ where
             chain_name: locator.chain_name.to_owned(),
             provider,
             inbox_address,
+            gas_escalator,
+            transaction_overrides,
         }
     }
-}
 
-#[async_trait]
-impl<M> InboxValidatorManager for EthereumInboxValidatorManager<M>
-where
-    M: Middleware + 'static,
-{
-    #[tracing::instrument(skip(self))]
-    async fn process(
+    /// The account that submits process transactions.
+    fn submitter(&self) -> Result<Address, ChainCommunicationError> {
+        self.provider.default_sender().ok_or_else(|| {
+            ChainCommunicationError::CustomError("No signer to submit transactions with".into())
+        })
+    }
+
+    /// Sign and broadcast a transaction whose nonce, gas and fees are already
+    /// set. It is sent as a raw transaction, bypassing the provider's nonce
+    /// manager, so that the caller's nonce is never replaced, e.g. when the
+    /// nonce manager resyncs after a failed send.
+    async fn send_raw(&self, mut tx: TypedTransaction) -> Result<H256, ChainCommunicationError> {
+        let submitter = self.submitter()?;
+        let chain_id = self
+            .provider
+            .get_chainid()
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+        tx.set_from(submitter);
+        tx.set_chain_id(chain_id.as_u64());
+        let signature = self
+            .provider
+            .sign_transaction(&tx, submitter)
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+        let pending = self
+            .provider
+            .send_raw_transaction(tx.rlp_signed(&signature))
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+        Ok(*pending)
+    }
+
+    /// Returns a ContractCall that processes the provided message.
+    fn process_contract_call(
         &self,
         multisig_signed_checkpoint: &MultisigSignedCheckpoint,
         message: &AbacusMessage,
         proof: &Proof,
-    ) -> Result<TxOutcome, ChainCommunicationError> {
+    ) -> ContractCall<M, ()> {
         let mut sol_proof: [[u8; 32]; 32] = Default::default();
         sol_proof
             .iter_mut()
             .enumerate()
             .for_each(|(i, elem)| *elem = proof.path[i].to_fixed_bytes());
 
-        let tx = self.contract.process(
+        self.contract.process(
             self.inbox_address,
             multisig_signed_checkpoint.checkpoint.root.to_fixed_bytes(),
             multisig_signed_checkpoint.checkpoint.index.into(),
@@ -115,12 +209,215 @@ where
             message.to_vec().into(),
             sol_proof,
             proof.index.into(),
+        )
+    }
+
+    /// Returns a ContractCall that processes the provided messages through the
+    /// Multicall3 contract at `multicall_address`, allowing each to fail.
+    fn batch_process_contract_call(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+    ) -> ContractCall<M, Vec<(bool, Bytes)>> {
+        let calls = calls
+            .iter()
+            .map(|call| {
+                let calldata = self
+                    .process_contract_call(
+                        call.multisig_signed_checkpoint,
+                        call.message,
+                        call.proof,
+                    )
+                    .calldata()
+                    .expect("process call has calldata");
+                (self.contract.address(), true, calldata)
+            })
+            .collect();
+        MulticallInternal::new(multicall_address, self.provider.clone()).aggregate_3(calls)
+    }
+
+    /// Simulate a process call with eth_call, so that a call that would revert
+    /// fails with the decoded reason instead of being sent.
+    async fn simulate_process(
+        &self,
+        tx: &ContractCall<M, ()>,
+    ) -> Result<(), ChainCommunicationError>
+    where
+        M: 'static,
+    {
+        let e = match tx.call().await {
+            Ok(()) => return Ok(()),
+            Err(ContractError::Revert(data)) => ChainCommunicationError::Reverted(
+                super::decode_revert_data(&[&INBOXVALIDATORMANAGER_ABI, &INBOX_ABI], &data)
+                    .unwrap_or_else(|| {
+                        format!("{UNDECODED_REVERT_REASON} 0x{}", hex::encode(&data))
+                    }),
+            ),
+            Err(e) => e.into(),
+        };
+        Err(classify_process_revert(e))
+    }
+
+    /// The estimated gas used by a process call, adjusted by the transaction
+    /// overrides. Callers add any margin for estimation inaccuracies.
+    async fn process_gas_estimate(
+        &self,
+        tx: &ContractCall<M, ()>,
+    ) -> Result<U256, ChainCommunicationError>
+    where
+        M: 'static,
+    {
+        match tx.estimate_gas().await {
+            Ok(estimate) => Ok(self.transaction_overrides.gas_limit(estimate)),
+            // Estimation errors rarely include the revert data, so simulate the
+            // call to decode the reason if it reverts.
+            Err(e) => {
+                self.simulate_process(tx).await?;
+                Err(classify_process_revert(e.into()))
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<M> InboxValidatorManager for EthereumInboxValidatorManager<M>
+where
+    M: Middleware + 'static,
+{
+    fn contract_address(&self) -> Address {
+        self.contract.address()
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn process(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+        gas_limit: Option<U256>,
+    ) -> Result<TxOutcome, ChainCommunicationError> {
+        let tx = self.process_contract_call(multisig_signed_checkpoint, message, proof);
+        self.simulate_process(&tx).await?;
+        let gas = match gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => self
+                .process_gas_estimate(&tx)
+                .await?
+                .saturating_add(PROCESS_GAS_BUFFER.into()),
+        };
+        let mut gassed = tx.gas(gas);
+        self.transaction_overrides
+            .apply_fees(&mut gassed.tx, &self.provider, None)
+            .await?;
+        let receipt = report_tx(gassed, &self.provider, self.gas_escalator.as_ref()).await?;
+        Ok(receipt.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn process_estimate_costs(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Result<TxCostEstimate, ChainCommunicationError> {
+        let tx = self.process_contract_call(multisig_signed_checkpoint, message, proof);
+        let gas_estimate = self.process_gas_estimate(&tx).await?;
+        let gas_price = self.transaction_overrides.cap_gas_price(
+            self.provider
+                .get_gas_price()
+                .await
+                .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?,
         );
-        let gas = tx.estimate_gas().await?.saturating_add(U256::from(100000));
-        let gassed = tx.gas(gas);
-        let receipt = report_tx(gassed).await?;
+
+        Ok(TxCostEstimate {
+            gas_limit: gas_estimate.saturating_add(PROCESS_GAS_BUFFER.into()),
+            gas_estimate,
+            gas_price,
+        })
+    }
+
+    fn process_calldata(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+    ) -> Vec<u8> {
+        self.process_contract_call(multisig_signed_checkpoint, message, proof)
+            .calldata()
+            .expect("process call has calldata")
+            .to_vec()
+    }
+
+    #[tracing::instrument(skip(self, calls), fields(batch_size = calls.len()))]
+    async fn simulate_batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+    ) -> Result<Vec<bool>, ChainCommunicationError> {
+        let results = self
+            .batch_process_contract_call(multicall_address, calls)
+            .call()
+            .await?;
+        Ok(results.into_iter().map(|(success, _)| success).collect())
+    }
+
+    #[tracing::instrument(skip(self, calls), fields(batch_size = calls.len()))]
+    async fn batch_process(
+        &self,
+        multicall_address: Address,
+        calls: &[ProcessCall<'_>],
+        gas_limit: U256,
+    ) -> Result<TxOutcome, ChainCommunicationError> {
+        let mut tx = self
+            .batch_process_contract_call(multicall_address, calls)
+            .gas(gas_limit);
+        self.transaction_overrides
+            .apply_fees(&mut tx.tx, &self.provider, None)
+            .await?;
+        let receipt = report_tx(tx, &self.provider, self.gas_escalator.as_ref()).await?;
         Ok(receipt.into())
     }
+
+    async fn submitter_nonce(&self) -> Result<U256, ChainCommunicationError> {
+        self.provider
+            .get_transaction_count(self.submitter()?, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))
+    }
+
+    async fn submitter_balance(&self) -> Result<U256, ChainCommunicationError> {
+        self.provider
+            .get_balance(self.submitter()?, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn send_process(
+        &self,
+        multisig_signed_checkpoint: &MultisigSignedCheckpoint,
+        message: &AbacusMessage,
+        proof: &Proof,
+        nonce: U256,
+        tx_cost: &TxCostEstimate,
+    ) -> Result<H256, ChainCommunicationError> {
+        let mut tx = self.process_contract_call(multisig_signed_checkpoint, message, proof);
+        tx.tx.set_nonce(nonce);
+        tx.tx.set_gas(tx_cost.gas_limit);
+        self.transaction_overrides
+            .apply_fees(&mut tx.tx, &self.provider, Some(tx_cost.gas_price))
+            .await?;
+        self.send_raw(tx.tx).await
+    }
+
+    async fn tx_outcome(&self, txid: H256) -> Result<Option<TxOutcome>, ChainCommunicationError> {
+        let receipt = self
+            .provider
+            .get_transaction_receipt(txid)
+            .await
+            .map_err(|e| ChainCommunicationError::CustomError(Box::new(e)))?;
+        Ok(receipt.map(Into::into))
+    }
 }
 
 pub struct EthereumInboxValidatorManagerAbi;